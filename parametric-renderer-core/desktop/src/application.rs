@@ -1,53 +1,404 @@
-use glam::Vec3;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use clap::Args;
+use glam::{UVec2, Vec3};
+use log::{error, info, warn};
 use renderer_core::{
     application::{AppCommand, Application, WasmCanvas},
-    camera::camera_controller::{self, CameraController, IsCameraController},
-    game::{MaterialInfo, ModelInfo, ShaderId, ShaderInfo},
-    input::WinitAppHelper,
+    camera::{
+        camera_controller::{
+            self, CameraController, GeneralControllerSettings, IsCameraController,
+        },
+        Angle, CameraBookmark, CameraSettings,
+    },
+    game::{Background, GameRes, MaterialInfo, ModelInfo, ShaderId, ShaderInfo, TextureId},
+    geometry_image::GeometryImage,
+    input::{Action, ActionMap, InputRecording, WinitAppHelper},
+    renderer::GpuSettings,
     transform::Transform,
 };
 use winit::event_loop::EventLoop;
 
-use crate::config::{CacheFile, CachedCamera, CachedChosenController};
+use crate::{
+    config::{
+        CacheFile, CachedBackground, CachedBookmark, CachedCamera, CachedCameraSettings,
+        CachedChosenController, ControlsFile, LoadConfigError,
+    },
+    scene::SceneFile,
+};
 
-const CACHE_FILE: &'static str = "cache.json";
+pub const CACHE_FILE: &'static str = "cache.json";
+const CONTROLS_FILE: &'static str = "controls.json";
+/// Where the default scene gets saved to
+const DEFAULT_SCENE_FILE: &'static str = "scene.json";
 const HEART_SPHERE_SHADER_CODE: &'static str = include_str!("../../shaders/HeartSphere.wgsl");
 
 fn save_cache(mut cache_file: CacheFile) -> impl FnOnce(&mut Application) {
     move |app: &mut Application| {
-        let controller = app.app.camera_controller.general_controller();
-        cache_file.camera = Some(CachedCamera {
-            position: controller.position.to_array(),
-            orientation: controller.orientation.to_array(),
-            distance_to_center: controller.distance_to_center,
-            chosen: match app.app.camera_controller.get_chosen_kind() {
-                camera_controller::ChosenKind::Orbitcam => CachedChosenController::Orbitcam,
-                camera_controller::ChosenKind::Freecam => CachedChosenController::Freecam,
-            },
+        cache_file.camera = Some(cached_camera(
+            &app.app.camera_controller.general_controller(),
+            app.app.camera_controller.get_chosen_kind(),
+        ));
+        let (camera, controller) = (app.app.camera_settings(), app.app.controller_settings());
+        cache_file.camera_settings = Some(CachedCameraSettings {
+            fov: camera.fov.to_degrees(),
+            z_near: camera.z_near,
+            z_far: camera.z_far,
+            fly_speed: controller.fly_speed,
+            pan_speed: controller.pan_speed,
+            rotation_sensitivity: controller.rotation_sensitivity,
         });
+        cache_file.bookmarks = app
+            .app
+            .bookmarks
+            .iter()
+            .map(|bookmark| CachedBookmark {
+                name: bookmark.name.clone(),
+                camera: cached_camera(&bookmark.camera, bookmark.kind),
+                fov: bookmark.fov.to_degrees(),
+                slot: bookmark.slot,
+            })
+            .collect();
         cache_file.save_to_file(CACHE_FILE).unwrap();
     }
 }
 
-pub fn run() -> anyhow::Result<()> {
+fn cached_camera(
+    controller: &camera_controller::GeneralController,
+    kind: camera_controller::ChosenKind,
+) -> CachedCamera {
+    CachedCamera {
+        position: controller.position.to_array(),
+        orientation: controller.orientation.to_array(),
+        distance_to_center: controller.distance_to_center,
+        chosen: match kind {
+            camera_controller::ChosenKind::Orbitcam => CachedChosenController::Orbitcam,
+            camera_controller::ChosenKind::Freecam => CachedChosenController::Freecam,
+        },
+    }
+}
+
+fn from_cached_camera(
+    camera: &CachedCamera,
+) -> (
+    camera_controller::GeneralController,
+    camera_controller::ChosenKind,
+) {
+    let controller = camera_controller::GeneralController {
+        position: Vec3::from(camera.position),
+        orientation: glam::Quat::from_array(camera.orientation),
+        distance_to_center: camera.distance_to_center,
+    };
+    let kind = match camera.chosen {
+        CachedChosenController::Orbitcam => camera_controller::ChosenKind::Orbitcam,
+        CachedChosenController::Freecam => camera_controller::ChosenKind::Freecam,
+    };
+    (controller, kind)
+}
+
+/// Saves the cache, and then the recorded inputs.
+fn save_recording(
+    path: PathBuf,
+    save_cache: impl FnOnce(&mut Application) + 'static,
+) -> impl FnOnce(&mut Application) {
+    move |app: &mut Application| {
+        save_cache(app);
+        if let Some(recording) = app.stop_recording() {
+            match recording.save_to_file(&path) {
+                Ok(()) => info!(
+                    "Recorded {} frames to {}",
+                    recording.frames.len(),
+                    path.display()
+                ),
+                Err(e) => error!("Failed to write {}: {e}", path.display()),
+            }
+        }
+    }
+}
+
+/// Reads the scene, its shaders and its textures again, and keeps the camera and the background.
+fn reload_scene(app: &mut Application, path: &Path) -> anyhow::Result<()> {
+    let scene = SceneFile::from_file(path).map_err(|e| anyhow::anyhow!("{e}"))?;
+    for (shader_id, shader_info) in scene.load_shaders(scene_directory(path))? {
+        app.set_shader(shader_id, shader_info);
+    }
+    for (texture_id, texture_info) in scene.load_textures(scene_directory(path))? {
+        app.set_texture(texture_id, texture_info);
+    }
+    if let Some(lighting) = &scene.lighting {
+        lighting.apply_to(&mut app.app);
+    }
+    let mut models = scene.models();
+    models.extend(
+        app.app
+            .models
+            .iter()
+            .filter(|model| model.shader_id == GeometryImage::shader_id())
+            .cloned(),
+    );
+    app.app.update_models(models);
+    info!("Reloaded {}", path.display());
+    Ok(())
+}
+
+/// Also writes the shaders that are not files yet, like the built-in default shader.
+fn save_scene(app: &Application, path: &Path) -> anyhow::Result<()> {
+    let previous = SceneFile::from_file(path).ok();
+    let camera = cached_camera(
+        &app.app.camera_controller.general_controller(),
+        app.app.camera_controller.get_chosen_kind(),
+    );
+    let scene = SceneFile::from_game(&app.app, previous.as_ref(), camera);
+    for model in &scene.models {
+        let shader_path = scene_directory(path).join(&model.parametric_shader);
+        let shader_id = ShaderId(model.parametric_shader.clone());
+        if shader_path.exists() {
+            continue;
+        }
+        if let Some(shader) = app.app.shaders.get(&shader_id) {
+            std::fs::write(&shader_path, &shader.code)?;
+        }
+    }
+    scene.save_to_file(path)?;
+    info!("Saved {}", path.display());
+    Ok(())
+}
+
+fn scene_directory(path: &Path) -> &Path {
+    path.parent().unwrap_or(Path::new(""))
+}
+
+fn on_scene_action(scene_path: PathBuf) -> impl Fn(&mut Application, Action) {
+    move |app: &mut Application, action| {
+        let (verb, result) = match action {
+            Action::ReloadScene => ("reload", reload_scene(app, &scene_path)),
+            Action::SaveScene => ("save", save_scene(app, &scene_path)),
+            _ => return,
+        };
+        if let Err(e) = result {
+            error!("Failed to {verb} {}: {e}", scene_path.display());
+        }
+    }
+}
+
+/// What to open, from the command line.
+#[derive(Args, Debug, Clone, Default)]
+pub struct OpenFiles {
+    /// A `scene.json`. Saving writes to it, or to `scene.json` when there is none.
+    #[arg(long, global = true)]
+    pub scene: Option<PathBuf>,
+    /// A PNG that mesh2gim generated
+    #[arg(long, global = true)]
+    pub geometry_image: Option<String>,
+}
+
+/// Where the inputs come from.
+pub enum InputMode {
+    Live,
+    /// Writes every frame of input to a file when the app exits
+    Record(PathBuf),
+    /// Uses the inputs from a recording, and then continues live
+    Replay {
+        path: PathBuf,
+        live_probes: bool,
+    },
+}
+
+/// How to create the window and the renderer.
+pub struct WindowSettings {
+    pub gpu: GpuSettings,
+    pub size: Option<UVec2>,
+    pub lod_threshold: Option<f32>,
+    /// Measures the time of every GPU pass
+    pub gpu_profiling: bool,
+}
+
+pub fn run(
+    files: OpenFiles,
+    input_mode: InputMode,
+    window_settings: WindowSettings,
+) -> anyhow::Result<()> {
     let event_loop = EventLoop::<AppCommand>::with_user_event().build()?;
     event_loop.set_control_flow(winit::event_loop::ControlFlow::Poll);
     let event_loop_proxy = event_loop.create_proxy();
     let cache_file = CacheFile::from_file(CACHE_FILE).unwrap_or_default();
-    let cached_camera = cache_file.camera.clone();
-    let mut application =
-        Application::new(event_loop_proxy, save_cache(cache_file), WasmCanvas::new());
+    let on_exit: Box<dyn FnOnce(&mut Application)> = match &input_mode {
+        InputMode::Record(path) => {
+            Box::new(save_recording(path.clone(), save_cache(cache_file.clone())))
+        }
+        _ => Box::new(save_cache(cache_file.clone())),
+    };
+    let mut application = Application::new(event_loop_proxy, on_exit, WasmCanvas::new());
+
+    application.gpu_settings = window_settings.gpu;
+    application.window_size = window_settings.size;
+    application.initial_threshold_factor = window_settings.lod_threshold;
+    application.app.profiler_settings.gpu = window_settings.gpu_profiling;
+    application.app.actions = load_controls();
+    create_scene(&mut application.app, &files, &cache_file)?;
+    let scene_path = files
+        .scene
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_SCENE_FILE));
+    application.on_action = Some(Arc::new(on_scene_action(scene_path)));
+    match input_mode {
+        InputMode::Live => {}
+        InputMode::Record(_) => application.start_recording(),
+        InputMode::Replay { path, live_probes } => {
+            let recording = InputRecording::from_file(&path)
+                .map_err(|e| anyhow::anyhow!("Failed to load {}: {e}", path.display()))?;
+            application.start_replay(recording, live_probes);
+        }
+    }
+
+    event_loop.run_app(&mut WinitAppHelper::new(application))?;
+    Ok(())
+}
 
-    application.app.profiler_settings.gpu = true;
+/// Writes the default controls when there is no controls file yet, so that they can be edited.
+fn load_controls() -> ActionMap {
+    let mut actions = ActionMap::default();
+    match ControlsFile::from_file(CONTROLS_FILE) {
+        Ok(controls_file) => {
+            if let Err(e) = controls_file.apply_to(&mut actions) {
+                warn!("Invalid {CONTROLS_FILE}, using the default controls: {e}");
+            }
+        }
+        Err(LoadConfigError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            if let Err(e) = ControlsFile::from_action_map(&actions).save_to_file(CONTROLS_FILE) {
+                warn!("Failed to write {CONTROLS_FILE}: {e}");
+            }
+        }
+        Err(e) => warn!("Failed to load {CONTROLS_FILE}, using the default controls: {e}"),
+    }
+    actions
+}
+
+/// The scene file, or the default scene. The camera and the background of the scene file win over the ones from the cache file.
+pub fn create_scene(
+    app: &mut GameRes,
+    files: &OpenFiles,
+    cache_file: &CacheFile,
+) -> anyhow::Result<()> {
+    let scene = match &files.scene {
+        Some(path) => {
+            let scene = SceneFile::from_file(path)
+                .map_err(|e| anyhow::anyhow!("Failed to load {}: {e}", path.display()))?;
+            for (shader_id, shader_info) in scene.load_shaders(scene_directory(path))? {
+                app.set_shader(shader_id, shader_info);
+            }
+            for (texture_id, texture_info) in scene.load_textures(scene_directory(path))? {
+                app.set_texture(texture_id, texture_info);
+            }
+            if let Some(lighting) = &scene.lighting {
+                lighting.apply_to(app);
+            }
+            Some(scene)
+        }
+        None => None,
+    };
+    let mut models = match &scene {
+        Some(scene) => scene.models(),
+        None => default_models(app),
+    };
+
+    // Optionally render a geometry image that mesh2gim generated
+    if let Some(geometry_image_path) = files.geometry_image.clone() {
+        let geometry_image = GeometryImage::load(&geometry_image_path)?;
+        let texture_id = TextureId(geometry_image_path.clone());
+        app.set_shader(GeometryImage::shader_id(), GeometryImage::shader_info());
+        models.push(geometry_image.model_info(
+            geometry_image_path,
+            texture_id.clone(),
+            MaterialInfo {
+                color: Vec3::new(0.9, 0.9, 0.9),
+                emissive: Vec3::new(0.0, 0.0, 0.0),
+                roughness: 0.7,
+                metallic: 0.1,
+                opacity: 1.0,
+                albedo_texture: None,
+                roughness_texture: None,
+                normal_texture: None,
+            },
+        ));
+        app.set_texture(texture_id, geometry_image.texture);
+    }
+    app.update_models(models);
+
+    let scene_background = scene.as_ref().and_then(|scene| scene.background.as_ref());
+    if let Some(CachedBackground {
+        top,
+        bottom,
+        skybox,
+    }) = scene_background.or(cache_file.background.as_ref()).cloned()
+    {
+        let skybox = skybox.and_then(|skybox| match app.load_texture(&skybox) {
+            Ok(texture_id) => Some(texture_id),
+            Err(e) => {
+                warn!("Failed to load the skybox {skybox}, using the background colors: {e}");
+                None
+            }
+        });
+        app.background = match (skybox, bottom) {
+            (Some(skybox), _) => Background::Skybox(skybox),
+            (None, Some(bottom)) => Background::Gradient {
+                top: Vec3::from(top),
+                bottom: Vec3::from(bottom),
+            },
+            (None, None) => Background::Solid(Vec3::from(top)),
+        };
+    }
+
+    if let Some(settings) = &cache_file.camera_settings {
+        app.set_camera_settings(CameraSettings {
+            fov: Angle::from_degrees(settings.fov),
+            z_near: settings.z_near,
+            z_far: settings.z_far,
+            ..app.camera_settings().clone()
+        });
+        app.set_controller_settings(GeneralControllerSettings {
+            fly_speed: settings.fly_speed,
+            pan_speed: settings.pan_speed,
+            rotation_sensitivity: settings.rotation_sensitivity,
+            ..app.controller_settings().clone()
+        });
+    }
+    let scene_camera = scene.as_ref().and_then(|scene| scene.camera.as_ref());
+    if let Some(camera) = scene_camera.or(cache_file.camera.as_ref()) {
+        let (controller, kind) = from_cached_camera(camera);
+        app.camera_controller =
+            CameraController::new(controller, app.camera_controller.settings.clone(), kind);
+    }
+    app.bookmarks = cache_file
+        .bookmarks
+        .iter()
+        .map(|bookmark| {
+            let (camera, kind) = from_cached_camera(&bookmark.camera);
+            CameraBookmark {
+                name: bookmark.name.clone(),
+                camera,
+                kind,
+                fov: Angle::from_degrees(bookmark.fov),
+                slot: bookmark.slot,
+            }
+        })
+        .collect();
+    Ok(())
+}
+
+/// The HeartSphere demo, for when no scene file was opened.
+fn default_models(app: &mut GameRes) -> Vec<ModelInfo> {
     let shader_id = ShaderId("HeartSphere.wgsl".into());
-    application.app.set_shader(
+    app.set_shader(
         shader_id.clone(),
         ShaderInfo {
             label: "HeartSphere".into(),
             code: HEART_SPHERE_SHADER_CODE.into(),
         },
     );
-    application.app.update_models(vec![ModelInfo {
+    vec![ModelInfo {
         id: "0659dcb1-6229-46bd-a306-6ceebfcf2e46".into(),
         transform: Transform {
             position: Vec3::new(0.0, 0.0, 0.0),
@@ -58,32 +409,14 @@ pub fn run() -> anyhow::Result<()> {
             emissive: Vec3::new(0.0, 0.0, 0.0),
             roughness: 0.7,
             metallic: 0.1,
+            opacity: 1.0,
+            albedo_texture: None,
+            roughness_texture: None,
+            normal_texture: None,
         },
         shader_id,
         instance_count: 5,
-    }]);
-
-    if let Some(CachedCamera {
-        position,
-        orientation,
-        distance_to_center,
-        chosen,
-    }) = cached_camera
-    {
-        application.app.camera_controller = CameraController::new(
-            camera_controller::GeneralController {
-                position: Vec3::from(position),
-                orientation: glam::Quat::from_array(orientation),
-                distance_to_center,
-            },
-            application.app.camera_controller.settings,
-            match chosen {
-                CachedChosenController::Orbitcam => camera_controller::ChosenKind::Orbitcam,
-                CachedChosenController::Freecam => camera_controller::ChosenKind::Freecam,
-            },
-        );
-    }
-
-    event_loop.run_app(&mut WinitAppHelper::new(application))?;
-    Ok(())
+        parameters: Default::default(),
+        texture_id: None,
+    }]
 }
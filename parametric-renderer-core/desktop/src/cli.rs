@@ -0,0 +1,145 @@
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use glam::UVec2;
+use renderer_core::renderer::GpuSettings;
+
+use crate::{application::OpenFiles, render_sequence::RenderSequenceArgs};
+
+/// The desktop version of the Math2Model renderer.
+#[derive(Parser, Debug)]
+#[command(version)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    #[command(flatten)]
+    pub files: OpenFiles,
+    #[command(flatten)]
+    pub gpu: GpuArgs,
+    /// In physical pixels, like 1280x720
+    #[arg(long, global = true, value_parser = parse_size)]
+    pub window_size: Option<UVec2>,
+    /// Starting threshold factor of the LOD algorithm. Higher values split the surfaces into fewer triangles.
+    #[arg(long, global = true)]
+    pub lod_threshold: Option<f32>,
+    /// Skips the GPU timer queries, which some drivers do not support. `P` then only measures the CPU.
+    #[arg(long, global = true)]
+    pub no_gpu_profiling: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Renders a PNG sequence without opening a window
+    #[command(alias = "headless")]
+    Render(RenderSequenceArgs),
+    /// Saves the inputs of every frame to a file when the app exits
+    Record { file: PathBuf },
+    /// Replays the inputs from a file, and then continues with the live inputs
+    Replay {
+        file: PathBuf,
+        /// Uses the points under the cursor that the renderer finds now, instead of the recorded ones
+        #[arg(long)]
+        live_probes: bool,
+    },
+}
+
+/// For testing on different machines.
+#[derive(Args, Debug)]
+pub struct GpuArgs {
+    #[arg(long, global = true, value_enum, default_value_t = BackendArg::Primary)]
+    pub backend: BackendArg,
+    /// Uses the first adapter whose name contains this, like `llvmpipe` or `NVIDIA`
+    #[arg(long, global = true, conflicts_with_all = ["low_power", "software"])]
+    pub adapter: Option<String>,
+    /// Prefers the integrated GPU
+    #[arg(long, global = true)]
+    pub low_power: bool,
+    /// Only uses a software adapter
+    #[arg(long, global = true)]
+    pub software: bool,
+    #[arg(long, global = true, value_enum, default_value_t = PresentModeArg::AutoVsync)]
+    pub present_mode: PresentModeArg,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum BackendArg {
+    /// Vulkan on Linux and Windows. Metal and DX12 are not compiled in.
+    Primary,
+    Vulkan,
+    /// OpenGL or OpenGL ES
+    Gl,
+    All,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+pub enum PresentModeArg {
+    AutoVsync,
+    AutoNoVsync,
+    Fifo,
+    Mailbox,
+    Immediate,
+}
+
+impl GpuArgs {
+    pub fn gpu_settings(&self) -> GpuSettings {
+        GpuSettings {
+            backends: match self.backend {
+                BackendArg::Primary => wgpu::Backends::PRIMARY,
+                BackendArg::Vulkan => wgpu::Backends::VULKAN,
+                BackendArg::Gl => wgpu::Backends::GL,
+                BackendArg::All => wgpu::Backends::all(),
+            },
+            power_preference: if self.low_power {
+                wgpu::PowerPreference::LowPower
+            } else {
+                wgpu::PowerPreference::HighPerformance
+            },
+            adapter_name: self.adapter.clone(),
+            force_fallback_adapter: self.software,
+            present_mode: match self.present_mode {
+                PresentModeArg::AutoVsync => wgpu::PresentMode::AutoVsync,
+                PresentModeArg::AutoNoVsync => wgpu::PresentMode::AutoNoVsync,
+                PresentModeArg::Fifo => wgpu::PresentMode::Fifo,
+                PresentModeArg::Mailbox => wgpu::PresentMode::Mailbox,
+                PresentModeArg::Immediate => wgpu::PresentMode::Immediate,
+            },
+        }
+    }
+}
+
+/// Like `1920x1080`
+pub fn parse_size(value: &str) -> Result<UVec2, String> {
+    let (width, height) = value
+        .split_once('x')
+        .ok_or_else(|| format!("Expected a size like 1920x1080, got {value}"))?;
+    let parse = |v: &str| v.parse::<u32>().map_err(|e| format!("{v}: {e}"));
+    Ok(UVec2::new(parse(width)?, parse(height)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_window_size() {
+        assert_eq!(parse_size("1280x720"), Ok(UVec2::new(1280, 720)));
+        assert!(parse_size("1280").is_err());
+        assert!(parse_size("1280x").is_err());
+        assert!(parse_size("-1x720").is_err());
+        assert!(parse_size("1280 x 720").is_err());
+    }
+
+    #[test]
+    fn adapter_conflicts_with_the_power_preference() {
+        assert!(Cli::try_parse_from(["desktop", "--adapter", "llvmpipe"]).is_ok());
+        assert!(Cli::try_parse_from(["desktop", "--adapter", "llvmpipe", "--software"]).is_err());
+        assert!(Cli::try_parse_from(["desktop", "--adapter", "llvmpipe", "--low-power"]).is_err());
+    }
+
+    #[test]
+    fn gpu_profiling_is_on_by_default() {
+        assert!(!Cli::try_parse_from(["desktop"]).unwrap().no_gpu_profiling);
+        let cli = Cli::try_parse_from(["desktop", "replay", "inputs.json", "--no-gpu-profiling"]);
+        assert!(cli.unwrap().no_gpu_profiling);
+    }
+}
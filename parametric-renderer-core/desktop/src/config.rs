@@ -1,10 +1,15 @@
 use core::fmt;
 use nanoserde::{DeJson, DeJsonErr, SerJson};
+use renderer_core::input::{Action, ActionMap, Binding};
 use std::path::Path;
 
 #[derive(DeJson, SerJson, Debug, Clone)]
 pub struct CacheFile {
     pub camera: Option<CachedCamera>,
+    pub background: Option<CachedBackground>,
+    pub camera_settings: Option<CachedCameraSettings>,
+    #[nserde(default)]
+    pub bookmarks: Vec<CachedBookmark>,
 }
 
 #[derive(DeJson, SerJson, Debug, Clone)]
@@ -14,15 +19,148 @@ pub struct CachedCamera {
     pub distance_to_center: f32,
     pub chosen: CachedChosenController,
 }
+#[derive(DeJson, SerJson, Debug, Clone)]
+pub struct CachedCameraSettings {
+    /// Vertical, in degrees
+    pub fov: f32,
+    pub z_near: f32,
+    /// Infinitely far when missing
+    pub z_far: Option<f32>,
+    pub fly_speed: f32,
+    pub pan_speed: f32,
+    pub rotation_sensitivity: f32,
+}
+
+/// A named viewpoint, which a number key can fly to.
+#[derive(DeJson, SerJson, Debug, Clone)]
+pub struct CachedBookmark {
+    pub name: String,
+    pub camera: CachedCamera,
+    /// In degrees
+    pub fov: f32,
+    /// The number key, from 1 to 9
+    pub slot: Option<u8>,
+}
+
 #[derive(DeJson, SerJson, Debug, Clone)]
 pub enum CachedChosenController {
     Orbitcam,
     Freecam,
 }
 
+/// A solid color, a vertical gradient when there is a bottom color, or a skybox image.
+/// Colors are in linear sRGB.
+#[derive(DeJson, SerJson, Debug, Clone)]
+pub struct CachedBackground {
+    pub top: [f32; 3],
+    pub bottom: Option<[f32; 3]>,
+    /// Path to an equirectangular HDR image
+    pub skybox: Option<String>,
+}
+
+/// A camera path for offline rendering. Angles are in degrees.
+#[derive(DeJson, Debug, Clone)]
+pub struct CameraPathFile {
+    pub keyframes: Vec<CameraPathKeyframe>,
+    /// Starts and stops smoothly, instead of moving at a constant speed
+    pub ease_in_out: bool,
+    pub looping: bool,
+}
+
+#[derive(DeJson, Debug, Clone)]
+pub struct CameraPathKeyframe {
+    /// In seconds since the start of the path
+    pub time: f32,
+    pub center: [f32; 3],
+    pub yaw: f32,
+    pub pitch: f32,
+    pub roll: Option<f32>,
+    pub distance: f32,
+}
+
+impl CameraPathFile {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, LoadConfigError> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(DeJson::deserialize_json(&content)?)
+    }
+}
+
+/// Which keys and mouse buttons trigger which actions.
+/// Actions that are not in the file keep their default bindings.
+#[derive(DeJson, SerJson, Debug, Clone)]
+pub struct ControlsFile {
+    pub actions: Vec<ControlsAction>,
+}
+
+#[derive(DeJson, SerJson, Debug, Clone)]
+pub struct ControlsAction {
+    /// Like `MoveForward`
+    pub action: String,
+    /// Like `KeyW` or `Shift+MouseMiddle`
+    pub bindings: Vec<String>,
+}
+
+impl ControlsFile {
+    pub fn from_action_map(actions: &ActionMap) -> Self {
+        Self {
+            actions: Action::ALL
+                .into_iter()
+                .map(|action| ControlsAction {
+                    action: action.name(),
+                    bindings: actions
+                        .bindings(action)
+                        .iter()
+                        .map(|binding| binding.to_string())
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Leaves the action map unchanged if any action or binding is invalid.
+    pub fn apply_to(&self, actions: &mut ActionMap) -> anyhow::Result<()> {
+        let parsed = self
+            .actions
+            .iter()
+            .map(|entry| {
+                let action = Action::from_name(&entry.action)
+                    .ok_or_else(|| anyhow::anyhow!("Unknown action {}", entry.action))?;
+                let bindings = entry
+                    .bindings
+                    .iter()
+                    .map(|binding| {
+                        Binding::parse(binding)
+                            .ok_or_else(|| anyhow::anyhow!("Unknown binding {binding}"))
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                Ok((action, bindings))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        for (action, bindings) in parsed {
+            actions.set_bindings(action, bindings);
+        }
+        Ok(())
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, LoadConfigError> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(DeJson::deserialize_json(&content)?)
+    }
+
+    pub fn save_to_file(&self, path: impl AsRef<Path>) -> Result<(), std::io::Error> {
+        let content = SerJson::serialize_json(self);
+        std::fs::write(path, content)
+    }
+}
+
 impl CacheFile {
     pub fn new() -> Self {
-        Self { camera: None }
+        Self {
+            camera: None,
+            background: None,
+            camera_settings: None,
+            bookmarks: Vec::new(),
+        }
     }
 
     pub fn from_file(path: impl AsRef<Path>) -> Result<Self, LoadConfigError> {
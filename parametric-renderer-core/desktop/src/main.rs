@@ -1,10 +1,37 @@
 mod application;
+mod cli;
 mod config;
+mod render_sequence;
+mod scene;
 
-use application::run;
+use application::{run, InputMode, WindowSettings};
+use clap::Parser;
+use cli::{Cli, Command};
 
 fn main() -> anyhow::Result<()> {
     env_logger::init();
     any_spawner::Executor::init_futures_executor().expect("Futures executor failed to init");
-    run()
+    let cli = Cli::parse();
+    let gpu_settings = cli.gpu.gpu_settings();
+    let input_mode = match cli.command {
+        Some(Command::Render(args)) => {
+            return render_sequence::run(args, &cli.files, &gpu_settings, cli.lod_threshold)
+        }
+        Some(Command::Record { file }) => InputMode::Record(file),
+        Some(Command::Replay { file, live_probes }) => InputMode::Replay {
+            path: file,
+            live_probes,
+        },
+        None => InputMode::Live,
+    };
+    run(
+        cli.files,
+        input_mode,
+        WindowSettings {
+            gpu: gpu_settings,
+            size: cli.window_size,
+            lod_threshold: cli.lod_threshold,
+            gpu_profiling: !cli.no_gpu_profiling,
+        },
+    )
 }
@@ -0,0 +1,173 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use clap::Args;
+use glam::UVec2;
+use log::{error, info};
+use pollster::FutureExt;
+use renderer_core::{
+    camera::{
+        camera_path::{CameraKeyframe, CameraPath, Easing},
+        Angle,
+    },
+    game::{GameRes, ShaderId},
+    input::RecordedFrame,
+    renderer::{
+        frame_counter::{Seconds, TimeSource},
+        GpuApplication, GpuApplicationBuilder, GpuSettings,
+    },
+    window_or_fallback::WindowOrFallback,
+};
+
+use crate::{
+    application::{create_scene, OpenFiles, CACHE_FILE},
+    cli::parse_size,
+    config::{CacheFile, CameraPathFile},
+};
+
+/// Renders frames without a window, with a fixed time step.
+/// Every run renders exactly the same frames.
+#[derive(Args, Debug)]
+pub struct RenderSequenceArgs {
+    /// Directory for the `frame_00000.png` files
+    #[arg(long, default_value = "frames")]
+    pub output: PathBuf,
+    #[arg(long, default_value_t = 120)]
+    pub frames: u32,
+    #[arg(long, default_value_t = 30.0)]
+    pub fps: f32,
+    /// The `time.elapsed` of the first frame, in seconds. Also the time on the turntable or the camera path.
+    #[arg(long = "start", default_value_t = 0.0)]
+    pub start_time: f32,
+    #[arg(long, default_value = "1920x1080", value_parser = parse_size)]
+    pub size: UVec2,
+    /// Seconds for one full turn around the center
+    #[arg(long, conflicts_with = "camera_path")]
+    pub turntable: Option<f32>,
+    /// A JSON file with keyframes
+    #[arg(long)]
+    pub camera_path: Option<PathBuf>,
+}
+
+pub enum SequenceCamera {
+    /// Stays where the camera was last time
+    Still,
+    /// Seconds for one full turn
+    Turntable(f32),
+    /// A JSON file with keyframes
+    Path(PathBuf),
+}
+
+impl RenderSequenceArgs {
+    pub fn camera(&self) -> SequenceCamera {
+        match (self.turntable, &self.camera_path) {
+            (Some(seconds_per_turn), _) => SequenceCamera::Turntable(seconds_per_turn),
+            (None, Some(path)) => SequenceCamera::Path(path.clone()),
+            (None, None) => SequenceCamera::Still,
+        }
+    }
+}
+
+pub fn run(
+    args: RenderSequenceArgs,
+    files: &OpenFiles,
+    gpu_settings: &GpuSettings,
+    lod_threshold: Option<f32>,
+) -> anyhow::Result<()> {
+    if args.fps <= 0.0 {
+        anyhow::bail!("The frames per second must be positive");
+    }
+    let mut game = GameRes::new();
+    let cache_file = CacheFile::from_file(CACHE_FILE).unwrap_or_default();
+    create_scene(&mut game, files, &cache_file)?;
+    game.set_time_source(TimeSource::Fixed {
+        start: Seconds(args.start_time),
+        delta: Seconds(1.0 / args.fps),
+    });
+    match &args.camera() {
+        SequenceCamera::Still => {}
+        SequenceCamera::Turntable(seconds_per_turn) => {
+            game.camera_controller.play_turntable(*seconds_per_turn)
+        }
+        SequenceCamera::Path(path) => game.camera_controller.play(load_camera_path(path)?),
+    }
+
+    let mut renderer = GpuApplicationBuilder::with_settings(
+        WindowOrFallback::Headless { size: args.size },
+        gpu_settings,
+    )
+    .block_on()?
+    .build();
+    if let Some(factor) = lod_threshold {
+        renderer.set_threshold_factor(factor);
+    }
+    for (texture_id, texture_info) in game.take_pending_textures() {
+        renderer.set_texture(texture_id, &texture_info);
+    }
+    compile_shaders(&renderer, &game)?;
+
+    std::fs::create_dir_all(&args.output)?;
+    let idle = RecordedFrame::default();
+    for frame in 0..args.frames {
+        game.update(&idle.inputs());
+        // The camera follows the same clock as the shaders, so a sequence can be split into several runs
+        game.camera_controller.seek(game.time.elapsed.0);
+        renderer.render(&game)?;
+        let file_name = args.output.join(format!("frame_{frame:05}.png"));
+        renderer.capture_frame()?.save(&file_name)?;
+        info!("Rendered {}", file_name.display());
+    }
+    Ok(())
+}
+
+fn load_camera_path(path: &Path) -> anyhow::Result<CameraPath> {
+    let file = CameraPathFile::from_file(path)
+        .map_err(|e| anyhow::anyhow!("Failed to load {}: {e}", path.display()))?;
+    let keyframes = file
+        .keyframes
+        .into_iter()
+        .map(|keyframe| CameraKeyframe {
+            time: keyframe.time,
+            center: keyframe.center.into(),
+            yaw: Angle::from_degrees(keyframe.yaw),
+            pitch: Angle::from_degrees(keyframe.pitch),
+            roll: Angle::from_degrees(keyframe.roll.unwrap_or(0.0)),
+            distance: keyframe.distance,
+        })
+        .collect();
+    let easing = if file.ease_in_out {
+        Easing::EaseInOut
+    } else {
+        Easing::Linear
+    };
+    CameraPath::new(keyframes, easing, file.looping)
+        .ok_or_else(|| anyhow::anyhow!("{} has no keyframes", path.display()))
+}
+
+/// Shaders are compiled in the background, but every frame needs them.
+fn compile_shaders(renderer: &GpuApplication, game: &GameRes) -> anyhow::Result<()> {
+    for (shader_id, shader_info) in &game.shaders {
+        renderer.set_shader(
+            shader_id.clone(),
+            shader_info,
+            Some(Arc::new(
+                |shader_id: &ShaderId, messages: Vec<wgpu::CompilationMessage>| {
+                    for message in messages {
+                        if message.message_type == wgpu::CompilationMessageType::Error {
+                            error!("{}: {}", shader_id.0, message.message);
+                        }
+                    }
+                },
+            )),
+        );
+    }
+    any_spawner::Executor::poll_local();
+    for shader_id in game.shaders.keys() {
+        if renderer.get_shader_parameters(shader_id).is_none() {
+            anyhow::bail!("Failed to compile {}", shader_id.0);
+        }
+    }
+    Ok(())
+}
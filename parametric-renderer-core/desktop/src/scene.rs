@@ -0,0 +1,509 @@
+use std::{collections::HashMap, path::Path};
+
+use glam::{EulerRot, Quat, Vec3, Vec4};
+use nanoserde::{DeJson, SerJson};
+use renderer_core::{
+    game::{
+        AmbientOcclusion, Background, GameRes, MaterialInfo, ModelInfo, PointLight, ShaderId,
+        ShaderInfo, TextureId, TextureInfo, ToneMappingCurve, ToneMappingSettings,
+    },
+    geometry_image::GeometryImage,
+    transform::Transform,
+};
+
+use crate::config::{CachedBackground, CachedCamera, LoadConfigError};
+
+/// The same as the `scene.json` of the web app, so that scenes can be opened in both.
+const SCENE_SCHEMA_URL: &'static str = "http://virtual/scene-schema.json";
+
+/// Models with their shaders and materials, and optionally a camera, a background and the lighting.
+/// Shader and texture paths are relative to the scene file.
+/// The optional fields are extensions that the web app ignores.
+#[derive(DeJson, SerJson, Debug, Clone)]
+pub struct SceneFile {
+    #[nserde(rename = "$schema")]
+    pub schema: Option<String>,
+    pub models: Vec<SceneModel>,
+    /// Only used when opening the scene, and not when reloading it
+    pub camera: Option<CachedCamera>,
+    pub background: Option<CachedBackground>,
+    pub lighting: Option<SceneLighting>,
+}
+
+#[derive(DeJson, SerJson, Debug, Clone, PartialEq)]
+pub struct SceneModel {
+    /// Always `model`
+    #[nserde(rename = "type")]
+    #[nserde(default)]
+    pub kind: String,
+    pub id: String,
+    pub name: String,
+    pub position: [f32; 3],
+    /// XYZ Euler angles, in radians
+    pub rotation: [f32; 3],
+    pub scale: f32,
+    /// Path to the WGSL file, like `my-shader.wgsl`
+    #[nserde(rename = "parametricShader")]
+    pub parametric_shader: String,
+    pub material: SceneMaterial,
+    #[nserde(rename = "instanceCount")]
+    pub instance_count: Option<u32>,
+    /// Values for the `struct Parameters` of the shader, by member name
+    pub parameters: Option<HashMap<String, [f32; 4]>>,
+    /// Path to the image that the shader samples with `user_texture`
+    pub texture: Option<String>,
+}
+
+#[derive(DeJson, SerJson, Debug, Clone, PartialEq)]
+pub struct SceneMaterial {
+    pub color: [f32; 3],
+    pub roughness: f32,
+    pub metallic: f32,
+    pub emissive: [f32; 3],
+    /// Fully opaque when missing
+    pub opacity: Option<f32>,
+    /// Paths to images that repeat across the surface
+    #[nserde(rename = "albedoTexture")]
+    pub albedo_texture: Option<String>,
+    #[nserde(rename = "roughnessTexture")]
+    pub roughness_texture: Option<String>,
+    #[nserde(rename = "normalTexture")]
+    pub normal_texture: Option<String>,
+}
+
+#[derive(DeJson, SerJson, Debug, Clone, PartialEq)]
+pub struct SceneLighting {
+    /// Linear sRGB. The default ambient light is kept when it is missing.
+    pub ambient: Option<[f32; 3]>,
+    /// The default point light is kept when they are missing
+    #[nserde(rename = "pointLights")]
+    pub point_lights: Option<Vec<ScenePointLight>>,
+    #[nserde(rename = "ambientOcclusion")]
+    pub ambient_occlusion: SceneAmbientOcclusion,
+    #[nserde(rename = "toneMapping")]
+    pub tone_mapping: SceneToneMapping,
+    /// In stops
+    pub exposure: f32,
+}
+
+#[derive(DeJson, SerJson, Debug, Clone, PartialEq)]
+pub struct ScenePointLight {
+    pub position: [f32; 3],
+    pub range: f32,
+    /// Linear sRGB
+    pub color: [f32; 3],
+    pub intensity: f32,
+}
+
+#[derive(DeJson, SerJson, Debug, Clone, Copy, PartialEq)]
+pub enum SceneAmbientOcclusion {
+    Off,
+    Low,
+    High,
+}
+
+#[derive(DeJson, SerJson, Debug, Clone, Copy, PartialEq)]
+pub enum SceneToneMapping {
+    Aces,
+    AgX,
+    Reinhard,
+}
+
+impl SceneFile {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, LoadConfigError> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(DeJson::deserialize_json(&content)?)
+    }
+
+    pub fn save_to_file(&self, path: impl AsRef<Path>) -> Result<(), std::io::Error> {
+        let content = SerJson::serialize_json(self);
+        std::fs::write(path, content)
+    }
+
+    /// The models of the app, except for geometry images, which are opened separately.
+    /// Keeps the names of the previous scene file.
+    pub fn from_game(app: &GameRes, previous: Option<&SceneFile>, camera: CachedCamera) -> Self {
+        let names: HashMap<_, _> = previous
+            .map(|scene| {
+                scene
+                    .models
+                    .iter()
+                    .map(|model| (model.id.as_str(), model.name.as_str()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let models = app
+            .models
+            .iter()
+            .filter(|model| model.shader_id != GeometryImage::shader_id())
+            .map(|model| {
+                let name = match names.get(model.id.as_str()) {
+                    Some(name) => name.to_string(),
+                    None => app
+                        .shaders
+                        .get(&model.shader_id)
+                        .map(|shader| shader.label.clone())
+                        .unwrap_or_else(|| model.id.clone()),
+                };
+                SceneModel::from_model_info(model, name)
+            })
+            .collect();
+        Self {
+            schema: Some(SCENE_SCHEMA_URL.into()),
+            models,
+            camera: Some(camera),
+            background: Some(cached_background(&app.background)),
+            lighting: Some(SceneLighting::from_game(app)),
+        }
+    }
+
+    pub fn models(&self) -> Vec<ModelInfo> {
+        self.models.iter().map(SceneModel::to_model_info).collect()
+    }
+
+    /// Reads every shader that the models use from the directory of the scene file.
+    pub fn load_shaders(&self, directory: &Path) -> anyhow::Result<Vec<(ShaderId, ShaderInfo)>> {
+        let mut paths = self
+            .models
+            .iter()
+            .map(|model| model.parametric_shader.as_str())
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths.dedup();
+        paths
+            .into_iter()
+            .map(|path| {
+                let file_path = directory.join(path);
+                let code = std::fs::read_to_string(&file_path)
+                    .map_err(|e| anyhow::anyhow!("Failed to read {}: {e}", file_path.display()))?;
+                let label = Path::new(path)
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| path.to_string());
+                Ok((ShaderId(path.to_string()), ShaderInfo { label, code }))
+            })
+            .collect()
+    }
+
+    /// Reads every texture that the models use from the directory of the scene file.
+    /// Their IDs are the paths in the scene file.
+    pub fn load_textures(&self, directory: &Path) -> anyhow::Result<Vec<(TextureId, TextureInfo)>> {
+        let mut paths = self
+            .models
+            .iter()
+            .flat_map(|model| {
+                let material = &model.material;
+                [
+                    &model.texture,
+                    &material.albedo_texture,
+                    &material.roughness_texture,
+                    &material.normal_texture,
+                ]
+            })
+            .filter_map(|path| path.as_deref())
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths.dedup();
+        paths
+            .into_iter()
+            .map(|path| {
+                let file_path = directory.join(path);
+                let texture = TextureInfo::load(&file_path)
+                    .map_err(|e| anyhow::anyhow!("Failed to read {}: {e}", file_path.display()))?;
+                Ok((TextureId(path.to_string()), texture))
+            })
+            .collect()
+    }
+}
+
+impl SceneModel {
+    fn from_model_info(model: &ModelInfo, name: String) -> Self {
+        let (x, y, z) = model.transform.rotation.to_euler(EulerRot::XYZ);
+        let material = &model.material_info;
+        let texture_path = |texture: &Option<TextureId>| texture.as_ref().map(|v| v.0.clone());
+        SceneModel {
+            kind: "model".into(),
+            id: model.id.clone(),
+            name,
+            position: model.transform.position.to_array(),
+            rotation: [x, y, z],
+            scale: model.transform.scale,
+            parametric_shader: model.shader_id.0.clone(),
+            material: SceneMaterial {
+                color: material.color.to_array(),
+                roughness: material.roughness,
+                metallic: material.metallic,
+                emissive: material.emissive.to_array(),
+                opacity: (material.opacity < 1.0).then_some(material.opacity),
+                albedo_texture: texture_path(&material.albedo_texture),
+                roughness_texture: texture_path(&material.roughness_texture),
+                normal_texture: texture_path(&material.normal_texture),
+            },
+            instance_count: Some(model.instance_count),
+            parameters: (!model.parameters.is_empty()).then(|| {
+                model
+                    .parameters
+                    .iter()
+                    .map(|(name, value)| (name.clone(), value.to_array()))
+                    .collect()
+            }),
+            texture: texture_path(&model.texture_id),
+        }
+    }
+
+    fn to_model_info(&self) -> ModelInfo {
+        let texture_id = |path: &Option<String>| path.clone().map(TextureId);
+        ModelInfo {
+            id: self.id.clone(),
+            transform: Transform {
+                position: Vec3::from(self.position),
+                rotation: Quat::from_euler(
+                    EulerRot::XYZ,
+                    self.rotation[0],
+                    self.rotation[1],
+                    self.rotation[2],
+                ),
+                scale: self.scale,
+            },
+            material_info: MaterialInfo {
+                color: Vec3::from(self.material.color),
+                emissive: Vec3::from(self.material.emissive),
+                roughness: self.material.roughness,
+                metallic: self.material.metallic,
+                opacity: self.material.opacity.unwrap_or(1.0),
+                albedo_texture: texture_id(&self.material.albedo_texture),
+                roughness_texture: texture_id(&self.material.roughness_texture),
+                normal_texture: texture_id(&self.material.normal_texture),
+            },
+            shader_id: ShaderId(self.parametric_shader.clone()),
+            instance_count: self.instance_count.unwrap_or(1),
+            parameters: self
+                .parameters
+                .iter()
+                .flatten()
+                .map(|(name, value)| (name.clone(), Vec4::from_array(*value)))
+                .collect(),
+            texture_id: texture_id(&self.texture),
+        }
+    }
+}
+
+impl SceneLighting {
+    pub fn from_game(app: &GameRes) -> Self {
+        Self {
+            ambient: Some(app.lighting.ambient.to_array()),
+            point_lights: Some(
+                app.lighting
+                    .points
+                    .iter()
+                    .map(|light| ScenePointLight {
+                        position: light.position.to_array(),
+                        range: light.range,
+                        color: light.color.to_array(),
+                        intensity: light.intensity,
+                    })
+                    .collect(),
+            ),
+            ambient_occlusion: match app.ambient_occlusion {
+                AmbientOcclusion::Off => SceneAmbientOcclusion::Off,
+                AmbientOcclusion::Low => SceneAmbientOcclusion::Low,
+                AmbientOcclusion::High => SceneAmbientOcclusion::High,
+            },
+            tone_mapping: match app.tone_mapping.curve {
+                ToneMappingCurve::Aces => SceneToneMapping::Aces,
+                ToneMappingCurve::AgX => SceneToneMapping::AgX,
+                ToneMappingCurve::Reinhard => SceneToneMapping::Reinhard,
+            },
+            exposure: app.tone_mapping.exposure,
+        }
+    }
+
+    pub fn apply_to(&self, app: &mut GameRes) {
+        if let Some(ambient) = self.ambient {
+            app.lighting.ambient = Vec3::from(ambient);
+        }
+        if let Some(point_lights) = &self.point_lights {
+            app.lighting.points = point_lights
+                .iter()
+                .map(|light| PointLight {
+                    position: Vec3::from(light.position),
+                    range: light.range,
+                    color: Vec3::from(light.color),
+                    intensity: light.intensity,
+                })
+                .collect();
+        }
+        app.ambient_occlusion = match self.ambient_occlusion {
+            SceneAmbientOcclusion::Off => AmbientOcclusion::Off,
+            SceneAmbientOcclusion::Low => AmbientOcclusion::Low,
+            SceneAmbientOcclusion::High => AmbientOcclusion::High,
+        };
+        app.tone_mapping = ToneMappingSettings {
+            curve: match self.tone_mapping {
+                SceneToneMapping::Aces => ToneMappingCurve::Aces,
+                SceneToneMapping::AgX => ToneMappingCurve::AgX,
+                SceneToneMapping::Reinhard => ToneMappingCurve::Reinhard,
+            },
+            exposure: self.exposure,
+        };
+    }
+}
+
+/// Skyboxes keep the default color, for when their image cannot be loaded.
+fn cached_background(background: &Background) -> CachedBackground {
+    match background {
+        Background::Solid(color) => CachedBackground {
+            top: color.to_array(),
+            bottom: None,
+            skybox: None,
+        },
+        Background::Gradient { top, bottom } => CachedBackground {
+            top: top.to_array(),
+            bottom: Some(bottom.to_array()),
+            skybox: None,
+        },
+        Background::Skybox(texture_id) => CachedBackground {
+            skybox: Some(texture_id.0.clone()),
+            ..cached_background(&Background::default())
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Saved by the web app, so it has none of the extensions
+    const WEB_SCENE: &str = include_str!("../../shaders/showcase/scene.json");
+
+    /// With extensions, and with fields that the desktop app does not know
+    const EXTENDED_SCENE: &str = r#"{
+  "$schema": "http://virtual/scene-schema.json",
+  "selectedModel": "a",
+  "models": [
+    {
+      "type": "model",
+      "id": "a",
+      "name": "Wavy plane",
+      "position": [1, 2, 3],
+      "rotation": [0.3, 0.5, 0.7],
+      "scale": 2,
+      "parametricShader": "plane.wgsl",
+      "material": {
+        "color": [1, 0.5, 0],
+        "roughness": 0.5,
+        "metallic": 0.1,
+        "emissive": [0, 0, 0],
+        "opacity": 0.5,
+        "albedoTexture": "wood.png",
+        "gloss": 1
+      },
+      "instanceCount": 3,
+      "parameters": { "amplitude": [0.25, 0, 0, 0] },
+      "texture": "height.png",
+      "visible": true
+    }
+  ],
+  "lighting": {
+    "ambient": [0.05, 0.05, 0.1],
+    "pointLights": [
+      { "position": [0, 5, 0], "range": 20, "color": [1, 0.9, 0.8], "intensity": 4 }
+    ],
+    "ambientOcclusion": "High",
+    "toneMapping": "AgX",
+    "exposure": 1.5
+  }
+}"#;
+
+    #[test]
+    fn opens_web_app_scene() {
+        let scene: SceneFile = DeJson::deserialize_json(WEB_SCENE).unwrap();
+        assert_eq!(scene.schema.as_deref(), Some(SCENE_SCHEMA_URL));
+        assert!(scene.camera.is_none());
+        assert!(scene.lighting.is_none());
+        let models = scene.models();
+        assert_eq!(models.len(), scene.models.len());
+        let heart = &models[0];
+        assert_eq!(heart.shader_id, ShaderId("heart.wgsl".into()));
+        assert_eq!(heart.transform.position, Vec3::new(5.0, 0.0, 0.0));
+        // The web app leaves out the instance count when there is only one
+        assert_eq!(heart.instance_count, 1);
+        assert_eq!(heart.material_info.opacity, 1.0);
+        assert_eq!(heart.material_info.albedo_texture, None);
+        assert!(heart.parameters.is_empty());
+        assert_eq!(heart.texture_id, None);
+    }
+
+    #[test]
+    fn keeps_extensions_and_skips_unknown_fields() {
+        let scene: SceneFile = DeJson::deserialize_json(EXTENDED_SCENE).unwrap();
+        let model = &scene.models()[0];
+        assert_eq!(model.instance_count, 3);
+        assert_eq!(model.material_info.opacity, 0.5);
+        assert_eq!(
+            model.material_info.albedo_texture,
+            Some(TextureId("wood.png".into()))
+        );
+        assert_eq!(model.material_info.normal_texture, None);
+        assert_eq!(
+            model.parameters["amplitude"],
+            Vec4::new(0.25, 0.0, 0.0, 0.0)
+        );
+        assert_eq!(model.texture_id, Some(TextureId("height.png".into())));
+        assert_eq!(
+            scene.lighting,
+            Some(SceneLighting {
+                ambient: Some([0.05, 0.05, 0.1]),
+                point_lights: Some(vec![ScenePointLight {
+                    position: [0.0, 5.0, 0.0],
+                    range: 20.0,
+                    color: [1.0, 0.9, 0.8],
+                    intensity: 4.0,
+                }]),
+                ambient_occlusion: SceneAmbientOcclusion::High,
+                tone_mapping: SceneToneMapping::AgX,
+                exposure: 1.5,
+            })
+        );
+
+        let saved: SceneFile = DeJson::deserialize_json(&SerJson::serialize_json(&scene)).unwrap();
+        assert_eq!(saved.models, scene.models);
+        assert_eq!(saved.lighting, scene.lighting);
+    }
+
+    #[test]
+    fn lighting_round_trips_through_the_app() {
+        let scene: SceneFile = DeJson::deserialize_json(EXTENDED_SCENE).unwrap();
+        let lighting = scene.lighting.unwrap();
+        let mut app = GameRes::new();
+        lighting.apply_to(&mut app);
+        assert_eq!(app.lighting.ambient, Vec3::new(0.05, 0.05, 0.1));
+        assert_eq!(app.lighting.points[0].position, Vec3::new(0.0, 5.0, 0.0));
+        assert_eq!(SceneLighting::from_game(&app), lighting);
+    }
+
+    #[test]
+    fn rotation_is_xyz_euler_like_the_web_app() {
+        let scene: SceneFile = DeJson::deserialize_json(EXTENDED_SCENE).unwrap();
+        let model = &scene.models()[0];
+        // The web app passes the Euler angles to `WasmTransform`, which rotates around Z first, then Y and then X
+        let expected =
+            Quat::from_rotation_x(0.3) * Quat::from_rotation_y(0.5) * Quat::from_rotation_z(0.7);
+        assert!(model.transform.rotation.abs_diff_eq(expected, 1e-6));
+
+        let saved = SceneModel::from_model_info(model, "Wavy plane".into());
+        for (saved, original) in saved.rotation.iter().zip(scene.models[0].rotation) {
+            assert!((saved - original).abs() < 1e-5);
+        }
+        assert_eq!(
+            SceneModel {
+                rotation: scene.models[0].rotation,
+                ..saved
+            },
+            SceneModel {
+                kind: "model".into(),
+                ..scene.models[0].clone()
+            }
+        );
+    }
+}
@@ -46,9 +46,15 @@ fn main() {
             emissive: Vec3::new(0.0, 0.0, 0.0),
             roughness: 0.7,
             metallic: 0.1,
+            opacity: 1.0,
+            albedo_texture: None,
+            roughness_texture: None,
+            normal_texture: None,
         },
         shader_id,
         instance_count: 1,
+        parameters: Default::default(),
+        texture_id: None,
     }]);
 
     // TODO: Why is this needed for benchmarking?
@@ -83,27 +89,9 @@ fn main() {
 
             // If we aren't allowed to mutate, then we'll either use a different criterion function
             // or we'll use the internal render_commands function
-            let dummy_set_0 = Default::default();
-            let dummy_set_1 = Default::default();
-            let dummy_set_2 = Default::default();
-            app.update(&renderer_core::input::WindowInputs {
-                mouse: renderer_core::input::WindowMouseInputs {
-                    position: Default::default(),
-                    position_delta: Default::default(),
-                    motion: Default::default(),
-                    scroll_delta: Default::default(),
-                    inputs: Default::default(),
-                    held: &dummy_set_0,
-                },
-                keyboard: renderer_core::input::WindowKeyboardInputs {
-                    inputs: Default::default(),
-                    physical_held: &dummy_set_1,
-                    logical_held: &dummy_set_2,
-                },
-                new_size: Default::default(),
-                new_scale_factor: Default::default(),
-                close_requested: Default::default(),
-            });
+            // No inputs at all
+            let frame = renderer_core::input::RecordedFrame::default();
+            app.update(&frame.inputs());
             renderer.force_wait();
             let render_results = renderer.render(&app).unwrap();
             timer.increment_query(render_results.profiler_results.unwrap());
@@ -30,8 +30,21 @@ fn main() {
         "../shaders/ComputePatches.wgsl",
         "compute_patches",
     ));
+    shaders.push(watch_shader(
+        "../shaders/ComputeBounds.wgsl",
+        "compute_bounds",
+    ));
     shaders.push(watch_shader("../shaders/CopyPatches.wgsl", "copy_patches"));
     shaders.push(watch_shader("../shaders/GroundPlane.wgsl", "ground_plane"));
+    shaders.push(watch_shader("../shaders/Fxaa.wgsl", "fxaa"));
+    shaders.push(watch_shader("../shaders/Tonemap.wgsl", "tonemap"));
+    shaders.push(watch_shader(
+        "../shaders/DepthResolve.wgsl",
+        "depth_resolve",
+    ));
+    shaders.push(watch_shader("../shaders/CursorDepth.wgsl", "cursor_depth"));
+    shaders.push(watch_shader("../shaders/Ssao.wgsl", "ssao"));
+    shaders.push(watch_shader("../shaders/SsaoApply.wgsl", "ssao_apply"));
 
     let mut text = String::new();
     writeln!(&mut text, "// File automatically generated by build.rs.").unwrap();
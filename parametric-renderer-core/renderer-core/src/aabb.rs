@@ -0,0 +1,89 @@
+use glam::{Mat4, Vec3};
+
+/// An axis aligned bounding box.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    /// Radius of the sphere that encloses the box
+    pub fn radius(&self) -> f32 {
+        (self.max - self.min).length() * 0.5
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    /// The box that encloses all corners after the transformation.
+    pub fn transform(&self, matrix: Mat4) -> Aabb {
+        (0..8)
+            .map(|i| {
+                let corner = Vec3::select(
+                    glam::BVec3::new(i & 1 != 0, i & 2 != 0, i & 4 != 0),
+                    self.max,
+                    self.min,
+                );
+                matrix.transform_point3(corner)
+            })
+            .fold(
+                Aabb {
+                    min: Vec3::INFINITY,
+                    max: Vec3::NEG_INFINITY,
+                },
+                |bounds, point| Aabb {
+                    min: bounds.min.min(point),
+                    max: bounds.max.max(point),
+                },
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Quat;
+
+    #[test]
+    fn transform_scales_and_moves() {
+        let bounds = Aabb {
+            min: Vec3::new(-1.0, 0.0, -2.0),
+            max: Vec3::new(1.0, 2.0, 2.0),
+        };
+        let matrix = Mat4::from_scale_rotation_translation(
+            Vec3::splat(2.0),
+            Quat::IDENTITY,
+            Vec3::new(10.0, 0.0, 0.0),
+        );
+        assert_eq!(
+            bounds.transform(matrix),
+            Aabb {
+                min: Vec3::new(8.0, 0.0, -4.0),
+                max: Vec3::new(12.0, 4.0, 4.0),
+            }
+        );
+    }
+
+    #[test]
+    fn transform_encloses_rotated_corners() {
+        let bounds = Aabb {
+            min: Vec3::splat(-1.0),
+            max: Vec3::splat(1.0),
+        };
+        let rotated = bounds.transform(Mat4::from_rotation_y(std::f32::consts::FRAC_PI_4));
+        let half_diagonal = std::f32::consts::SQRT_2;
+        assert!((rotated.max.x - half_diagonal).abs() < 1e-5);
+        assert!((rotated.min.z + half_diagonal).abs() < 1e-5);
+        assert!((rotated.max.y - 1.0).abs() < 1e-5);
+        assert!((rotated.center() - Vec3::ZERO).length() < 1e-5);
+    }
+}
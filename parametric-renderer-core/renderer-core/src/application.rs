@@ -7,9 +7,13 @@ use winit::{
 };
 
 use crate::{
-    game::{GameRes, ShaderId},
-    input::{InputHandler, WindowInputs},
-    renderer::{frame_counter::Seconds, GpuApplication, GpuApplicationBuilder},
+    camera::{
+        camera_controller::{ChosenKind, GeneralController, IsCameraController},
+        Angle, Projection,
+    },
+    game::{GameRes, ShaderId, ShaderInfo, TextureId, TextureInfo},
+    input::{Action, InputHandler, InputRecording, InputReplay, RecordedFrame, WindowInputs},
+    renderer::{frame_counter::Seconds, GpuApplication, GpuApplicationBuilder, GpuSettings},
     window_or_fallback::WindowOrFallback,
 };
 pub struct WasmCanvas {
@@ -33,9 +37,22 @@ pub struct Application {
     window: Option<Arc<Window>>,
     pub renderer: Option<GpuApplication>,
     pub time_counters: TimeCounters,
+    /// Only used when the window gets created
+    pub gpu_settings: GpuSettings,
+    /// In physical pixels. Only used when the window gets created.
+    pub window_size: Option<UVec2>,
+    /// The threshold factor of the LOD algorithm, once the renderer exists
+    pub initial_threshold_factor: Option<f32>,
     app_commands: EventLoopProxy<AppCommand>,
     on_exit_callback: Option<Box<dyn FnOnce(&mut Application)>>,
     pub on_shader_compiled: Option<Arc<dyn Fn(&ShaderId, Vec<wgpu::CompilationMessage>)>>,
+    /// Called after every frame in which the camera moved, or changed its controller, projection or field of view
+    pub on_camera_changed: Option<Arc<dyn Fn(&GameRes)>>,
+    last_camera: Option<(GeneralController, ChosenKind, Projection, Angle)>,
+    /// Called for the actions that the host application handles, like saving the scene
+    pub on_action: Option<Arc<dyn Fn(&mut Application, Action)>>,
+    input_recording: Option<InputRecording>,
+    input_replay: Option<InputReplay>,
     _canvas: WasmCanvas,
 }
 
@@ -50,35 +67,136 @@ impl Application {
             app: GameRes::new(),
             renderer: None,
             time_counters: TimeCounters::default(),
+            gpu_settings: GpuSettings::default(),
+            window_size: None,
+            initial_threshold_factor: None,
             app_commands,
             on_exit_callback: Some(Box::new(on_exit)),
             on_shader_compiled: None,
+            on_camera_changed: None,
+            last_camera: None,
+            on_action: None,
+            input_recording: None,
+            input_replay: None,
             _canvas: canvas,
         }
     }
 
     fn on_exit(&mut self) {
         info!("Stopping the application.");
+        // So that the user's settings get saved, and not the recorded ones
+        if let Some(replay) = self.input_replay.take() {
+            replay.finish(&mut self.app);
+        }
         if let Some(on_exit_callback) = self.on_exit_callback.take() {
             on_exit_callback(self);
         }
     }
 
+    /// Also compiles the shader, if the renderer already exists.
+    pub fn set_shader(&mut self, shader_id: ShaderId, info: ShaderInfo) {
+        let on_shader_compiled = self.on_shader_compiled.clone();
+        if let Some(renderer) = &mut self.renderer {
+            renderer.set_shader(shader_id.clone(), &info, on_shader_compiled);
+        }
+        self.app.set_shader(shader_id, info);
+    }
+
+    /// Uploads the texture, or keeps it until the renderer exists.
+    pub fn set_texture(&mut self, texture_id: TextureId, info: TextureInfo) {
+        match &self.renderer {
+            Some(renderer) => renderer.set_texture(texture_id, &info),
+            None => self.app.set_texture(texture_id, info),
+        }
+    }
+
+    pub fn remove_texture(&mut self, texture_id: &TextureId) {
+        self.app.remove_texture(texture_id);
+        if let Some(renderer) = &self.renderer {
+            renderer.remove_texture(texture_id);
+        }
+    }
+
+    /// Records the inputs of every frame from now on, starting at the current camera.
+    pub fn start_recording(&mut self) {
+        self.input_recording = Some(InputRecording::new(&self.app));
+    }
+
+    pub fn stop_recording(&mut self) -> Option<InputRecording> {
+        self.input_recording.take()
+    }
+
+    /// Uses the recorded inputs instead of the real ones, until the recording ends.
+    /// Closing the window still works while replaying.
+    /// Afterwards, the camera settings and controls are the user's own again.
+    /// With `live_probes`, the points under the cursor come from the renderer instead of the recording.
+    pub fn start_replay(&mut self, recording: InputRecording, live_probes: bool) {
+        let mut replay = InputReplay::start(recording, &mut self.app);
+        replay.live_probes = live_probes;
+        self.input_replay = Some(replay);
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        self.input_replay.is_some()
+    }
+
+    fn notify_camera_changed(&mut self) {
+        let Some(on_camera_changed) = &self.on_camera_changed else {
+            return;
+        };
+        let camera = (
+            self.app.camera_controller.general_controller(),
+            self.app.camera_controller.get_chosen_kind(),
+            self.app.camera.settings.projection,
+            self.app.camera.settings.fov,
+        );
+        let changed = match &self.last_camera {
+            Some((controller, kind, projection, fov)) => {
+                !controller.approx_eq(&camera.0)
+                    || *kind != camera.1
+                    || *projection != camera.2
+                    || *fov != camera.3
+            }
+            None => true,
+        };
+        if changed {
+            self.last_camera = Some(camera);
+            on_camera_changed(&self.app);
+        }
+    }
+
     fn create_surface(&mut self, window: Window) {
         let window = Arc::new(window);
         self.window = Some(window.clone());
 
-        let gpu_builder = GpuApplicationBuilder::new(WindowOrFallback::Window(window));
-
+        let gpu_settings = self.gpu_settings.clone();
         let app_commands = self.app_commands.clone();
         let on_shader_compiled = self.on_shader_compiled.clone();
         let task = async move {
             println!("Creating renderer");
-            let renderer = gpu_builder.await.unwrap().build();
+            let renderer = match GpuApplicationBuilder::with_settings(
+                WindowOrFallback::Window(window),
+                &gpu_settings,
+            )
+            .await
+            {
+                Ok(builder) => builder.build(),
+                Err(e) => {
+                    error!("Failed to create the renderer: {e:#}");
+                    let _ = app_commands.send_event(AppCommand::Exit);
+                    return;
+                }
+            };
             let _ = run_on_main(app_commands, move |app| {
+                if let Some(factor) = app.initial_threshold_factor {
+                    renderer.set_threshold_factor(factor);
+                }
                 for (shader_id, shader_info) in &app.app.shaders {
                     renderer.set_shader(shader_id.clone(), shader_info, on_shader_compiled.clone());
                 }
+                for (texture_id, texture_info) in app.app.take_pending_textures() {
+                    renderer.set_texture(texture_id, &texture_info);
+                }
                 app.renderer = Some(renderer)
             })
             .await;
@@ -89,6 +207,8 @@ impl Application {
 
 pub enum AppCommand {
     RunCallback(Box<dyn FnOnce(&mut Application)>),
+    /// Stops the event loop, like closing the window
+    Exit,
 }
 
 /// Run a function on the main thread and awaits its result.
@@ -122,7 +242,11 @@ impl ApplicationHandler<AppCommand> for Application {
             return;
         }
 
-        let window_attributes = Window::default_attributes();
+        let mut window_attributes = Window::default_attributes();
+        if let Some(size) = self.window_size {
+            window_attributes =
+                window_attributes.with_inner_size(PhysicalSize::new(size.x, size.y));
+        }
         #[cfg(target_arch = "wasm32")]
         let window_attributes = {
             use winit::platform::web::WindowAttributesExtWebSys;
@@ -133,9 +257,13 @@ impl ApplicationHandler<AppCommand> for Application {
         self.create_surface(window);
     }
 
-    fn user_event(&mut self, _event_loop: &winit::event_loop::ActiveEventLoop, event: AppCommand) {
+    fn user_event(&mut self, event_loop: &winit::event_loop::ActiveEventLoop, event: AppCommand) {
         match event {
             AppCommand::RunCallback(callback) => callback(self),
+            AppCommand::Exit => {
+                self.on_exit();
+                event_loop.exit();
+            }
         }
     }
 
@@ -171,11 +299,10 @@ impl ApplicationHandler<AppCommand> for Application {
 impl InputHandler for Application {
     fn update(&mut self, event_loop: &winit::event_loop::ActiveEventLoop, input: WindowInputs<'_>) {
         #[cfg(not(target_arch = "wasm32"))]
-        if input
-            .keyboard
-            .just_released_logical(winit::keyboard::Key::Named(
-                winit::keyboard::NamedKey::Escape,
-            ))
+        if self
+            .app
+            .actions
+            .just_released(crate::input::Action::Exit, &input)
         {
             self.on_exit();
             return event_loop.exit();
@@ -187,9 +314,10 @@ impl InputHandler for Application {
 
         // Press P to print profiling data
         #[cfg(not(target_arch = "wasm32"))]
-        if input
-            .keyboard
-            .just_pressed_physical(winit::keyboard::KeyCode::KeyP)
+        if self
+            .app
+            .actions
+            .just_pressed(crate::input::Action::Profile, &input)
         {
             match &self.time_counters.last_results {
                 Some(data) => {
@@ -214,6 +342,14 @@ impl InputHandler for Application {
             }
         }
 
+        if let Some(on_action) = self.on_action.clone() {
+            for action in [Action::ReloadScene, Action::SaveScene] {
+                if self.app.actions.just_pressed(action, &input) {
+                    on_action(self, action);
+                }
+            }
+        }
+
         if let Some(PhysicalSize { width, height }) = input.new_size {
             self.renderer
                 .as_mut()
@@ -232,7 +368,44 @@ impl InputHandler for Application {
             }
         }
 
-        self.app.update(&input);
+        if let Some(renderer) = &self.renderer {
+            self.app.model_bounds = renderer.model_bounds();
+            self.app.surface_size = renderer.size();
+            self.app.cursor_world_position = renderer.cursor_world_position();
+            self.app.center_world_position = renderer.center_world_position();
+        }
+        let live_probes = self
+            .input_replay
+            .as_ref()
+            .is_some_and(|replay| replay.live_probes);
+        match self
+            .input_replay
+            .as_mut()
+            .and_then(|replay| replay.next_frame())
+        {
+            Some(frame) => {
+                // The renderer would find a slightly different point under the cursor
+                if !live_probes {
+                    self.app.cursor_world_position = frame.cursor_world_position;
+                    self.app.center_world_position = frame.center_world_position;
+                }
+                self.app.update_with_time(&frame.inputs(), frame.time);
+                if let Some(recording) = &mut self.input_recording {
+                    recording.frames.push(frame.clone());
+                }
+            }
+            None => {
+                if let Some(replay) = self.input_replay.take() {
+                    replay.finish(&mut self.app);
+                    info!("Finished replaying the inputs");
+                }
+                self.app.update(&input);
+                if let Some(recording) = &mut self.input_recording {
+                    recording.frames.push(RecordedFrame::new(&input, &self.app));
+                }
+            }
+        }
+        self.notify_camera_changed();
         match self.renderer.as_mut().map(|r| r.render(&self.app)) {
             None => (),
             Some(Ok(render_results)) => {
@@ -0,0 +1,27 @@
+use super::{
+    camera_controller::{CameraController, ChosenKind, GeneralController, IsCameraController},
+    Angle, CameraSettings,
+};
+
+/// A named viewpoint that the camera can fly back to.
+#[derive(Debug, Clone)]
+pub struct CameraBookmark {
+    pub name: String,
+    pub camera: GeneralController,
+    pub kind: ChosenKind,
+    pub fov: Angle,
+    /// The number key that flies to it, from 1 to 9
+    pub slot: Option<u8>,
+}
+
+impl CameraBookmark {
+    pub fn new(name: String, controller: &CameraController, settings: &CameraSettings) -> Self {
+        Self {
+            name,
+            camera: controller.general_controller(),
+            kind: controller.get_chosen_kind(),
+            fov: settings.fov,
+            slot: None,
+        }
+    }
+}
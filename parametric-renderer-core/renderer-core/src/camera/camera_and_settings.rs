@@ -2,28 +2,75 @@ use glam::{Mat4, Quat, UVec2, Vec3};
 
 use super::{angle::Angle, camera_controller::IsCameraController};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Projection {
+    #[default]
+    Perspective,
+    /// Keeps parallel lines parallel. Shows the same area at the focus distance as the perspective projection.
+    Orthographic,
+}
+
 #[derive(Debug, Clone)]
 pub struct CameraSettings {
     pub z_near: f32,
-    pub z_far: f32,
+    /// How far the camera can see. `None` is infinitely far.
+    pub z_far: Option<f32>,
+    /// The vertical field of view
     pub fov: Angle,
+    pub projection: Projection,
 }
 
 impl Default for CameraSettings {
     fn default() -> Self {
         Self {
             z_near: 0.1,
-            z_far: 100.0,
+            z_far: None,
             fov: Angle::from_degrees(60.0),
+            projection: Projection::Perspective,
+        }
+    }
+}
+
+impl CameraSettings {
+    /// Keeps the settings in a range where the projection matrices work.
+    pub fn clamped(self) -> Self {
+        let z_near = self.z_near.max(0.0001);
+        Self {
+            z_near,
+            z_far: self.z_far.map(|z_far| z_far.max(z_near * 1.01)),
+            fov: Angle::new(self.fov.radians.clamp(
+                Angle::from_degrees(1.0).radians,
+                Angle::from_degrees(179.0).radians,
+            )),
+            projection: self.projection,
+        }
+    }
+
+    /// How far away a sphere has to be to fit into the view, in both directions.
+    pub fn framing_distance(&self, radius: f32, aspect_ratio: f32) -> f32 {
+        let half_fov_tan = (self.fov.radians / 2.0).tan();
+        match self.projection {
+            Projection::Perspective => {
+                let horizontal_half_fov = (half_fov_tan * aspect_ratio).atan();
+                let half_fov = horizontal_half_fov.min(self.fov.radians / 2.0);
+                radius / half_fov.sin()
+            }
+            // Same half extents as in the projection matrix
+            Projection::Orthographic => radius / (half_fov_tan * aspect_ratio.min(1.0)),
         }
     }
 }
 
+/// The orthographic projection cannot see infinitely far. It also sees this far behind the camera.
+const ORTHOGRAPHIC_DEPTH: f32 = 100.0;
+
 #[derive(Debug, Clone)]
 pub struct Camera {
     pub position: Vec3,
     pub orientation: Quat,
     pub settings: CameraSettings,
+    /// Distance to the point that the camera is looking at
+    pub focus_distance: f32,
 
     view: Mat4,
 }
@@ -39,6 +86,7 @@ impl Camera {
             position,
             orientation,
             settings,
+            focus_distance: 1.0,
             view,
         }
     }
@@ -48,19 +96,49 @@ impl Camera {
         self.view
     }
 
+    /// Uses a reversed Z, where the near plane is at 1 and the far plane is at 0.
     pub fn projection_matrix(&self, size: UVec2) -> Mat4 {
         let aspect_ratio = size.x as f32 / size.y as f32;
 
-        Mat4::perspective_infinite_reverse_rh(
-            self.settings.fov.radians,
-            aspect_ratio,
-            self.settings.z_near,
-        )
+        match self.settings.projection {
+            Projection::Perspective => match self.settings.z_far {
+                None => Mat4::perspective_infinite_reverse_rh(
+                    self.settings.fov.radians,
+                    aspect_ratio,
+                    self.settings.z_near,
+                ),
+                // Swapping near and far reverses the Z
+                Some(z_far) => Mat4::perspective_rh(
+                    self.settings.fov.radians,
+                    aspect_ratio,
+                    z_far,
+                    self.settings.z_near,
+                ),
+            },
+            Projection::Orthographic => {
+                let half_height = self.focus_distance * (self.settings.fov.radians / 2.0).tan();
+                let half_width = half_height * aspect_ratio;
+                let z_far = self
+                    .settings
+                    .z_far
+                    .unwrap_or(self.focus_distance + ORTHOGRAPHIC_DEPTH);
+                // Swapping near and far reverses the Z. Also includes what is behind the camera.
+                Mat4::orthographic_rh(
+                    -half_width,
+                    half_width,
+                    -half_height,
+                    half_height,
+                    z_far,
+                    -ORTHOGRAPHIC_DEPTH,
+                )
+            }
+        }
     }
 
     pub fn update_camera(&mut self, controller: &impl IsCameraController) {
         self.position = controller.position();
         self.orientation = controller.orientation();
+        self.focus_distance = controller.general_controller().distance_to_center;
 
         self.view = calculate_view(self.position, self.orientation);
     }
@@ -83,7 +161,42 @@ impl Camera {
 
 fn calculate_view(position: Vec3, orientation: Quat) -> Mat4 {
     let cam_direction = orientation * Camera::forward();
-    let target = position + cam_direction;
+    // The rotated up vector also works when looking straight down
+    let cam_up = orientation * Camera::up();
+
+    Mat4::look_to_rh(position, cam_direction, cam_up)
+}
 
-    Mat4::look_at_rh(position, target, Camera::up())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn framing_fits_narrow_views() {
+        let settings = CameraSettings::default();
+        let half_fov = settings.fov.radians / 2.0;
+        for aspect_ratio in [0.5, 1.0, 2.0] {
+            let distance = settings.framing_distance(1.0, aspect_ratio);
+            let horizontal_half_fov = (half_fov.tan() * aspect_ratio).atan();
+            let sphere_half_angle = (1.0 / distance).asin();
+            assert!(sphere_half_angle <= half_fov + 1e-5);
+            assert!(sphere_half_angle <= horizontal_half_fov + 1e-5);
+        }
+        assert!(settings.framing_distance(1.0, 0.5) > settings.framing_distance(1.0, 2.0));
+    }
+
+    #[test]
+    fn orthographic_framing_uses_the_half_extents() {
+        let mut camera = Camera::new(CameraSettings {
+            projection: Projection::Orthographic,
+            ..Default::default()
+        });
+        let size = UVec2::new(400, 800);
+        camera.focus_distance = camera.settings.framing_distance(1.0, 0.5);
+        let projected = camera
+            .projection_matrix(size)
+            .project_point3(Vec3::new(1.0, 1.0, -1.0));
+        assert!((projected.x - 1.0).abs() < 1e-5);
+        assert!(projected.y.abs() <= 1.0);
+    }
 }
@@ -1,8 +1,15 @@
 use glam::{Quat, Vec3};
 
-use crate::{input::WindowInputs, renderer::CursorCapture};
+use crate::{
+    aabb::Aabb,
+    input::{ActionMap, WindowInputs},
+    renderer::CursorCapture,
+};
 
-use super::{freecam_controller::FreecamController, orbitcam_controller::OrbitcamController};
+use super::{
+    angle::Angle, camera_path::CameraPath, freecam_controller::FreecamController,
+    orbitcam_controller::OrbitcamController, Camera, CameraSettings,
+};
 
 pub trait IsCameraController {
     fn position(&self) -> Vec3;
@@ -10,6 +17,7 @@ pub trait IsCameraController {
     fn general_controller(&self) -> GeneralController;
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ChosenKind {
     Orbitcam,
     Freecam,
@@ -31,33 +39,117 @@ impl ChosenController {
     fn update(
         &mut self,
         input: &WindowInputs,
+        actions: &ActionMap,
         delta_time: f32,
-        settings: &GeneralControllerSettings,
+        settings: &mut GeneralControllerSettings,
+        cursor_position: Option<Vec3>,
     ) -> CursorCapture {
         match self {
-            ChosenController::Orbitcam(orbitcam) => orbitcam.update(input, delta_time, settings),
-            ChosenController::Freecam(freecam) => freecam.update(input, delta_time, settings),
+            ChosenController::Orbitcam(orbitcam) => {
+                orbitcam.update(input, actions, delta_time, settings, cursor_position)
+            }
+            ChosenController::Freecam(freecam) => {
+                freecam.update(input, actions, delta_time, settings)
+            }
         }
     }
 }
 
-#[derive(Debug, Clone)]
+/// Standard views for diagrams. They look at the same point as before.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViewPreset {
+    /// Looks along the negative Z axis
+    Front,
+    /// Looks down along the negative Y axis, as far as the pitch limits of the controller allow
+    Top,
+    /// Looks along the negative X axis
+    Side,
+    /// Looks at the center from the (1, 1, 1) direction
+    Isometric,
+}
+
+impl ViewPreset {
+    fn yaw_pitch(self) -> (Angle, Angle) {
+        match self {
+            ViewPreset::Front => (Angle::from_degrees(0.0), Angle::from_degrees(0.0)),
+            ViewPreset::Top => (Angle::from_degrees(0.0), Angle::from_degrees(-90.0)),
+            ViewPreset::Side => (Angle::from_degrees(90.0), Angle::from_degrees(0.0)),
+            ViewPreset::Isometric => (
+                Angle::from_degrees(45.0),
+                Angle::new(-(1.0f32 / 3.0f32.sqrt()).asin()),
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct GeneralController {
     pub position: Vec3,
     pub orientation: Quat,
     pub distance_to_center: f32,
 }
 
+impl GeneralController {
+    /// Ignores tiny changes, like the last bit of an inertia that slowly stops.
+    pub fn approx_eq(&self, other: &GeneralController) -> bool {
+        const EPSILON: f32 = 1e-5;
+        self.position.abs_diff_eq(other.position, EPSILON)
+            && self.orientation.abs_diff_eq(other.orientation, EPSILON)
+            && (self.distance_to_center - other.distance_to_center).abs() <= EPSILON
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct GeneralControllerSettings {
+    /// Freecam: Units per second. Scrolling changes it.
     pub fly_speed: f32,
     pub pan_speed: f32,
     pub rotation_sensitivity: f32,
+    pub orbit: OrbitcamSettings,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OrbitMode {
+    /// Keeps the world up axis pointing up, and limits the pitch
+    #[default]
+    Turntable,
+    /// Rotates freely around the center, which can tilt the camera
+    Trackball,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrbitcamSettings {
+    pub mode: OrbitMode,
+    /// Only used by the turntable mode. Negative pitches look down onto the center.
+    pub min_pitch: Angle,
+    pub max_pitch: Angle,
+    /// Zooms towards the point under the cursor, instead of towards the center
+    pub zoom_to_cursor: bool,
+    /// Keeps rotating and panning after letting go. The speed halves every this many seconds.
+    pub inertia: Option<f32>,
+}
+
+impl Default for OrbitcamSettings {
+    fn default() -> Self {
+        Self {
+            mode: OrbitMode::Turntable,
+            min_pitch: Angle::from_degrees(-88.0),
+            max_pitch: Angle::from_degrees(88.0),
+            zoom_to_cursor: true,
+            inertia: None,
+        }
+    }
 }
 
 pub struct CameraController {
     pub settings: GeneralControllerSettings,
     chosen: ChosenController,
+    playback: Option<PathPlayback>,
+}
+
+struct PathPlayback {
+    path: CameraPath,
+    time: f32,
 }
 
 impl CameraController {
@@ -67,11 +159,27 @@ impl CameraController {
         chosen_kind: ChosenKind,
     ) -> Self {
         let chosen = ChosenController::new(controller, chosen_kind);
-        Self { settings, chosen }
+        Self {
+            settings,
+            chosen,
+            playback: None,
+        }
+    }
+
+    /// Keeps the view. `focus_distance` is how far away the scene is in the middle of the screen,
+    /// which the orbit camera then rotates around.
+    pub fn switch_to(&mut self, chosen_kind: ChosenKind, focus_distance: Option<f32>) {
+        let mut controller = self.general_controller();
+        if let Some(focus_distance) = focus_distance {
+            controller.distance_to_center = focus_distance;
+        }
+        self.chosen = ChosenController::new(controller, chosen_kind);
     }
 
-    pub fn switch_to(&mut self, chosen_kind: ChosenKind) {
-        self.chosen = ChosenController::new(self.general_controller(), chosen_kind);
+    /// Jumps to a camera state, and stops any animation.
+    pub fn set_general_controller(&mut self, controller: GeneralController) {
+        self.playback = None;
+        self.chosen = ChosenController::new(controller, self.get_chosen_kind());
     }
 
     pub fn get_chosen_kind(&self) -> ChosenKind {
@@ -81,8 +189,116 @@ impl CameraController {
         }
     }
 
-    pub fn update(&mut self, input: &WindowInputs, delta_time: f32) -> CursorCapture {
-        self.chosen.update(input, delta_time, &self.settings)
+    /// Clicking, scrolling or touching stops a playing animation, and hands the camera back to the user.
+    /// `cursor_position` is the point in the scene under the cursor, if it is known.
+    pub fn update(
+        &mut self,
+        input: &WindowInputs,
+        actions: &ActionMap,
+        delta_time: f32,
+        cursor_position: Option<Vec3>,
+    ) -> CursorCapture {
+        if self.playback.is_some() {
+            let user_input = !input.mouse.held.is_empty()
+                || input.mouse.scroll_delta.y != 0.0
+                || input.touch.is_active();
+            if !user_input {
+                self.advance(delta_time);
+                return CursorCapture::Free;
+            }
+            self.playback = None;
+        }
+        self.chosen.update(
+            input,
+            actions,
+            delta_time,
+            &mut self.settings,
+            cursor_position,
+        )
+    }
+
+    /// Starts playing a path from its beginning.
+    pub fn play(&mut self, path: CameraPath) {
+        self.playback = Some(PathPlayback { path, time: 0.0 });
+        self.seek(0.0);
+    }
+
+    /// An eased transition from the current camera to the target.
+    pub fn fly_to(&mut self, target: &GeneralController, duration: f32) {
+        self.play(CameraPath::fly_to(
+            &self.general_controller(),
+            target,
+            duration,
+        ));
+    }
+
+    /// Flies to a distance where the box fits into the view, without changing the direction.
+    pub fn frame_bounds(&mut self, bounds: &Aabb, camera: &CameraSettings, aspect_ratio: f32) {
+        let controller = self.general_controller();
+        let distance = camera.framing_distance(bounds.radius().max(0.01), aspect_ratio);
+        let target = GeneralController {
+            position: bounds.center() - controller.orientation * (Camera::forward() * distance),
+            orientation: controller.orientation,
+            distance_to_center: distance,
+        };
+        self.fly_to(&target, 0.5);
+    }
+
+    pub fn stop(&mut self) {
+        self.playback = None;
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playback.is_some()
+    }
+
+    /// Moves the camera to an exact point in time of the playing path.
+    /// Offline rendering uses this to get the same frames every time.
+    pub fn seek(&mut self, time: f32) {
+        let Some(playback) = &mut self.playback else {
+            return;
+        };
+        playback.time = time;
+        let controller = playback.path.sample(time).general_controller();
+        let finished = playback.path.is_finished(time);
+        self.chosen = ChosenController::new(controller, self.get_chosen_kind());
+        if finished {
+            self.playback = None;
+        }
+    }
+
+    fn advance(&mut self, delta_time: f32) {
+        if let Some(time) = self.playback.as_ref().map(|playback| playback.time) {
+            self.seek(time + delta_time);
+        }
+    }
+
+    /// A turntable around the point that the camera is looking at.
+    pub fn play_turntable(&mut self, seconds_per_turn: f32) {
+        self.play(CameraPath::turntable(
+            &self.general_controller(),
+            seconds_per_turn,
+        ));
+    }
+
+    /// Rotates the camera around the point that it is looking at.
+    pub fn set_view(&mut self, preset: ViewPreset) {
+        self.playback = None;
+        let (yaw, pitch) = preset.yaw_pitch();
+        match &mut self.chosen {
+            ChosenController::Orbitcam(orbitcam) => {
+                orbitcam.set_pitch_yaw(pitch, yaw, &self.settings);
+                orbitcam.roll = Angle::new(0.0);
+            }
+            ChosenController::Freecam(freecam) => {
+                let controller = freecam.general_controller();
+                let center = controller.position
+                    + controller.orientation * (Camera::forward() * controller.distance_to_center);
+                freecam.set_pitch_yaw(pitch, yaw);
+                freecam.position = center
+                    - freecam.orientation() * (Camera::forward() * controller.distance_to_center);
+            }
+        }
     }
 }
 
@@ -0,0 +1,283 @@
+use glam::{Quat, Vec3};
+
+use super::{angle::Angle, camera_controller::GeneralController, Camera};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Easing {
+    /// Constant speed, which is what turntables need
+    Linear,
+    /// Starts and stops smoothly
+    #[default]
+    EaseInOut,
+}
+
+impl Easing {
+    /// Maps a progress from 0 to 1 onto another progress from 0 to 1.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+/// A camera state that orbits around a center point.
+/// Interpolating these instead of positions and rotations keeps the camera on a circle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CameraKeyframe {
+    /// In seconds since the start of the path
+    pub time: f32,
+    pub center: Vec3,
+    /// Is interpolated as is, so going from 0 to 360 degrees makes a full turn.
+    pub yaw: Angle,
+    pub pitch: Angle,
+    /// Around the viewing direction. Also keeps cameras upright that went past a pole.
+    pub roll: Angle,
+    pub distance: f32,
+}
+
+impl CameraKeyframe {
+    pub fn from_controller(time: f32, controller: &GeneralController) -> Self {
+        let (yaw, pitch, roll) = controller.orientation.to_euler(glam::EulerRot::YXZ);
+        Self {
+            time,
+            center: controller.position
+                + controller.orientation * (Camera::forward() * controller.distance_to_center),
+            yaw: Angle::new(yaw),
+            pitch: Angle::new(pitch),
+            roll: Angle::new(roll),
+            distance: controller.distance_to_center,
+        }
+    }
+
+    pub fn general_controller(&self) -> GeneralController {
+        let orientation = Quat::from_euler(
+            glam::EulerRot::YXZ,
+            self.yaw.radians,
+            self.pitch.radians,
+            self.roll.radians,
+        );
+        GeneralController {
+            position: self.center + orientation * Vec3::new(0.0, 0.0, self.distance),
+            orientation,
+            distance_to_center: self.distance,
+        }
+    }
+}
+
+/// Keyframes that are connected with smooth splines.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CameraPath {
+    keyframes: Vec<CameraKeyframe>,
+    /// Applied to the time of the whole path
+    pub easing: Easing,
+    /// Starts again from the beginning after the last keyframe
+    pub looping: bool,
+}
+
+impl CameraPath {
+    /// Returns `None` when there are no keyframes.
+    pub fn new(mut keyframes: Vec<CameraKeyframe>, easing: Easing, looping: bool) -> Option<Self> {
+        if keyframes.is_empty() {
+            return None;
+        }
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Some(Self {
+            keyframes,
+            easing,
+            looping,
+        })
+    }
+
+    /// A smooth transition that takes the shorter way around.
+    pub fn fly_to(from: &GeneralController, to: &GeneralController, duration: f32) -> Self {
+        let start = CameraKeyframe::from_controller(0.0, from);
+        let mut end = CameraKeyframe::from_controller(duration.max(0.0), to);
+        end.yaw = shorter_way(start.yaw, end.yaw);
+        end.roll = shorter_way(start.roll, end.roll);
+        Self {
+            keyframes: vec![start, end],
+            easing: Easing::EaseInOut,
+            looping: false,
+        }
+    }
+
+    /// One full turn around the center point, at a constant speed.
+    pub fn turntable(controller: &GeneralController, duration: f32) -> Self {
+        let start = CameraKeyframe::from_controller(0.0, controller);
+        let end = CameraKeyframe {
+            time: duration.max(0.0),
+            yaw: start.yaw + Angle::from_degrees(360.0),
+            ..start.clone()
+        };
+        Self {
+            keyframes: vec![start, end],
+            easing: Easing::Linear,
+            looping: true,
+        }
+    }
+
+    pub fn keyframes(&self) -> &[CameraKeyframe] {
+        &self.keyframes
+    }
+
+    /// The time of the last keyframe
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map(|v| v.time).unwrap_or(0.0)
+    }
+
+    /// Is only ever finished when it does not loop.
+    pub fn is_finished(&self, time: f32) -> bool {
+        !self.looping && time >= self.duration()
+    }
+
+    /// Evaluates the path at a time in seconds. Does not depend on any earlier calls.
+    pub fn sample(&self, time: f32) -> CameraKeyframe {
+        let start_time = self.keyframes[0].time;
+        let duration = self.duration() - start_time;
+        if duration <= 0.0 {
+            return CameraKeyframe {
+                time,
+                ..self.keyframes[0].clone()
+            };
+        }
+        let progress = if self.looping {
+            (time - start_time).rem_euclid(duration) / duration
+        } else {
+            (time - start_time) / duration
+        };
+        let path_time = start_time + self.easing.apply(progress) * duration;
+
+        let index = self
+            .keyframes
+            .windows(2)
+            .position(|pair| path_time <= pair[1].time)
+            .unwrap_or(self.keyframes.len() - 2);
+        let (a, b) = (&self.keyframes[index], &self.keyframes[index + 1]);
+        let segment_duration = b.time - a.time;
+        if segment_duration <= 0.0 {
+            return CameraKeyframe { time, ..b.clone() };
+        }
+        let t = ((path_time - a.time) / segment_duration).clamp(0.0, 1.0);
+
+        // A cubic Hermite spline, with Catmull-Rom tangents that take the timing into account
+        let tangent = |i: usize, value: &dyn Fn(&CameraKeyframe) -> Vec3| -> Vec3 {
+            let previous = &self.keyframes[i.saturating_sub(1)];
+            let next = &self.keyframes[(i + 1).min(self.keyframes.len() - 1)];
+            let dt = next.time - previous.time;
+            if dt <= 0.0 {
+                Vec3::ZERO
+            } else {
+                (value(next) - value(previous)) / dt
+            }
+        };
+        let interpolate = |value: &dyn Fn(&CameraKeyframe) -> Vec3| -> Vec3 {
+            let t2 = t * t;
+            let t3 = t2 * t;
+            (2.0 * t3 - 3.0 * t2 + 1.0) * value(a)
+                + (t3 - 2.0 * t2 + t) * segment_duration * tangent(index, value)
+                + (-2.0 * t3 + 3.0 * t2) * value(b)
+                + (t3 - t2) * segment_duration * tangent(index + 1, value)
+        };
+
+        let center = interpolate(&|k| k.center);
+        let angles = interpolate(&|k| Vec3::new(k.yaw.radians, k.pitch.radians, k.roll.radians));
+        // The distance is interpolated logarithmically, like the orbit camera zooms
+        let log_distance = interpolate(&|k| Vec3::splat(k.distance.ln())).x;
+        CameraKeyframe {
+            time,
+            center,
+            yaw: Angle::new(angles.x),
+            pitch: Angle::new(angles.y),
+            roll: Angle::new(angles.z),
+            distance: log_distance.exp(),
+        }
+    }
+}
+
+/// The angle closest to `from` that points in the same direction as `to`.
+fn shorter_way(from: Angle, to: Angle) -> Angle {
+    let difference = (to.radians - from.radians + std::f32::consts::PI)
+        .rem_euclid(std::f32::consts::TAU)
+        - std::f32::consts::PI;
+    Angle::new(from.radians + difference)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keyframe(time: f32, x: f32, yaw_degrees: f32) -> CameraKeyframe {
+        CameraKeyframe {
+            time,
+            center: Vec3::new(x, 0.0, 0.0),
+            yaw: Angle::from_degrees(yaw_degrees),
+            pitch: Angle::from_degrees(-20.0),
+            roll: Angle::new(0.0),
+            distance: 4.0,
+        }
+    }
+
+    #[test]
+    fn passes_through_keyframes() {
+        let path = CameraPath::new(
+            vec![
+                keyframe(2.0, 3.0, 90.0),
+                keyframe(0.0, 0.0, 0.0),
+                keyframe(1.0, 1.0, 45.0),
+            ],
+            Easing::Linear,
+            false,
+        )
+        .unwrap();
+        for (time, x) in [(0.0, 0.0), (1.0, 1.0), (2.0, 3.0)] {
+            let sample = path.sample(time);
+            assert!((sample.center.x - x).abs() < 1e-5);
+            assert!((sample.distance - 4.0).abs() < 1e-5);
+        }
+        assert!(path.is_finished(2.5));
+        assert!((path.sample(5.0).center.x - 3.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn turntable_loops() {
+        let controller = keyframe(0.0, 0.0, 0.0).general_controller();
+        let path = CameraPath::turntable(&controller, 10.0);
+        assert!(!path.is_finished(100.0));
+        let half_turn = path.sample(15.0);
+        assert!((half_turn.yaw.to_degrees() - 180.0).abs() < 1e-3);
+        assert!((half_turn.pitch.to_degrees() + 20.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn fly_to_takes_shorter_way() {
+        let from = keyframe(0.0, 0.0, 170.0).general_controller();
+        let to = keyframe(0.0, 0.0, -170.0).general_controller();
+        let path = CameraPath::fly_to(&from, &to, 1.0);
+        let end = path.sample(1.0);
+        assert!((end.yaw.to_degrees() - 190.0).abs() < 1e-3);
+        assert!((end.center - Vec3::ZERO).length() < 1e-4);
+    }
+
+    #[test]
+    fn fly_to_keeps_rolled_cameras() {
+        let rolled = CameraKeyframe {
+            roll: Angle::from_degrees(30.0),
+            ..keyframe(0.0, 0.0, 10.0)
+        }
+        .general_controller();
+        // Went over the top, so it looks backwards and is upside down
+        let past_pole = GeneralController {
+            orientation: Quat::from_rotation_y(0.5) * Quat::from_rotation_x(-2.0),
+            ..rolled.clone()
+        };
+        for (from, to) in [(&rolled, &past_pole), (&past_pole, &rolled)] {
+            let path = CameraPath::fly_to(from, to, 1.0);
+            let start = path.sample(0.0).general_controller();
+            let end = path.sample(1.0).general_controller();
+            assert!(start.orientation.angle_between(from.orientation) < 1e-3);
+            assert!(end.orientation.angle_between(to.orientation) < 1e-3);
+        }
+    }
+}
@@ -1,7 +1,8 @@
+use crate::{
+    input::{Action, ActionMap, WindowInputs},
+    renderer::CursorCapture,
+};
 use glam::{Quat, Vec2, Vec3};
-use winit::{event::MouseButton, keyboard::KeyCode};
-
-use crate::{input::WindowInputs, renderer::CursorCapture};
 
 use super::{
     angle::Angle,
@@ -13,6 +14,8 @@ pub struct FreecamController {
     pub position: Vec3,
     pub pitch: Angle,
     pub yaw: Angle,
+    /// Only kept for switching back to the orbit camera
+    pub distance_to_center: f32,
 }
 
 impl FreecamController {
@@ -23,23 +26,31 @@ impl FreecamController {
             position: controller.position,
             pitch: Angle::new(pitch),
             yaw: Angle::new(yaw),
+            distance_to_center: controller.distance_to_center,
         }
     }
     pub fn update(
         &mut self,
         input: &WindowInputs,
+        actions: &ActionMap,
         delta_time: f32,
-        settings: &GeneralControllerSettings,
+        settings: &mut GeneralControllerSettings,
     ) -> CursorCapture {
         let mut cursor_capture = CursorCapture::Free;
+        // There is nothing to zoom towards, so scrolling changes the speed instead
+        let scroll = input.mouse.scroll_delta.y as f32;
+        if scroll != 0.0 {
+            settings.fly_speed =
+                (settings.fly_speed * (scroll * 0.002).exp()).clamp(MIN_FLY_SPEED, MAX_FLY_SPEED);
+        }
         let mouse_delta = Vec2::new(input.mouse.motion.0 as f32, input.mouse.motion.1 as f32);
-        if input.mouse.pressed(MouseButton::Right) {
+        if actions.pressed(Action::FreecamLook, input) {
             self.update_orientation(mouse_delta, settings);
             cursor_capture = CursorCapture::LockedAndHidden;
         }
 
-        self.update_position(input_to_direction(input), delta_time, settings);
-        if input.mouse.pressed(MouseButton::Middle) {
+        self.update_position(input_to_direction(input, actions), delta_time, settings);
+        if actions.pressed(Action::FreecamPan, input) {
             self.update_pan_position(mouse_delta, delta_time, settings);
             cursor_capture = CursorCapture::LockedAndHidden;
         }
@@ -65,7 +76,7 @@ impl FreecamController {
         self.position += vertical_movement * settings.pan_speed * delta_time;
     }
 
-    fn set_pitch_yaw(&mut self, new_pitch: Angle, new_yaw: Angle) {
+    pub(super) fn set_pitch_yaw(&mut self, new_pitch: Angle, new_yaw: Angle) {
         const TWO_PI: f32 = std::f32::consts::PI * 2.0;
         let max_pitch = 88f32;
         self.pitch = new_pitch
@@ -89,8 +100,8 @@ impl FreecamController {
     }
 }
 
-// Magic number.
-const FREECAM_DISTANCE_TO_CENTER: f32 = 15.;
+const MIN_FLY_SPEED: f32 = 0.01;
+const MAX_FLY_SPEED: f32 = 1000.0;
 
 impl IsCameraController for FreecamController {
     fn position(&self) -> Vec3 {
@@ -110,33 +121,31 @@ impl IsCameraController for FreecamController {
         GeneralController {
             position: self.position(),
             orientation: self.orientation(),
-            distance_to_center: FREECAM_DISTANCE_TO_CENTER,
+            distance_to_center: self.distance_to_center,
         }
     }
 }
 
-fn input_to_direction(input: &WindowInputs) -> Vec3 {
+fn input_to_direction(input: &WindowInputs, actions: &ActionMap) -> Vec3 {
     let mut direction = Vec3::ZERO;
-    if input.keyboard.pressed_physical(KeyCode::KeyW) {
+    if actions.pressed(Action::MoveForward, input) {
         direction += Camera::forward();
     }
-    if input.keyboard.pressed_physical(KeyCode::KeyS) {
+    if actions.pressed(Action::MoveBackward, input) {
         direction -= Camera::forward();
     }
 
-    if input.keyboard.pressed_physical(KeyCode::KeyD) {
+    if actions.pressed(Action::MoveRight, input) {
         direction += Camera::right();
     }
-    if input.keyboard.pressed_physical(KeyCode::KeyA) {
+    if actions.pressed(Action::MoveLeft, input) {
         direction -= Camera::right();
     }
 
-    if input.keyboard.pressed_physical(KeyCode::Space) {
+    if actions.pressed(Action::MoveUp, input) {
         direction += Camera::up();
     }
-    if input.keyboard.pressed_physical(KeyCode::ShiftLeft)
-        || input.keyboard.pressed_physical(KeyCode::ShiftRight)
-    {
+    if actions.pressed(Action::MoveDown, input) {
         direction -= Camera::up();
     }
     direction
@@ -1,6 +1,10 @@
 mod angle;
+mod bookmark;
 mod camera_and_settings;
 pub mod camera_controller;
+pub mod camera_path;
 pub mod freecam_controller;
 pub mod orbitcam_controller;
-pub use camera_and_settings::{Camera, CameraSettings};
+pub use angle::Angle;
+pub use bookmark::CameraBookmark;
+pub use camera_and_settings::{Camera, CameraSettings, Projection};
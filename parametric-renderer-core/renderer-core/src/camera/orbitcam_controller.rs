@@ -1,11 +1,15 @@
 use glam::{Quat, Vec2, Vec3};
-use winit::event::MouseButton;
 
-use crate::{input::WindowInputs, renderer::CursorCapture};
+use crate::{
+    input::{Action, ActionMap, WindowInputs},
+    renderer::CursorCapture,
+};
 
 use super::{
     angle::Angle,
-    camera_controller::{GeneralController, GeneralControllerSettings, IsCameraController},
+    camera_controller::{
+        GeneralController, GeneralControllerSettings, IsCameraController, OrbitMode,
+    },
     Camera,
 };
 
@@ -20,11 +24,21 @@ impl LogarithmicDistance {
     }
 }
 
+/// In mouse pixels per second
+#[derive(Default)]
+struct OrbitVelocity {
+    rotation: Vec2,
+    pan: Vec2,
+}
+
 pub struct OrbitcamController {
     pub center: Vec3,
     pub pitch: Angle,
     pub yaw: Angle,
+    /// Only the trackball mode tilts the camera
+    pub roll: Angle,
     logarithmic_distance: LogarithmicDistance,
+    velocity: OrbitVelocity,
 }
 
 impl OrbitcamController {
@@ -32,65 +46,152 @@ impl OrbitcamController {
         let center = controller.position
             + controller.orientation * (Camera::forward() * controller.distance_to_center);
 
-        let (yaw, pitch, _) = controller.orientation.to_euler(glam::EulerRot::YXZ);
+        let (yaw, pitch, roll) = controller.orientation.to_euler(glam::EulerRot::YXZ);
 
         Self {
             center,
             pitch: Angle::new(pitch),
             yaw: Angle::new(yaw),
+            roll: Angle::new(roll),
             logarithmic_distance: LogarithmicDistance::new(controller.distance_to_center),
+            velocity: OrbitVelocity::default(),
         }
     }
+
+    /// `cursor_position` is the point in the scene under the cursor, which zooming moves towards.
     pub fn update(
         &mut self,
         input: &WindowInputs,
+        actions: &ActionMap,
         delta_time: f32,
         settings: &GeneralControllerSettings,
+        cursor_position: Option<Vec3>,
     ) -> CursorCapture {
         let mut cursor_capture = CursorCapture::Free;
         let mouse_delta = Vec2::new(input.mouse.motion.0 as f32, input.mouse.motion.1 as f32);
-        if input.mouse.pressed(MouseButton::Left) {
-            self.update_orientation(mouse_delta, settings);
+        let mut rotation = Vec2::ZERO;
+        let mut pan = Vec2::ZERO;
+        let mut dragging = false;
+        if actions.pressed(Action::OrbitRotate, input) {
+            rotation += mouse_delta;
+            dragging = true;
             cursor_capture = CursorCapture::LockedAndHidden;
         }
-
-        if input.mouse.pressed(MouseButton::Right) {
-            self.update_pan_position(mouse_delta, delta_time, settings);
+        if actions.pressed(Action::OrbitPan, input) {
+            pan += mouse_delta;
+            dragging = true;
             cursor_capture = CursorCapture::LockedAndHidden;
         }
+        // Independent of the frame rate, unlike the dragging
+        let mut zoom = -(input.mouse.scroll_delta.y as f32) * 0.002;
 
-        self.logarithmic_distance.0 +=
-            -1.0 * (input.mouse.scroll_delta.y as f32) * 0.1 * delta_time;
+        // One finger rotates, two fingers pan and pinch to zoom. Trackpad gestures work the same way.
+        let touch = &input.touch;
+        if let Some((x, y)) = touch.one_finger_drag() {
+            rotation += Vec2::new(x as f32, y as f32);
+        }
+        if let Some(gesture) = touch.two_finger_gesture() {
+            let (x, y) = gesture.pan;
+            pan += Vec2::new(x as f32, y as f32);
+            // Moving the fingers twice as far apart halves the distance
+            zoom -= gesture.pinch_scale.ln() as f32;
+        }
+        pan += Vec2::new(touch.pan_delta.x as f32, touch.pan_delta.y as f32);
+        zoom -= touch.pinch_delta as f32;
+        if touch.rotation_delta != 0.0 {
+            self.set_pitch_yaw(
+                self.pitch,
+                self.yaw + Angle::from_degrees(touch.rotation_delta as f32),
+                settings,
+            );
+        }
+
+        // Trackpad gestures do not have a start and an end, so any pan counts
+        dragging |= !touch.fingers.is_empty() || pan != Vec2::ZERO;
+        match settings.orbit.inertia {
+            Some(half_life) if !dragging => {
+                rotation += self.velocity.rotation * delta_time;
+                pan += self.velocity.pan * delta_time;
+                let decay = 0.5f32.powf(delta_time / half_life.max(0.001));
+                self.velocity.rotation *= decay;
+                self.velocity.pan *= decay;
+            }
+            Some(_) if delta_time > 0.0 => {
+                self.velocity.rotation = rotation / delta_time;
+                self.velocity.pan = pan / delta_time;
+            }
+            _ => self.velocity = OrbitVelocity::default(),
+        }
+
+        if rotation != Vec2::ZERO {
+            self.update_orientation(rotation, settings);
+        }
+        self.update_pan_position(pan, settings);
+        if zoom != 0.0 {
+            let target = cursor_position.filter(|_| settings.orbit.zoom_to_cursor);
+            self.zoom(zoom, target);
+        }
 
         cursor_capture
     }
 
     fn update_orientation(&mut self, mouse_delta: Vec2, settings: &GeneralControllerSettings) {
-        self.set_pitch_yaw(
-            self.pitch - Angle::new(mouse_delta.y * settings.rotation_sensitivity),
-            self.yaw - Angle::new(mouse_delta.x * settings.rotation_sensitivity),
-        );
+        let delta = mouse_delta * settings.rotation_sensitivity;
+        match settings.orbit.mode {
+            OrbitMode::Turntable => {
+                self.roll = Angle::new(0.0);
+                self.set_pitch_yaw(
+                    self.pitch - Angle::new(delta.y),
+                    self.yaw - Angle::new(delta.x),
+                    settings,
+                );
+            }
+            OrbitMode::Trackball => {
+                // Rotates around the axes of the camera, instead of around the world up axis
+                let orientation = self.orientation()
+                    * Quat::from_rotation_y(-delta.x)
+                    * Quat::from_rotation_x(-delta.y);
+                let (yaw, pitch, roll) = orientation.normalize().to_euler(glam::EulerRot::YXZ);
+                self.yaw = Angle::new(yaw);
+                self.pitch = Angle::new(pitch);
+                self.roll = Angle::new(roll);
+            }
+        }
     }
 
-    fn set_pitch_yaw(&mut self, new_pitch: Angle, new_yaw: Angle) {
+    /// Keeps the pitch within the limits of the turntable mode.
+    pub(super) fn set_pitch_yaw(
+        &mut self,
+        new_pitch: Angle,
+        new_yaw: Angle,
+        settings: &GeneralControllerSettings,
+    ) {
         const TWO_PI: f32 = std::f32::consts::PI * 2.0;
-        let max_pitch = 88f32;
-        self.pitch = new_pitch
-            .min(Angle::from_degrees(0.))
-            .max(Angle::from_degrees(-max_pitch));
+        if settings.orbit.mode == OrbitMode::Turntable {
+            self.pitch = new_pitch
+                .min(settings.orbit.max_pitch)
+                .max(settings.orbit.min_pitch);
+        } else {
+            self.pitch = new_pitch;
+        }
         self.yaw = Angle::new(new_yaw.radians.rem_euclid(TWO_PI));
     }
 
-    fn update_pan_position(
-        &mut self,
-        direction: Vec2,
-        delta_time: f32,
-        settings: &GeneralControllerSettings,
-    ) {
+    /// Moves the center by the same amount on the screen, no matter how far away it is.
+    fn update_pan_position(&mut self, direction: Vec2, settings: &GeneralControllerSettings) {
+        let scale = settings.pan_speed * self.logarithmic_distance.distance() * 0.004;
         let horizontal_movement = self.orientation() * (Camera::right() * direction.x * -1.0);
         let vertical_movement = self.orientation() * (Camera::up() * direction.y * 1.0);
-        self.center += horizontal_movement * settings.pan_speed * delta_time;
-        self.center += vertical_movement * settings.pan_speed * delta_time;
+        self.center += (horizontal_movement + vertical_movement) * scale;
+    }
+
+    /// Changes the logarithmic distance. With a target, the target stays at the same spot on the screen.
+    fn zoom(&mut self, amount: f32, target: Option<Vec3>) {
+        if let Some(target) = target {
+            // Scaling everything around the target keeps the viewing direction
+            self.center = target + (self.center - target) * amount.exp();
+        }
+        self.logarithmic_distance.0 += amount;
     }
 }
 
@@ -104,7 +205,7 @@ impl IsCameraController for OrbitcamController {
             glam::EulerRot::YXZ,
             self.yaw.radians,
             self.pitch.radians,
-            0.0,
+            self.roll.radians,
         )
     }
 
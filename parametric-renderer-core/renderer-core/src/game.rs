@@ -1,17 +1,21 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, path::Path, sync::Arc};
 
-use glam::{Vec2, Vec3};
-use web_time::Instant;
+use glam::{UVec2, Vec2, Vec3, Vec4};
 
 use crate::{
+    aabb::Aabb,
     camera::{
         camera_controller::{
             CameraController, ChosenKind, GeneralController, GeneralControllerSettings,
+            OrbitcamSettings, ViewPreset,
         },
-        Camera, CameraSettings,
+        Camera, CameraBookmark, CameraSettings, Projection,
+    },
+    input::{Action, ActionMap, WindowInputs},
+    renderer::{
+        frame_counter::{FrameCounter, FrameTime, TimeSource},
+        CursorCapture, WindowCursorCapture,
     },
-    input::WindowInputs,
-    renderer::{frame_counter::Seconds, CursorCapture, WindowCursorCapture},
     transform::Transform,
 };
 
@@ -20,6 +24,159 @@ pub struct ProfilerSettings {
     pub gpu: bool,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct AntiAliasingSettings {
+    /// MSAA samples per pixel. 1 disables MSAA, and 4 is supported everywhere.
+    pub msaa_samples: u32,
+    /// Post processing anti-aliasing, which also smooths out the edges within a surface.
+    pub fxaa: bool,
+}
+
+impl Default for AntiAliasingSettings {
+    fn default() -> Self {
+        Self {
+            msaa_samples: 1,
+            fxaa: false,
+        }
+    }
+}
+
+/// Screen space ambient occlusion darkens the ambient light in creases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AmbientOcclusion {
+    #[default]
+    Off,
+    Low,
+    High,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ToneMappingCurve {
+    #[default]
+    Aces,
+    AgX,
+    Reinhard,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToneMappingSettings {
+    pub curve: ToneMappingCurve,
+    /// In stops. Increasing it by 1 doubles the brightness of the scene.
+    pub exposure: f32,
+}
+
+impl Default for ToneMappingSettings {
+    fn default() -> Self {
+        Self {
+            curve: ToneMappingCurve::default(),
+            exposure: 0.0,
+        }
+    }
+}
+
+/// What is drawn behind the models. Colors are in linear sRGB.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Background {
+    /// Shown exactly, without tone mapping
+    Solid(Vec3),
+    /// From the top to the bottom of the screen, without tone mapping
+    Gradient { top: Vec3, bottom: Vec3 },
+    /// An equirectangular HDR image, which gets tone mapped like the scene
+    Skybox(TextureId),
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Background::Solid(Vec3::new(0.1, 0.2, 0.3))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GroundPlaneMode {
+    #[default]
+    Grid,
+    /// Only draws the X and Z axes
+    Axes,
+}
+
+/// The spacing and the fade distance grow in powers of two when the camera zooms out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroundPlaneSettings {
+    pub visible: bool,
+    pub mode: GroundPlaneMode,
+    pub height: f32,
+    /// Distance between two minor lines
+    pub grid_spacing: f32,
+    /// Every nth line is a major line, 0 disables them
+    pub major_lines: u32,
+    /// Colors the X axis red and the Z axis blue
+    pub axis_colors: bool,
+    /// Distance from the origin where the grid has faded out
+    pub fade_distance: f32,
+}
+
+impl Default for GroundPlaneSettings {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            mode: GroundPlaneMode::Grid,
+            height: 0.0,
+            grid_spacing: 1.0,
+            major_lines: 10,
+            axis_colors: true,
+            fade_distance: 40.0,
+        }
+    }
+}
+
+impl GroundPlaneSettings {
+    /// The shader divides by the grid spacing and the fade distance.
+    pub fn clamped(self) -> Self {
+        Self {
+            grid_spacing: self.grid_spacing.max(0.0001),
+            fade_distance: self.fade_distance.max(0.0001),
+            ..self
+        }
+    }
+}
+
+/// A light that fades out towards its range, like the `KHR_lights_punctual` point lights of glTF.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PointLight {
+    pub position: Vec3,
+    /// Distance where the light has faded out
+    pub range: f32,
+    /// In linear sRGB
+    pub color: Vec3,
+    pub intensity: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LightingSettings {
+    /// Light that reaches every surface, in linear sRGB
+    pub ambient: Vec3,
+    /// Only the first [`LightingSettings::MAX_POINT_LIGHTS`] are used
+    pub points: Vec<PointLight>,
+}
+
+impl LightingSettings {
+    pub const MAX_POINT_LIGHTS: usize = 16;
+}
+
+impl Default for LightingSettings {
+    fn default() -> Self {
+        Self {
+            ambient: Vec3::splat(0.1),
+            points: vec![PointLight {
+                position: Vec3::new(0.0, 4.0, 2.0),
+                range: 40.0,
+                color: Vec3::ONE,
+                intensity: 6.0,
+            }],
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct ModelInfo {
     pub id: String,
@@ -27,6 +184,11 @@ pub struct ModelInfo {
     pub material_info: MaterialInfo,
     pub shader_id: ShaderId,
     pub instance_count: u32,
+    /// Values for the `struct Parameters` of the shader, by member name.
+    /// Scalars only use the first component.
+    pub parameters: HashMap<String, Vec4>,
+    /// Texture that the shader can sample with `user_texture` and `user_sampler`.
+    pub texture_id: Option<TextureId>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -35,6 +197,14 @@ pub struct MaterialInfo {
     pub emissive: Vec3,
     pub roughness: f32,
     pub metallic: f32,
+    /// 1.0 is fully opaque. Anything below is rendered in a separate, sorted pass.
+    pub opacity: f32,
+    /// Multiplies the color. Stored in sRGB.
+    pub albedo_texture: Option<TextureId>,
+    /// Multiplies the roughness with its green channel.
+    pub roughness_texture: Option<TextureId>,
+    /// A tangent space normal map.
+    pub normal_texture: Option<TextureId>,
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
@@ -46,16 +216,49 @@ pub struct ShaderInfo {
     pub code: String,
 }
 
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct TextureId(pub String);
+
+#[derive(Clone)]
+pub struct TextureInfo {
+    pub width: u32,
+    pub height: u32,
+    /// RGBA values, row by row. Not gamma corrected.
+    pub data: Vec<f32>,
+}
+
 pub struct GameRes {
     pub camera_controller: CameraController,
     pub models: Vec<ModelInfo>,
+    /// In model space, by model ID. Filled in by the renderer, and missing for models that it has not evaluated yet.
+    pub model_bounds: HashMap<String, Aabb>,
+    /// The point in the scene under the cursor. Filled in by the renderer.
+    pub cursor_world_position: Option<Vec3>,
+    /// The point in the scene in the middle of the screen. Filled in by the renderer.
+    pub center_world_position: Option<Vec3>,
+    /// In physical pixels. Filled in by the renderer.
+    pub surface_size: UVec2,
     pub shaders: HashMap<ShaderId, ShaderInfo>,
-    last_update_instant: Option<Instant>,
+    /// Textures that no renderer has uploaded yet. The renderer takes them, so that the pixels are not kept twice.
+    pending_textures: HashMap<TextureId, TextureInfo>,
+    frame_counter: FrameCounter,
+    /// The time of the current frame, which is used by the camera and by the shaders
+    pub time: FrameTime,
     pub camera: Camera,
     pub mouse: Vec2,
     pub mouse_held: bool,
     pub cursor_capture: WindowCursorCapture,
     pub profiler_settings: ProfilerSettings,
+    pub anti_aliasing: AntiAliasingSettings,
+    pub tone_mapping: ToneMappingSettings,
+    pub ambient_occlusion: AmbientOcclusion,
+    pub lighting: LightingSettings,
+    pub background: Background,
+    pub ground_plane: GroundPlaneSettings,
+    /// Which keys and mouse buttons control the camera and the application
+    pub actions: ActionMap,
+    /// Saved viewpoints. The number keys fly to the ones with a slot.
+    pub bookmarks: Vec<CameraBookmark>,
     pub lod_stage: Option<Arc<dyn Fn(&ShaderId, &str) + 'static>>,
 }
 
@@ -72,6 +275,7 @@ impl GameRes {
                 fly_speed: 5.0,
                 pan_speed: 1.0,
                 rotation_sensitivity: 0.01,
+                orbit: OrbitcamSettings::default(),
             },
             ChosenKind::Freecam,
         );
@@ -80,12 +284,26 @@ impl GameRes {
             camera,
             camera_controller,
             models: vec![],
+            model_bounds: HashMap::new(),
+            cursor_world_position: None,
+            center_world_position: None,
+            surface_size: UVec2::new(1, 1),
             shaders: HashMap::new(),
-            last_update_instant: None,
+            pending_textures: HashMap::new(),
+            frame_counter: FrameCounter::new(),
+            time: FrameTime::default(),
             mouse: Vec2::ZERO,
             mouse_held: false,
             cursor_capture: WindowCursorCapture::Free,
             profiler_settings: ProfilerSettings::default(),
+            anti_aliasing: AntiAliasingSettings::default(),
+            tone_mapping: ToneMappingSettings::default(),
+            ambient_occlusion: AmbientOcclusion::default(),
+            lighting: LightingSettings::default(),
+            background: Background::default(),
+            ground_plane: GroundPlaneSettings::default(),
+            actions: ActionMap::default(),
+            bookmarks: Vec::new(),
             lod_stage: None,
         }
     }
@@ -102,18 +320,202 @@ impl GameRes {
         self.shaders.remove(shader_id);
     }
 
-    pub fn update(&mut self, inputs: &WindowInputs) {
-        let now = Instant::now();
-        if let Some(last_update_instant) = self.last_update_instant {
-            let delta = Seconds((now - last_update_instant).as_secs_f32());
-            self.cursor_capture = match self.camera_controller.update(inputs, delta.0) {
-                CursorCapture::Free => WindowCursorCapture::Free,
-                CursorCapture::LockedAndHidden => {
-                    WindowCursorCapture::LockedAndHidden(inputs.mouse.position)
-                }
-            };
+    /// Keeps the texture until a renderer takes it with [`GameRes::take_pending_textures`].
+    pub fn set_texture(&mut self, texture_id: TextureId, info: TextureInfo) {
+        self.pending_textures.insert(texture_id, info);
+    }
+
+    pub fn remove_texture(&mut self, texture_id: &TextureId) {
+        self.pending_textures.remove(texture_id);
+    }
+
+    /// Hands the textures over to a renderer, which keeps the only copy on the GPU.
+    pub fn take_pending_textures(&mut self) -> HashMap<TextureId, TextureInfo> {
+        std::mem::take(&mut self.pending_textures)
+    }
+
+    /// Loads a PNG or HDR file, and uses its path as the ID.
+    pub fn load_texture(&mut self, path: impl AsRef<Path>) -> anyhow::Result<TextureId> {
+        let path = path.as_ref();
+        let texture_id = TextureId(path.to_string_lossy().into_owned());
+        self.set_texture(texture_id.clone(), TextureInfo::load(path)?);
+        Ok(texture_id)
+    }
+
+    /// The bounds of a model in world space.
+    pub fn model_world_bounds(&self, id: &str) -> Option<Aabb> {
+        let model = self.models.iter().find(|model| model.id == id)?;
+        let bounds = self.model_bounds.get(id)?;
+        Some(bounds.transform(model.transform.to_matrix()))
+    }
+
+    /// Moves the camera so that the model fills the view. Returns `false` when its bounds are not known yet.
+    pub fn frame_model(&mut self, id: &str) -> bool {
+        let Some(bounds) = self.model_world_bounds(id) else {
+            return false;
+        };
+        self.camera_controller
+            .frame_bounds(&bounds, &self.camera.settings, self.aspect_ratio());
+        true
+    }
+
+    /// Moves the camera so that every model with known bounds is in view.
+    pub fn frame_all(&mut self) -> bool {
+        let Some(bounds) = self
+            .models
+            .iter()
+            .filter_map(|model| self.model_world_bounds(&model.id))
+            .reduce(|a, b| a.union(&b))
+        else {
+            return false;
+        };
+        self.camera_controller
+            .frame_bounds(&bounds, &self.camera.settings, self.aspect_ratio());
+        true
+    }
+
+    fn aspect_ratio(&self) -> f32 {
+        self.surface_size.x.max(1) as f32 / self.surface_size.y.max(1) as f32
+    }
+
+    pub fn camera_settings(&self) -> &CameraSettings {
+        &self.camera.settings
+    }
+
+    /// Clamps the field of view and the clipping planes to values that work.
+    pub fn set_camera_settings(&mut self, settings: CameraSettings) {
+        self.camera.settings = settings.clamped();
+    }
+
+    pub fn controller_settings(&self) -> &GeneralControllerSettings {
+        &self.camera_controller.settings
+    }
+
+    pub fn set_controller_settings(&mut self, settings: GeneralControllerSettings) {
+        self.camera_controller.settings = settings;
+    }
+
+    /// Keeps the view. The orbit camera then rotates around the point in the middle of the screen.
+    pub fn set_camera_kind(&mut self, kind: ChosenKind) {
+        if kind == self.camera_controller.get_chosen_kind() {
+            return;
+        }
+        let focus_distance = match kind {
+            ChosenKind::Orbitcam => self.center_world_position.and_then(|center| {
+                // The probed point lags a few frames behind, so only its depth along the current view is used
+                let distance = (center - self.camera.position)
+                    .dot(self.camera.orientation * Camera::forward());
+                (distance > self.camera.settings.z_near).then_some(distance)
+            }),
+            ChosenKind::Freecam => None,
+        };
+        self.camera_controller.switch_to(kind, focus_distance);
+    }
+
+    /// Bookmarks the current camera. Replaces a bookmark with the same name, and keeps its number key.
+    pub fn save_bookmark(&mut self, name: String) {
+        let mut bookmark =
+            CameraBookmark::new(name, &self.camera_controller, &self.camera.settings);
+        match self.bookmarks.iter_mut().find(|v| v.name == bookmark.name) {
+            Some(existing) => {
+                bookmark.slot = existing.slot;
+                *existing = bookmark;
+            }
+            None => self.bookmarks.push(bookmark),
+        }
+    }
+
+    /// Bookmarks the current camera on a number key, and keeps the name of the bookmark that was there.
+    pub fn save_bookmark_slot(&mut self, slot: u8) {
+        let existing = self.bookmarks.iter().position(|v| v.slot == Some(slot));
+        let name = match existing {
+            Some(index) => self.bookmarks[index].name.clone(),
+            None => self.unused_bookmark_name(&format!("Bookmark {slot}")),
+        };
+        let mut bookmark =
+            CameraBookmark::new(name, &self.camera_controller, &self.camera.settings);
+        bookmark.slot = Some(slot);
+        match existing {
+            Some(index) => self.bookmarks[index] = bookmark,
+            None => self.bookmarks.push(bookmark),
+        }
+    }
+
+    /// Returns `false` when there is no bookmark on that number key.
+    pub fn go_to_bookmark_slot(&mut self, slot: u8) -> bool {
+        let Some(bookmark) = self
+            .bookmarks
+            .iter()
+            .find(|v| v.slot == Some(slot))
+            .cloned()
+        else {
+            return false;
+        };
+        self.fly_to_bookmark(&bookmark);
+        true
+    }
+
+    /// Returns `false` when there is no bookmark with that name.
+    pub fn go_to_bookmark(&mut self, name: &str) -> bool {
+        let Some(bookmark) = self.bookmarks.iter().find(|v| v.name == name).cloned() else {
+            return false;
+        };
+        self.fly_to_bookmark(&bookmark);
+        true
+    }
+
+    pub fn remove_bookmark(&mut self, name: &str) -> bool {
+        let count = self.bookmarks.len();
+        self.bookmarks.retain(|v| v.name != name);
+        self.bookmarks.len() != count
+    }
+
+    /// Appends a number when a bookmark already has the name, like `Bookmark 1 (2)`.
+    fn unused_bookmark_name(&self, name: &str) -> String {
+        let taken = |name: &str| self.bookmarks.iter().any(|v| v.name == name);
+        if !taken(name) {
+            return name.to_string();
         }
-        self.last_update_instant = Some(now);
+        (2..)
+            .map(|i| format!("{name} ({i})"))
+            .find(|name| !taken(name))
+            .unwrap()
+    }
+
+    /// Switches to the controller and the field of view of the bookmark, and flies there.
+    fn fly_to_bookmark(&mut self, bookmark: &CameraBookmark) {
+        self.camera_controller.switch_to(bookmark.kind, None);
+        self.camera_controller.fly_to(&bookmark.camera, 0.5);
+        self.camera.settings.fov = bookmark.fov;
+    }
+
+    /// Starts counting the frames from 0 again.
+    pub fn set_time_source(&mut self, time_source: TimeSource) {
+        self.frame_counter = FrameCounter::with_time_source(time_source);
+    }
+
+    pub fn update(&mut self, inputs: &WindowInputs) {
+        let time = self.frame_counter.new_frame();
+        self.update_with_time(inputs, time);
+    }
+
+    /// Like `update`, but the frame time is given instead of coming from the time source.
+    /// Replaying recorded inputs uses this.
+    pub fn update_with_time(&mut self, inputs: &WindowInputs, time: FrameTime) {
+        self.time = time;
+        self.cursor_capture = match self.camera_controller.update(
+            inputs,
+            &self.actions,
+            self.time.delta.0,
+            self.cursor_world_position,
+        ) {
+            CursorCapture::Free => WindowCursorCapture::Free,
+            CursorCapture::LockedAndHidden => {
+                WindowCursorCapture::LockedAndHidden(inputs.mouse.position)
+            }
+        };
+        self.update_view_shortcuts(inputs);
+        self.update_bookmark_shortcuts(inputs);
         self.camera.update_camera(&self.camera_controller);
         self.mouse = Vec2::new(
             inputs.mouse.position.x as f32,
@@ -121,4 +523,100 @@ impl GameRes {
         );
         self.mouse_held = inputs.mouse.pressed(winit::event::MouseButton::Left);
     }
+
+    fn update_bookmark_shortcuts(&mut self, inputs: &WindowInputs) {
+        for slot in 1..=9 {
+            if self
+                .actions
+                .just_pressed(Action::SaveBookmark(slot), inputs)
+            {
+                self.save_bookmark_slot(slot);
+            } else if self
+                .actions
+                .just_pressed(Action::RecallBookmark(slot), inputs)
+            {
+                self.go_to_bookmark_slot(slot);
+            }
+        }
+    }
+
+    fn update_view_shortcuts(&mut self, inputs: &WindowInputs) {
+        let presets = [
+            (Action::ViewFront, ViewPreset::Front),
+            (Action::ViewSide, ViewPreset::Side),
+            (Action::ViewTop, ViewPreset::Top),
+            (Action::ViewIsometric, ViewPreset::Isometric),
+        ];
+        for (action, preset) in presets {
+            if self.actions.just_pressed(action, inputs) {
+                self.camera_controller.set_view(preset);
+            }
+        }
+        if self.actions.just_pressed(Action::ToggleProjection, inputs) {
+            self.camera.settings.projection = match self.camera.settings.projection {
+                Projection::Perspective => Projection::Orthographic,
+                Projection::Orthographic => Projection::Perspective,
+            };
+        }
+        if self.actions.just_pressed(Action::FrameAll, inputs) {
+            self.frame_all();
+        }
+        if self.actions.just_pressed(Action::ToggleController, inputs) {
+            self.set_camera_kind(match self.camera_controller.get_chosen_kind() {
+                ChosenKind::Orbitcam => ChosenKind::Freecam,
+                ChosenKind::Freecam => ChosenKind::Orbitcam,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::camera::{camera_controller::IsCameraController, Angle};
+
+    use super::*;
+
+    #[test]
+    fn bookmark_slots_are_stable() {
+        let mut game = GameRes::new();
+        game.save_bookmark_slot(5);
+        assert_eq!(game.bookmarks[0].slot, Some(5));
+        assert!(!game.go_to_bookmark_slot(1));
+        assert!(game.go_to_bookmark_slot(5));
+
+        // Saving again replaces the bookmark and keeps its name
+        game.bookmarks[0].name = "Top".into();
+        game.save_bookmark_slot(5);
+        assert_eq!(game.bookmarks.len(), 1);
+        assert_eq!(game.bookmarks[0].name, "Top");
+
+        // Saving by name keeps the slot
+        game.save_bookmark("Top".into());
+        assert_eq!(game.bookmarks[0].slot, Some(5));
+    }
+
+    #[test]
+    fn view_presets_stay_within_the_pitch_limits() {
+        let mut game = GameRes::new();
+        for kind in [ChosenKind::Orbitcam, ChosenKind::Freecam] {
+            game.set_camera_kind(kind);
+            game.camera_controller.set_view(ViewPreset::Top);
+            let (_, pitch, _) = game
+                .camera_controller
+                .orientation()
+                .to_euler(glam::EulerRot::YXZ);
+            assert!(pitch >= Angle::from_degrees(-88.0).radians - 1e-5);
+            assert!(pitch < Angle::from_degrees(-80.0).radians);
+        }
+    }
+
+    #[test]
+    fn generated_bookmark_names_do_not_collide() {
+        let mut game = GameRes::new();
+        game.save_bookmark("Bookmark 2".into());
+        game.save_bookmark_slot(2);
+        assert_eq!(game.bookmarks.len(), 2);
+        assert_eq!(game.bookmarks[0].slot, None);
+        assert_eq!(game.bookmarks[1].name, "Bookmark 2 (2)");
+    }
 }
@@ -0,0 +1,94 @@
+use std::{collections::HashMap, path::Path};
+
+use glam::{Vec3, Vec4};
+use nanoserde::DeJson;
+
+use crate::game::{MaterialInfo, ModelInfo, ShaderId, ShaderInfo, TextureId, TextureInfo};
+
+const GEOMETRY_IMAGE_SHADER_CODE: &'static str = include_str!("../../shaders/GeometryImage.wgsl");
+
+/// The bounds that mesh2gim writes next to the image.
+#[derive(DeJson)]
+struct SerializeAABB {
+    min: SerializeVec3,
+    max: SerializeVec3,
+}
+
+#[derive(DeJson)]
+struct SerializeVec3 {
+    x: f32,
+    y: f32,
+    z: f32,
+}
+
+impl From<SerializeVec3> for Vec3 {
+    fn from(v: SerializeVec3) -> Self {
+        Vec3::new(v.x, v.y, v.z)
+    }
+}
+
+/// A mesh that was converted to an image of positions, normalized to its bounding box.
+pub struct GeometryImage {
+    pub texture: TextureInfo,
+    pub bounds_min: Vec3,
+    pub bounds_max: Vec3,
+}
+
+impl GeometryImage {
+    /// Decodes the PNG and the JSON bounds that mesh2gim writes.
+    pub fn decode(image: &[u8], bounds_json: &str) -> anyhow::Result<Self> {
+        let bounds = SerializeAABB::deserialize_json(bounds_json)
+            .map_err(|e| anyhow::anyhow!("Failed to parse geometry image bounds: {e}"))?;
+        Ok(Self {
+            texture: TextureInfo::decode(image)?,
+            bounds_min: bounds.min.into(),
+            bounds_max: bounds.max.into(),
+        })
+    }
+
+    /// Loads `name.png` together with its `name.json`.
+    pub fn load(image_path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let image_path = image_path.as_ref();
+        let image = std::fs::read(image_path)?;
+        let bounds_json = std::fs::read_to_string(image_path.with_extension("json"))?;
+        Self::decode(&image, &bounds_json)
+    }
+
+    /// The built-in shader that all geometry images share.
+    pub fn shader_id() -> ShaderId {
+        ShaderId("GeometryImage.wgsl".into())
+    }
+
+    pub fn shader_info() -> ShaderInfo {
+        ShaderInfo {
+            label: "GeometryImage".into(),
+            code: GEOMETRY_IMAGE_SHADER_CODE.into(),
+        }
+    }
+
+    /// The values for the `struct Parameters` of the built-in shader.
+    pub fn parameters(&self) -> HashMap<String, Vec4> {
+        HashMap::from([
+            ("bounds_min".to_string(), self.bounds_min.extend(0.0)),
+            ("bounds_max".to_string(), self.bounds_max.extend(0.0)),
+        ])
+    }
+
+    /// A model that renders this geometry image, once its texture is set with `texture_id`.
+    pub fn model_info(
+        &self,
+        id: String,
+        texture_id: TextureId,
+        material_info: MaterialInfo,
+    ) -> ModelInfo {
+        ModelInfo {
+            id,
+            transform: Default::default(),
+            material_info,
+            shader_id: Self::shader_id(),
+            instance_count: 1,
+            parameters: self.parameters(),
+            texture_id: Some(texture_id),
+        }
+    }
+}
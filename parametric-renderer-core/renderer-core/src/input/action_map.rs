@@ -0,0 +1,525 @@
+use std::{collections::HashMap, fmt};
+
+use winit::{event::MouseButton, keyboard::KeyCode};
+
+use super::WindowInputs;
+
+/// Something that the user can do, independently of which keys or buttons do it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    MoveForward,
+    MoveBackward,
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    /// Freecam: Look around while held
+    FreecamLook,
+    /// Freecam: Move sideways while held
+    FreecamPan,
+    /// Orbitcam: Rotate around the center while held
+    OrbitRotate,
+    /// Orbitcam: Move the center while held
+    OrbitPan,
+    ViewFront,
+    ViewSide,
+    ViewTop,
+    ViewIsometric,
+    ToggleProjection,
+    /// Switches between the orbit camera and the free camera
+    ToggleController,
+    FrameAll,
+    /// Flies to the bookmark in a slot from 1 to 9
+    RecallBookmark(u8),
+    /// Saves the camera into a bookmark slot from 1 to 9
+    SaveBookmark(u8),
+    /// Reads the scene file and its shaders again. Left to the host application.
+    ReloadScene,
+    /// Left to the host application
+    SaveScene,
+    /// Writes a benchmark of the current frame to a file
+    Profile,
+    Exit,
+}
+
+impl Action {
+    pub const ALL: [Action; 39] = [
+        Action::MoveForward,
+        Action::MoveBackward,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::FreecamLook,
+        Action::FreecamPan,
+        Action::OrbitRotate,
+        Action::OrbitPan,
+        Action::ViewFront,
+        Action::ViewSide,
+        Action::ViewTop,
+        Action::ViewIsometric,
+        Action::ToggleProjection,
+        Action::ToggleController,
+        Action::FrameAll,
+        Action::RecallBookmark(1),
+        Action::RecallBookmark(2),
+        Action::RecallBookmark(3),
+        Action::RecallBookmark(4),
+        Action::RecallBookmark(5),
+        Action::RecallBookmark(6),
+        Action::RecallBookmark(7),
+        Action::RecallBookmark(8),
+        Action::RecallBookmark(9),
+        Action::SaveBookmark(1),
+        Action::SaveBookmark(2),
+        Action::SaveBookmark(3),
+        Action::SaveBookmark(4),
+        Action::SaveBookmark(5),
+        Action::SaveBookmark(6),
+        Action::SaveBookmark(7),
+        Action::SaveBookmark(8),
+        Action::SaveBookmark(9),
+        Action::ReloadScene,
+        Action::SaveScene,
+        Action::Profile,
+        Action::Exit,
+    ];
+
+    /// The name in config files, like `MoveForward` or `RecallBookmark1`
+    pub fn name(self) -> String {
+        format!("{self:?}").replace(['(', ')'], "")
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Action::ALL.into_iter().find(|action| action.name() == name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BindingInput {
+    /// A physical key, so that the layout of the keyboard does not matter
+    Key(KeyCode),
+    Mouse(MouseButton),
+}
+
+/// Modifier keys that have to be held. Either the left or the right key counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub control: bool,
+    pub alt: bool,
+}
+
+impl Modifiers {
+    pub const NONE: Modifiers = Modifiers {
+        shift: false,
+        control: false,
+        alt: false,
+    };
+
+    fn held(&self, inputs: &WindowInputs) -> bool {
+        let held = |left, right| {
+            inputs.keyboard.pressed_physical(left) || inputs.keyboard.pressed_physical(right)
+        };
+        (!self.shift || held(KeyCode::ShiftLeft, KeyCode::ShiftRight))
+            && (!self.control || held(KeyCode::ControlLeft, KeyCode::ControlRight))
+            && (!self.alt || held(KeyCode::AltLeft, KeyCode::AltRight))
+    }
+
+    /// Whether every modifier of `other` is also in `self`
+    fn contains(&self, other: &Modifiers) -> bool {
+        (self.shift || !other.shift) && (self.control || !other.control) && (self.alt || !other.alt)
+    }
+}
+
+/// A key or a mouse button, together with the modifiers that have to be held.
+/// Is written as `Shift+Alt+MouseLeft` or `KeyW` in config files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Binding {
+    pub input: BindingInput,
+    pub modifiers: Modifiers,
+}
+
+impl Binding {
+    pub fn key(key: KeyCode) -> Self {
+        Self {
+            input: BindingInput::Key(key),
+            modifiers: Modifiers::NONE,
+        }
+    }
+
+    pub fn mouse(button: MouseButton) -> Self {
+        Self {
+            input: BindingInput::Mouse(button),
+            modifiers: Modifiers::NONE,
+        }
+    }
+
+    pub fn with_modifiers(self, modifiers: Modifiers) -> Self {
+        Self { modifiers, ..self }
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parts = text.split('+').map(str::trim).collect::<Vec<_>>();
+        let input = parts.pop()?;
+        let mut modifiers = Modifiers::NONE;
+        for part in parts {
+            match part {
+                "Shift" => modifiers.shift = true,
+                "Ctrl" => modifiers.control = true,
+                "Alt" => modifiers.alt = true,
+                _ => return None,
+            }
+        }
+        let input = parse_mouse_button(input)
+            .map(BindingInput::Mouse)
+            .or_else(|| parse_key_code(input).map(BindingInput::Key))?;
+        Some(Self { input, modifiers })
+    }
+
+    fn held(&self, inputs: &WindowInputs) -> bool {
+        let input_held = match self.input {
+            BindingInput::Key(key) => inputs.keyboard.pressed_physical(key),
+            BindingInput::Mouse(button) => inputs.mouse.pressed(button),
+        };
+        input_held && self.modifiers.held(inputs)
+    }
+
+    fn just_pressed(&self, inputs: &WindowInputs) -> bool {
+        let just_pressed = match self.input {
+            BindingInput::Key(key) => inputs.keyboard.just_pressed_physical(key),
+            BindingInput::Mouse(button) => inputs.mouse.just_pressed(button),
+        };
+        just_pressed && self.modifiers.held(inputs)
+    }
+
+    fn just_released(&self, inputs: &WindowInputs) -> bool {
+        let just_released = match self.input {
+            BindingInput::Key(key) => inputs.keyboard.just_released_physical(key),
+            BindingInput::Mouse(button) => inputs.mouse.just_released(button),
+        };
+        just_released && self.modifiers.held(inputs)
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.shift {
+            write!(f, "Shift+")?;
+        }
+        if self.modifiers.control {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.alt {
+            write!(f, "Alt+")?;
+        }
+        match self.input {
+            BindingInput::Key(key) => write!(f, "{key:?}"),
+            BindingInput::Mouse(button) => write!(f, "{}", mouse_button_name(button)),
+        }
+    }
+}
+
+/// Maps actions to any number of bindings.
+///
+/// When one binding has the same input as another one, but with more modifiers,
+/// then holding those modifiers only triggers the more specific one.
+/// So `MouseMiddle` can rotate while `Shift+MouseMiddle` pans.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActionMap {
+    bindings: HashMap<Action, Vec<Binding>>,
+}
+
+impl ActionMap {
+    pub fn empty() -> Self {
+        Self {
+            bindings: HashMap::new(),
+        }
+    }
+
+    pub fn bindings(&self, action: Action) -> &[Binding] {
+        self.bindings
+            .get(&action)
+            .map(|v| v.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn set_bindings(&mut self, action: Action, bindings: Vec<Binding>) {
+        self.bindings.insert(action, bindings);
+    }
+
+    /// Whether the action is held down in this frame
+    pub fn pressed(&self, action: Action, inputs: &WindowInputs) -> bool {
+        self.bindings(action)
+            .iter()
+            .any(|binding| binding.held(inputs) && !self.is_shadowed(binding, inputs))
+    }
+
+    pub fn just_pressed(&self, action: Action, inputs: &WindowInputs) -> bool {
+        self.bindings(action)
+            .iter()
+            .any(|binding| binding.just_pressed(inputs) && !self.is_shadowed(binding, inputs))
+    }
+
+    pub fn just_released(&self, action: Action, inputs: &WindowInputs) -> bool {
+        self.bindings(action)
+            .iter()
+            .any(|binding| binding.just_released(inputs) && !self.is_shadowed(binding, inputs))
+    }
+
+    /// Whether a binding with the same input and more modifiers is active
+    fn is_shadowed(&self, binding: &Binding, inputs: &WindowInputs) -> bool {
+        self.bindings.values().flatten().any(|other| {
+            other.input == binding.input
+                && other.modifiers != binding.modifiers
+                && other.modifiers.contains(&binding.modifiers)
+                && other.modifiers.held(inputs)
+        })
+    }
+}
+
+impl Default for ActionMap {
+    fn default() -> Self {
+        let mut map = Self::empty();
+        let keys = [
+            (Action::MoveForward, vec![KeyCode::KeyW]),
+            (Action::MoveBackward, vec![KeyCode::KeyS]),
+            (Action::MoveLeft, vec![KeyCode::KeyA]),
+            (Action::MoveRight, vec![KeyCode::KeyD]),
+            (Action::MoveUp, vec![KeyCode::Space]),
+            (
+                Action::MoveDown,
+                vec![KeyCode::ShiftLeft, KeyCode::ShiftRight],
+            ),
+            // The numpad keys and the home key from Blender
+            (Action::ViewFront, vec![KeyCode::Numpad1]),
+            (Action::ViewSide, vec![KeyCode::Numpad3]),
+            (Action::ViewTop, vec![KeyCode::Numpad7]),
+            (Action::ViewIsometric, vec![KeyCode::Numpad9]),
+            (Action::ToggleProjection, vec![KeyCode::Numpad5]),
+            (Action::ToggleController, vec![KeyCode::Tab]),
+            (Action::FrameAll, vec![KeyCode::Home]),
+            (Action::ReloadScene, vec![KeyCode::F5]),
+            (Action::Profile, vec![KeyCode::KeyP]),
+            (Action::Exit, vec![KeyCode::Escape]),
+        ];
+        for (action, keys) in keys {
+            map.set_bindings(action, keys.into_iter().map(Binding::key).collect());
+        }
+        let digits = [
+            KeyCode::Digit1,
+            KeyCode::Digit2,
+            KeyCode::Digit3,
+            KeyCode::Digit4,
+            KeyCode::Digit5,
+            KeyCode::Digit6,
+            KeyCode::Digit7,
+            KeyCode::Digit8,
+            KeyCode::Digit9,
+        ];
+        for (slot, key) in (1..).zip(digits) {
+            map.set_bindings(Action::RecallBookmark(slot), vec![Binding::key(key)]);
+            map.set_bindings(
+                Action::SaveBookmark(slot),
+                vec![Binding::key(key).with_modifiers(Modifiers {
+                    control: true,
+                    ..Modifiers::NONE
+                })],
+            );
+        }
+        map.set_bindings(
+            Action::SaveScene,
+            vec![Binding::key(KeyCode::KeyS).with_modifiers(Modifiers {
+                control: true,
+                ..Modifiers::NONE
+            })],
+        );
+        let buttons = [
+            (Action::FreecamLook, MouseButton::Right),
+            (Action::FreecamPan, MouseButton::Middle),
+            (Action::OrbitRotate, MouseButton::Left),
+            (Action::OrbitPan, MouseButton::Right),
+        ];
+        for (action, button) in buttons {
+            map.set_bindings(action, vec![Binding::mouse(button)]);
+        }
+        map
+    }
+}
+
+const MOUSE_BUTTONS: [(MouseButton, &str); 5] = [
+    (MouseButton::Left, "MouseLeft"),
+    (MouseButton::Right, "MouseRight"),
+    (MouseButton::Middle, "MouseMiddle"),
+    (MouseButton::Back, "MouseBack"),
+    (MouseButton::Forward, "MouseForward"),
+];
+
+pub(crate) fn mouse_button_name(button: MouseButton) -> String {
+    MOUSE_BUTTONS
+        .into_iter()
+        .find(|(v, _)| *v == button)
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| format!("{button:?}"))
+}
+
+pub(crate) fn parse_mouse_button(name: &str) -> Option<MouseButton> {
+    MOUSE_BUTTONS
+        .into_iter()
+        .find(|(_, v)| *v == name)
+        .map(|(button, _)| button)
+}
+
+pub(crate) fn parse_key_code(name: &str) -> Option<KeyCode> {
+    KEY_CODES.into_iter().find(|key| format!("{key:?}") == name)
+}
+
+/// The keys that can be used in config files, written like their `KeyCode` variant.
+const KEY_CODES: [KeyCode; 104] = [
+    KeyCode::KeyA,
+    KeyCode::KeyB,
+    KeyCode::KeyC,
+    KeyCode::KeyD,
+    KeyCode::KeyE,
+    KeyCode::KeyF,
+    KeyCode::KeyG,
+    KeyCode::KeyH,
+    KeyCode::KeyI,
+    KeyCode::KeyJ,
+    KeyCode::KeyK,
+    KeyCode::KeyL,
+    KeyCode::KeyM,
+    KeyCode::KeyN,
+    KeyCode::KeyO,
+    KeyCode::KeyP,
+    KeyCode::KeyQ,
+    KeyCode::KeyR,
+    KeyCode::KeyS,
+    KeyCode::KeyT,
+    KeyCode::KeyU,
+    KeyCode::KeyV,
+    KeyCode::KeyW,
+    KeyCode::KeyX,
+    KeyCode::KeyY,
+    KeyCode::KeyZ,
+    KeyCode::Digit0,
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+    KeyCode::Numpad0,
+    KeyCode::Numpad1,
+    KeyCode::Numpad2,
+    KeyCode::Numpad3,
+    KeyCode::Numpad4,
+    KeyCode::Numpad5,
+    KeyCode::Numpad6,
+    KeyCode::Numpad7,
+    KeyCode::Numpad8,
+    KeyCode::Numpad9,
+    KeyCode::NumpadAdd,
+    KeyCode::NumpadSubtract,
+    KeyCode::NumpadMultiply,
+    KeyCode::NumpadDivide,
+    KeyCode::NumpadDecimal,
+    KeyCode::NumpadEnter,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+    KeyCode::ArrowUp,
+    KeyCode::ArrowDown,
+    KeyCode::ArrowLeft,
+    KeyCode::ArrowRight,
+    KeyCode::Home,
+    KeyCode::End,
+    KeyCode::PageUp,
+    KeyCode::PageDown,
+    KeyCode::Insert,
+    KeyCode::Delete,
+    KeyCode::Space,
+    KeyCode::Enter,
+    KeyCode::Tab,
+    KeyCode::Backspace,
+    KeyCode::Escape,
+    KeyCode::ShiftLeft,
+    KeyCode::ShiftRight,
+    KeyCode::ControlLeft,
+    KeyCode::ControlRight,
+    KeyCode::AltLeft,
+    KeyCode::AltRight,
+    KeyCode::SuperLeft,
+    KeyCode::SuperRight,
+    KeyCode::CapsLock,
+    KeyCode::Backquote,
+    KeyCode::Minus,
+    KeyCode::Equal,
+    KeyCode::BracketLeft,
+    KeyCode::BracketRight,
+    KeyCode::Backslash,
+    KeyCode::Semicolon,
+    KeyCode::Quote,
+    KeyCode::Comma,
+    KeyCode::Period,
+    KeyCode::Slash,
+    KeyCode::IntlBackslash,
+    KeyCode::ContextMenu,
+    KeyCode::PrintScreen,
+    KeyCode::ScrollLock,
+    KeyCode::Pause,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_what_it_writes() {
+        let bindings = [
+            Binding::key(KeyCode::KeyW),
+            Binding::key(KeyCode::Numpad5),
+            Binding::mouse(MouseButton::Middle).with_modifiers(Modifiers {
+                shift: true,
+                control: false,
+                alt: true,
+            }),
+        ];
+        for binding in bindings {
+            assert_eq!(Binding::parse(&binding.to_string()), Some(binding));
+        }
+        assert_eq!(
+            Binding::key(KeyCode::KeyA).with_modifiers(Modifiers {
+                shift: false,
+                control: true,
+                alt: false,
+            }),
+            Binding::parse("Ctrl + KeyA").unwrap()
+        );
+        assert_eq!(Binding::parse("Hyper+KeyA"), None);
+        assert_eq!(Binding::parse("NotAKey"), None);
+    }
+
+    #[test]
+    fn action_names() {
+        for action in Action::ALL {
+            assert_eq!(Action::from_name(&action.name()), Some(action));
+        }
+        assert_eq!(
+            Action::from_name("SaveBookmark3"),
+            Some(Action::SaveBookmark(3))
+        );
+    }
+}
@@ -1,14 +1,15 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use winit::{
     dpi::{PhysicalPosition, PhysicalSize},
-    event::{ElementState, KeyEvent, MouseButton, MouseScrollDelta},
-    keyboard::{Key, KeyCode, NativeKeyCode, PhysicalKey},
+    event::{ElementState, KeyEvent, MouseButton, MouseScrollDelta, TouchPhase},
+    keyboard::{Key, KeyCode, NativeKeyCode, PhysicalKey, SmolStr},
 };
 
 pub struct WindowInputs<'a> {
     pub mouse: WindowMouseInputs<'a>,
     pub keyboard: WindowKeyboardInputs<'a>,
+    pub touch: WindowTouchInputs,
     pub new_size: Option<PhysicalSize<u32>>,
     pub new_scale_factor: Option<f64>,
     pub close_requested: bool,
@@ -43,8 +44,105 @@ impl<'a> WindowMouseInputs<'a> {
     }
 }
 
+/// Touch screens and trackpad gestures
+#[derive(Debug, Clone, Default)]
+pub struct WindowTouchInputs {
+    /// The fingers that are on the screen, sorted by their ID
+    pub fingers: Vec<Finger>,
+    /// Trackpad pinch. Positive values zoom in.
+    pub pinch_delta: f64,
+    /// Trackpad pan, in pixels
+    pub pan_delta: PhysicalPosition<f64>,
+    /// Trackpad rotation, in degrees. Positive values are counterclockwise.
+    pub rotation_delta: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Finger {
+    pub id: u64,
+    pub position: PhysicalPosition<f64>,
+    /// Where the finger was in the last frame, or `None` if it just touched the screen
+    pub previous_position: Option<PhysicalPosition<f64>>,
+}
+
+impl Finger {
+    fn delta(&self) -> Option<(f64, f64)> {
+        self.previous_position
+            .map(|previous| (self.position.x - previous.x, self.position.y - previous.y))
+    }
+}
+
+/// Two fingers that moved together
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TwoFingerGesture {
+    /// The average motion of both fingers, in pixels
+    pub pan: (f64, f64),
+    /// Greater than 1 when the fingers moved apart
+    pub pinch_scale: f64,
+}
+
+impl WindowTouchInputs {
+    /// Whether any finger is on the screen, or any trackpad gesture happened
+    pub fn is_active(&self) -> bool {
+        !self.fingers.is_empty()
+            || self.pinch_delta != 0.0
+            || self.pan_delta != PhysicalPosition::default()
+            || self.rotation_delta != 0.0
+    }
+
+    /// How far a single finger on the screen moved
+    pub fn one_finger_drag(&self) -> Option<(f64, f64)> {
+        match self.fingers.as_slice() {
+            [finger] => finger.delta(),
+            _ => None,
+        }
+    }
+
+    pub fn two_finger_gesture(&self) -> Option<TwoFingerGesture> {
+        let [a, b] = self.fingers.as_slice() else {
+            return None;
+        };
+        let (a_previous, b_previous) = (a.previous_position?, b.previous_position?);
+        let (a_delta, b_delta) = (a.delta()?, b.delta()?);
+        let distance =
+            |a: PhysicalPosition<f64>, b: PhysicalPosition<f64>| (a.x - b.x).hypot(a.y - b.y);
+        let previous_distance = distance(a_previous, b_previous);
+        let pinch_scale = if previous_distance > 0.0 {
+            distance(a.position, b.position) / previous_distance
+        } else {
+            1.0
+        };
+        Some(TwoFingerGesture {
+            pan: ((a_delta.0 + b_delta.0) * 0.5, (a_delta.1 + b_delta.1) * 0.5),
+            pinch_scale,
+        })
+    }
+}
+
+/// A key press or release. Like a winit `KeyEvent`, except that it can be constructed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeyInput {
+    pub physical_key: PhysicalKey,
+    pub logical_key: Key,
+    pub text: Option<SmolStr>,
+    pub state: ElementState,
+    pub repeat: bool,
+}
+
+impl From<&KeyEvent> for KeyInput {
+    fn from(event: &KeyEvent) -> Self {
+        Self {
+            physical_key: event.physical_key,
+            logical_key: event.logical_key.clone(),
+            text: event.text.clone(),
+            state: event.state,
+            repeat: event.repeat,
+        }
+    }
+}
+
 pub struct WindowKeyboardInputs<'a> {
-    pub inputs: Vec<KeyEvent>,
+    pub inputs: Vec<KeyInput>,
     pub physical_held: &'a HashSet<PhysicalKey>,
     pub logical_held: &'a HashSet<Key>,
 }
@@ -119,9 +217,14 @@ pub struct WindowInputCollector {
     scroll_delta: PhysicalPosition<f64>,
     mouse_inputs: Vec<MouseInput>,
     mouse_held: HashSet<MouseButton>,
-    key_inputs: Vec<KeyEvent>,
+    key_inputs: Vec<KeyInput>,
     physical_key_held: HashSet<PhysicalKey>,
     key_held: HashSet<Key>,
+    touches: HashMap<u64, PhysicalPosition<f64>>,
+    start_touches: HashMap<u64, PhysicalPosition<f64>>,
+    pinch_delta: f64,
+    pan_delta: PhysicalPosition<f64>,
+    rotation_delta: f64,
     new_size: Option<PhysicalSize<u32>>,
     new_scale_factor: Option<f64>,
     close_requested: bool,
@@ -132,8 +235,8 @@ pub struct WindowInputCollector {
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct MouseInput {
-    state: ElementState,
-    button: MouseButton,
+    pub state: ElementState,
+    pub button: MouseButton,
 }
 
 impl WindowInputCollector {
@@ -148,6 +251,11 @@ impl WindowInputCollector {
             key_inputs: Vec::new(),
             physical_key_held: HashSet::new(),
             key_held: HashSet::new(),
+            touches: HashMap::new(),
+            start_touches: HashMap::new(),
+            pinch_delta: 0.0,
+            pan_delta: Default::default(),
+            rotation_delta: 0.0,
             new_size: None,
             new_scale_factor: None,
             close_requested: false,
@@ -188,7 +296,7 @@ impl WindowInputCollector {
                 }
             }
             winit::event::WindowEvent::KeyboardInput { event, .. } => {
-                self.key_inputs.push(event.clone());
+                self.key_inputs.push(KeyInput::from(event));
                 match event.state {
                     ElementState::Pressed => {
                         self.key_held.insert(event.logical_key.clone());
@@ -200,6 +308,25 @@ impl WindowInputCollector {
                     }
                 }
             }
+            winit::event::WindowEvent::Touch(touch) => match touch.phase {
+                TouchPhase::Started | TouchPhase::Moved => {
+                    self.touches.insert(touch.id, touch.location);
+                }
+                TouchPhase::Ended | TouchPhase::Cancelled => {
+                    self.touches.remove(&touch.id);
+                }
+            },
+            // Can be NaN
+            winit::event::WindowEvent::PinchGesture { delta, .. } if delta.is_finite() => {
+                self.pinch_delta += delta;
+            }
+            winit::event::WindowEvent::PanGesture { delta, .. } => {
+                self.pan_delta.x += delta.x as f64;
+                self.pan_delta.y += delta.y as f64;
+            }
+            winit::event::WindowEvent::RotationGesture { delta, .. } => {
+                self.rotation_delta += *delta as f64;
+            }
             winit::event::WindowEvent::CloseRequested => {
                 self.close_requested = true;
             }
@@ -228,6 +355,16 @@ impl WindowInputCollector {
             self.end_cursor_position.x - self.start_cursor_position.x,
             self.end_cursor_position.y - self.start_cursor_position.y,
         );
+        let mut fingers = self
+            .touches
+            .iter()
+            .map(|(id, position)| Finger {
+                id: *id,
+                position: *position,
+                previous_position: self.start_touches.get(id).copied(),
+            })
+            .collect::<Vec<_>>();
+        fingers.sort_by_key(|finger| finger.id);
         let inputs = WindowInputs {
             mouse: WindowMouseInputs {
                 position: self.end_cursor_position,
@@ -242,6 +379,12 @@ impl WindowInputCollector {
                 physical_held: &self.physical_key_held,
                 logical_held: &self.key_held,
             },
+            touch: WindowTouchInputs {
+                fingers,
+                pinch_delta: self.pinch_delta,
+                pan_delta: self.pan_delta,
+                rotation_delta: self.rotation_delta,
+            },
             new_size: self.new_size,
             new_scale_factor: self.new_scale_factor,
             close_requested: self.close_requested,
@@ -251,6 +394,10 @@ impl WindowInputCollector {
         self.scroll_delta = Default::default();
         self.mouse_motion = (0.0, 0.0);
         self.mouse_inputs.clear();
+        self.start_touches = self.touches.clone();
+        self.pinch_delta = 0.0;
+        self.pan_delta = Default::default();
+        self.rotation_delta = 0.0;
         self.key_inputs.clear();
         self.new_size = None;
         self.new_scale_factor = None;
@@ -259,3 +406,76 @@ impl WindowInputCollector {
         inputs
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn finger(id: u64, previous: (f64, f64), position: (f64, f64)) -> Finger {
+        Finger {
+            id,
+            position: PhysicalPosition::new(position.0, position.1),
+            previous_position: Some(PhysicalPosition::new(previous.0, previous.1)),
+        }
+    }
+
+    fn gesture(fingers: Vec<Finger>) -> Option<TwoFingerGesture> {
+        WindowTouchInputs {
+            fingers,
+            ..Default::default()
+        }
+        .two_finger_gesture()
+    }
+
+    #[test]
+    fn pinch() {
+        let gesture = gesture(vec![
+            finger(0, (90.0, 100.0), (80.0, 100.0)),
+            finger(1, (110.0, 100.0), (120.0, 100.0)),
+        ])
+        .unwrap();
+        assert_eq!(gesture.pinch_scale, 2.0);
+        assert_eq!(gesture.pan, (0.0, 0.0));
+    }
+
+    #[test]
+    fn pan() {
+        let gesture = gesture(vec![
+            finger(0, (90.0, 100.0), (95.0, 90.0)),
+            finger(1, (110.0, 100.0), (115.0, 90.0)),
+        ])
+        .unwrap();
+        assert_eq!(gesture.pinch_scale, 1.0);
+        assert_eq!(gesture.pan, (5.0, -10.0));
+    }
+
+    #[test]
+    fn rotation_neither_pans_nor_pinches() {
+        // A quarter turn around (100, 100)
+        let gesture = gesture(vec![
+            finger(0, (90.0, 100.0), (100.0, 90.0)),
+            finger(1, (110.0, 100.0), (100.0, 110.0)),
+        ])
+        .unwrap();
+        assert_eq!(gesture.pinch_scale, 1.0);
+        assert_eq!(gesture.pan, (0.0, 0.0));
+    }
+
+    #[test]
+    fn needs_two_moving_fingers() {
+        assert_eq!(gesture(vec![finger(0, (0.0, 0.0), (10.0, 0.0))]), None);
+        let mut new_finger = finger(1, (0.0, 0.0), (20.0, 0.0));
+        new_finger.previous_position = None;
+        assert_eq!(
+            gesture(vec![finger(0, (0.0, 0.0), (10.0, 0.0)), new_finger]),
+            None
+        );
+        // Fingers that start at the same point do not pinch
+        let gesture = gesture(vec![
+            finger(0, (50.0, 50.0), (40.0, 50.0)),
+            finger(1, (50.0, 50.0), (60.0, 50.0)),
+        ])
+        .unwrap();
+        assert_eq!(gesture.pinch_scale, 1.0);
+    }
+}
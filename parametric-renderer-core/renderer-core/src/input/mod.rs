@@ -1,7 +1,14 @@
 // Heavily based on https://github.com/rukai/winit_input_helper/
 // But with a lot of modifications
+mod action_map;
 mod current_input;
+mod recording;
 mod winit_helper;
 
-pub use current_input::{MouseInput, WindowInputs, WindowKeyboardInputs, WindowMouseInputs};
+pub use action_map::{Action, ActionMap, Binding, BindingInput, Modifiers};
+pub use current_input::{
+    Finger, KeyInput, MouseInput, TwoFingerGesture, WindowInputs, WindowKeyboardInputs,
+    WindowMouseInputs, WindowTouchInputs,
+};
+pub use recording::{InputRecording, InputReplay, RecordedFrame};
 pub use winit_helper::{InputHandler, WinitAppHelper};
@@ -0,0 +1,761 @@
+use std::{collections::HashSet, path::Path};
+
+use glam::{Quat, Vec3};
+use nanoserde::{DeJson, SerJson};
+use winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::{ElementState, MouseButton},
+    keyboard::{Key, NativeKey, NativeKeyCode, PhysicalKey},
+};
+
+use crate::{
+    camera::{
+        camera_controller::{
+            CameraController, ChosenKind, GeneralController, GeneralControllerSettings,
+            IsCameraController, OrbitMode, OrbitcamSettings,
+        },
+        Angle, CameraSettings, Projection,
+    },
+    game::GameRes,
+    renderer::frame_counter::{FrameTime, Seconds},
+};
+
+use super::{
+    action_map::{mouse_button_name, parse_key_code, parse_mouse_button},
+    Action, ActionMap, Binding, Finger, KeyInput, MouseInput, WindowInputs, WindowKeyboardInputs,
+    WindowMouseInputs, WindowTouchInputs,
+};
+
+/// Everything that went into one `GameRes::update`, without any borrows.
+/// This includes the results of the depth probes, because the renderer finds them a frame late,
+/// and a slightly different GPU or window size would find slightly different points.
+#[derive(Debug, Clone, Default)]
+pub struct RecordedFrame {
+    pub time: FrameTime,
+    /// The point under the cursor, which the renderer found in an earlier frame
+    pub cursor_world_position: Option<Vec3>,
+    /// The point in the middle of the screen, which the renderer found in an earlier frame
+    pub center_world_position: Option<Vec3>,
+    pub mouse_position: PhysicalPosition<f64>,
+    pub mouse_position_delta: (f64, f64),
+    pub mouse_motion: (f64, f64),
+    pub scroll_delta: PhysicalPosition<f64>,
+    pub mouse_inputs: Vec<MouseInput>,
+    pub mouse_held: HashSet<MouseButton>,
+    pub key_inputs: Vec<KeyInput>,
+    pub physical_held: HashSet<PhysicalKey>,
+    pub logical_held: HashSet<Key>,
+    pub touch: WindowTouchInputs,
+    pub new_size: Option<PhysicalSize<u32>>,
+    pub new_scale_factor: Option<f64>,
+    pub close_requested: bool,
+}
+
+impl RecordedFrame {
+    /// Takes the time and the probed positions from the game, after it got updated.
+    pub fn new(inputs: &WindowInputs, game: &GameRes) -> Self {
+        Self {
+            time: game.time,
+            cursor_world_position: game.cursor_world_position,
+            center_world_position: game.center_world_position,
+            mouse_position: inputs.mouse.position,
+            mouse_position_delta: inputs.mouse.position_delta,
+            mouse_motion: inputs.mouse.motion,
+            scroll_delta: inputs.mouse.scroll_delta,
+            mouse_inputs: inputs.mouse.inputs.clone(),
+            mouse_held: inputs.mouse.held.clone(),
+            key_inputs: inputs.keyboard.inputs.clone(),
+            physical_held: inputs.keyboard.physical_held.clone(),
+            logical_held: inputs.keyboard.logical_held.clone(),
+            touch: inputs.touch.clone(),
+            new_size: inputs.new_size,
+            new_scale_factor: inputs.new_scale_factor,
+            close_requested: inputs.close_requested,
+        }
+    }
+
+    /// The inputs, like the `WindowInputCollector` returned them.
+    /// A default frame has no inputs at all.
+    pub fn inputs(&self) -> WindowInputs<'_> {
+        WindowInputs {
+            mouse: WindowMouseInputs {
+                position: self.mouse_position,
+                position_delta: self.mouse_position_delta,
+                motion: self.mouse_motion,
+                scroll_delta: self.scroll_delta,
+                inputs: self.mouse_inputs.clone(),
+                held: &self.mouse_held,
+            },
+            keyboard: WindowKeyboardInputs {
+                inputs: self.key_inputs.clone(),
+                physical_held: &self.physical_held,
+                logical_held: &self.logical_held,
+            },
+            touch: self.touch.clone(),
+            new_size: self.new_size,
+            new_scale_factor: self.new_scale_factor,
+            close_requested: self.close_requested,
+        }
+    }
+}
+
+/// The settings that decide how the inputs move the camera.
+#[derive(Debug, Clone)]
+pub struct RecordedSettings {
+    pub controller: GeneralControllerSettings,
+    pub camera: CameraSettings,
+    pub actions: ActionMap,
+}
+
+impl RecordedSettings {
+    pub fn from_game(game: &GameRes) -> Self {
+        Self {
+            controller: game.camera_controller.settings.clone(),
+            camera: game.camera.settings.clone(),
+            actions: game.actions.clone(),
+        }
+    }
+
+    pub fn apply_to(&self, game: &mut GameRes) {
+        game.camera_controller.settings = self.controller.clone();
+        game.camera.settings = self.camera.clone();
+        game.actions = self.actions.clone();
+    }
+}
+
+/// The inputs of every frame, where the camera started, and the settings that it used.
+/// Replaying them moves the camera exactly like the first time.
+#[derive(Debug, Clone)]
+pub struct InputRecording {
+    pub camera: GeneralController,
+    pub camera_kind: ChosenKind,
+    pub settings: RecordedSettings,
+    pub frames: Vec<RecordedFrame>,
+}
+
+impl InputRecording {
+    /// Starts at the current camera, with the current settings.
+    pub fn new(game: &GameRes) -> Self {
+        let controller = &game.camera_controller;
+        Self {
+            camera: controller.general_controller(),
+            camera_kind: controller.get_chosen_kind(),
+            settings: RecordedSettings::from_game(game),
+            frames: Vec::new(),
+        }
+    }
+
+    /// Only keys from the controls file are written, and only the text of logical keys.
+    /// Everything else is replayed as an unidentified key.
+    pub fn to_json(&self) -> String {
+        RecordingFile::from(self).serialize_json()
+    }
+
+    pub fn from_json(json: &str) -> anyhow::Result<Self> {
+        let file = RecordingFile::deserialize_json(json)
+            .map_err(|e| anyhow::anyhow!("Failed to parse input recording: {e}"))?;
+        Ok(file.into())
+    }
+
+    pub fn save_to_file(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        std::fs::write(path, self.to_json())
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+}
+
+/// Hands out the recorded frames one after the other.
+pub struct InputReplay {
+    recording: InputRecording,
+    next_frame: usize,
+    /// The settings of the game before the replay
+    replaced_settings: RecordedSettings,
+    /// Uses the points that the renderer finds now, instead of the recorded ones.
+    /// Useful for reproducing bugs in the depth probes, but the camera can end up somewhere else.
+    pub live_probes: bool,
+}
+
+impl InputReplay {
+    /// Moves the camera to where the recording started, and uses the recorded settings until [`InputReplay::finish`].
+    pub fn start(recording: InputRecording, game: &mut GameRes) -> Self {
+        let replaced_settings = RecordedSettings::from_game(game);
+        recording.settings.apply_to(game);
+        game.camera_controller = CameraController::new(
+            recording.camera.clone(),
+            recording.settings.controller.clone(),
+            recording.camera_kind,
+        );
+        Self {
+            recording,
+            next_frame: 0,
+            replaced_settings,
+            live_probes: false,
+        }
+    }
+
+    /// Puts back the settings that the game had before the replay.
+    pub fn finish(self, game: &mut GameRes) {
+        self.replaced_settings.apply_to(game);
+    }
+
+    pub fn recording(&self) -> &InputRecording {
+        &self.recording
+    }
+
+    /// Returns `None` after the last frame.
+    pub fn next_frame(&mut self) -> Option<&RecordedFrame> {
+        let frame = self.recording.frames.get(self.next_frame)?;
+        self.next_frame += 1;
+        Some(frame)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next_frame >= self.recording.frames.len()
+    }
+}
+
+#[derive(SerJson, DeJson)]
+struct RecordingFile {
+    camera_position: [f32; 3],
+    camera_orientation: [f32; 4],
+    camera_distance_to_center: f32,
+    /// "Orbitcam" or "Freecam"
+    camera_kind: String,
+    controller_settings: ControllerSettingsFile,
+    camera_settings: CameraSettingsFile,
+    /// The bindings of every action, like in the controls file
+    actions: Vec<ActionFile>,
+    frames: Vec<FrameFile>,
+}
+
+/// Angles are in radians, so that they are read back exactly.
+#[derive(SerJson, DeJson)]
+struct ControllerSettingsFile {
+    fly_speed: f32,
+    pan_speed: f32,
+    rotation_sensitivity: f32,
+    /// "Turntable" or "Trackball"
+    orbit_mode: String,
+    min_pitch: f32,
+    max_pitch: f32,
+    zoom_to_cursor: bool,
+    inertia: Option<f32>,
+}
+
+#[derive(SerJson, DeJson)]
+struct CameraSettingsFile {
+    z_near: f32,
+    z_far: Option<f32>,
+    /// Vertical, in radians
+    fov: f32,
+    /// "Perspective" or "Orthographic"
+    projection: String,
+}
+
+#[derive(SerJson, DeJson)]
+struct ActionFile {
+    /// Like `MoveForward`
+    action: String,
+    /// Like `KeyW` or `Shift+MouseMiddle`
+    bindings: Vec<String>,
+}
+
+/// The world positions are the recorded results of the depth probes, which a replay feeds back in.
+#[derive(SerJson, DeJson)]
+struct FrameFile {
+    frame: u64,
+    delta: f32,
+    elapsed: f32,
+    cursor_world_position: Option<[f32; 3]>,
+    center_world_position: Option<[f32; 3]>,
+    mouse_position: [f64; 2],
+    mouse_position_delta: [f64; 2],
+    mouse_motion: [f64; 2],
+    scroll_delta: [f64; 2],
+    mouse_inputs: Vec<ButtonFile>,
+    mouse_held: Vec<String>,
+    key_inputs: Vec<KeyFile>,
+    physical_held: Vec<String>,
+    logical_held: Vec<String>,
+    fingers: Vec<FingerFile>,
+    pinch_delta: f64,
+    pan_delta: [f64; 2],
+    rotation_delta: f64,
+    new_size: Option<[u32; 2]>,
+    new_scale_factor: Option<f64>,
+    close_requested: bool,
+}
+
+#[derive(SerJson, DeJson)]
+struct ButtonFile {
+    button: String,
+    pressed: bool,
+}
+
+#[derive(SerJson, DeJson)]
+struct KeyFile {
+    /// Like `KeyW`
+    physical_key: String,
+    /// The text of a character key
+    logical_key: Option<String>,
+    text: Option<String>,
+    pressed: bool,
+    repeat: bool,
+}
+
+#[derive(SerJson, DeJson)]
+struct FingerFile {
+    id: u64,
+    position: [f64; 2],
+    previous_position: Option<[f64; 2]>,
+}
+
+fn physical_key_name(key: &PhysicalKey) -> String {
+    match key {
+        PhysicalKey::Code(code) => format!("{code:?}"),
+        PhysicalKey::Unidentified(_) => String::new(),
+    }
+}
+
+fn parse_physical_key(name: &str) -> PhysicalKey {
+    parse_key_code(name)
+        .map(PhysicalKey::Code)
+        .unwrap_or(PhysicalKey::Unidentified(NativeKeyCode::Unidentified))
+}
+
+fn logical_key_text(key: &Key) -> Option<String> {
+    match key {
+        Key::Character(text) => Some(text.to_string()),
+        _ => None,
+    }
+}
+
+fn parse_logical_key(text: Option<&str>) -> Key {
+    match text {
+        Some(text) => Key::Character(text.into()),
+        None => Key::Unidentified(NativeKey::Unidentified),
+    }
+}
+
+fn position_to_array(position: PhysicalPosition<f64>) -> [f64; 2] {
+    [position.x, position.y]
+}
+
+fn array_to_position([x, y]: [f64; 2]) -> PhysicalPosition<f64> {
+    PhysicalPosition::new(x, y)
+}
+
+impl From<&InputRecording> for RecordingFile {
+    fn from(recording: &InputRecording) -> Self {
+        Self {
+            camera_position: recording.camera.position.to_array(),
+            camera_orientation: recording.camera.orientation.to_array(),
+            camera_distance_to_center: recording.camera.distance_to_center,
+            camera_kind: match recording.camera_kind {
+                ChosenKind::Orbitcam => "Orbitcam".into(),
+                ChosenKind::Freecam => "Freecam".into(),
+            },
+            controller_settings: ControllerSettingsFile::from(&recording.settings.controller),
+            camera_settings: CameraSettingsFile::from(&recording.settings.camera),
+            actions: Action::ALL
+                .into_iter()
+                .map(|action| ActionFile {
+                    action: action.name(),
+                    bindings: recording
+                        .settings
+                        .actions
+                        .bindings(action)
+                        .iter()
+                        .map(|binding| binding.to_string())
+                        .collect(),
+                })
+                .collect(),
+            frames: recording.frames.iter().map(FrameFile::from).collect(),
+        }
+    }
+}
+
+impl From<RecordingFile> for InputRecording {
+    fn from(file: RecordingFile) -> Self {
+        Self {
+            camera: GeneralController {
+                position: Vec3::from(file.camera_position),
+                orientation: Quat::from_array(file.camera_orientation),
+                distance_to_center: file.camera_distance_to_center,
+            },
+            camera_kind: match file.camera_kind.as_str() {
+                "Freecam" => ChosenKind::Freecam,
+                _ => ChosenKind::Orbitcam,
+            },
+            settings: RecordedSettings {
+                controller: file.controller_settings.into(),
+                camera: file.camera_settings.into(),
+                actions: action_map_from_files(&file.actions),
+            },
+            frames: file.frames.into_iter().map(RecordedFrame::from).collect(),
+        }
+    }
+}
+
+/// Skips the actions and bindings that this version does not know.
+fn action_map_from_files(actions: &[ActionFile]) -> ActionMap {
+    let mut action_map = ActionMap::empty();
+    for entry in actions {
+        if let Some(action) = Action::from_name(&entry.action) {
+            let bindings = entry
+                .bindings
+                .iter()
+                .filter_map(|binding| Binding::parse(binding))
+                .collect();
+            action_map.set_bindings(action, bindings);
+        }
+    }
+    action_map
+}
+
+impl From<&GeneralControllerSettings> for ControllerSettingsFile {
+    fn from(settings: &GeneralControllerSettings) -> Self {
+        Self {
+            fly_speed: settings.fly_speed,
+            pan_speed: settings.pan_speed,
+            rotation_sensitivity: settings.rotation_sensitivity,
+            orbit_mode: match settings.orbit.mode {
+                OrbitMode::Turntable => "Turntable".into(),
+                OrbitMode::Trackball => "Trackball".into(),
+            },
+            min_pitch: settings.orbit.min_pitch.radians,
+            max_pitch: settings.orbit.max_pitch.radians,
+            zoom_to_cursor: settings.orbit.zoom_to_cursor,
+            inertia: settings.orbit.inertia,
+        }
+    }
+}
+
+impl From<ControllerSettingsFile> for GeneralControllerSettings {
+    fn from(file: ControllerSettingsFile) -> Self {
+        Self {
+            fly_speed: file.fly_speed,
+            pan_speed: file.pan_speed,
+            rotation_sensitivity: file.rotation_sensitivity,
+            orbit: OrbitcamSettings {
+                mode: match file.orbit_mode.as_str() {
+                    "Trackball" => OrbitMode::Trackball,
+                    _ => OrbitMode::Turntable,
+                },
+                min_pitch: Angle::new(file.min_pitch),
+                max_pitch: Angle::new(file.max_pitch),
+                zoom_to_cursor: file.zoom_to_cursor,
+                inertia: file.inertia,
+            },
+        }
+    }
+}
+
+impl From<&CameraSettings> for CameraSettingsFile {
+    fn from(settings: &CameraSettings) -> Self {
+        Self {
+            z_near: settings.z_near,
+            z_far: settings.z_far,
+            fov: settings.fov.radians,
+            projection: match settings.projection {
+                Projection::Perspective => "Perspective".into(),
+                Projection::Orthographic => "Orthographic".into(),
+            },
+        }
+    }
+}
+
+impl From<CameraSettingsFile> for CameraSettings {
+    fn from(file: CameraSettingsFile) -> Self {
+        Self {
+            z_near: file.z_near,
+            z_far: file.z_far,
+            fov: Angle::new(file.fov),
+            projection: match file.projection.as_str() {
+                "Orthographic" => Projection::Orthographic,
+                _ => Projection::Perspective,
+            },
+        }
+    }
+}
+
+impl From<&RecordedFrame> for FrameFile {
+    fn from(frame: &RecordedFrame) -> Self {
+        // Sorted, so that the same inputs always give the same file
+        let sorted = |names: Vec<String>| {
+            let mut names = names;
+            names.sort();
+            names
+        };
+        Self {
+            frame: frame.time.frame,
+            delta: frame.time.delta.0,
+            elapsed: frame.time.elapsed.0,
+            cursor_world_position: frame.cursor_world_position.map(|v| v.to_array()),
+            center_world_position: frame.center_world_position.map(|v| v.to_array()),
+            mouse_position: position_to_array(frame.mouse_position),
+            mouse_position_delta: [frame.mouse_position_delta.0, frame.mouse_position_delta.1],
+            mouse_motion: [frame.mouse_motion.0, frame.mouse_motion.1],
+            scroll_delta: position_to_array(frame.scroll_delta),
+            mouse_inputs: frame
+                .mouse_inputs
+                .iter()
+                .map(|input| ButtonFile {
+                    button: mouse_button_name(input.button),
+                    pressed: input.state == ElementState::Pressed,
+                })
+                .collect(),
+            mouse_held: sorted(
+                frame
+                    .mouse_held
+                    .iter()
+                    .map(|button| mouse_button_name(*button))
+                    .collect(),
+            ),
+            key_inputs: frame
+                .key_inputs
+                .iter()
+                .map(|input| KeyFile {
+                    physical_key: physical_key_name(&input.physical_key),
+                    logical_key: logical_key_text(&input.logical_key),
+                    text: input.text.as_ref().map(|v| v.to_string()),
+                    pressed: input.state == ElementState::Pressed,
+                    repeat: input.repeat,
+                })
+                .collect(),
+            physical_held: sorted(frame.physical_held.iter().map(physical_key_name).collect()),
+            logical_held: sorted(
+                frame
+                    .logical_held
+                    .iter()
+                    .filter_map(logical_key_text)
+                    .collect(),
+            ),
+            fingers: frame
+                .touch
+                .fingers
+                .iter()
+                .map(|finger| FingerFile {
+                    id: finger.id,
+                    position: position_to_array(finger.position),
+                    previous_position: finger.previous_position.map(position_to_array),
+                })
+                .collect(),
+            pinch_delta: frame.touch.pinch_delta,
+            pan_delta: position_to_array(frame.touch.pan_delta),
+            rotation_delta: frame.touch.rotation_delta,
+            new_size: frame.new_size.map(|size| [size.width, size.height]),
+            new_scale_factor: frame.new_scale_factor,
+            close_requested: frame.close_requested,
+        }
+    }
+}
+
+impl From<FrameFile> for RecordedFrame {
+    fn from(file: FrameFile) -> Self {
+        let element_state = |pressed: bool| {
+            if pressed {
+                ElementState::Pressed
+            } else {
+                ElementState::Released
+            }
+        };
+        Self {
+            time: FrameTime {
+                frame: file.frame,
+                delta: Seconds(file.delta),
+                elapsed: Seconds(file.elapsed),
+            },
+            cursor_world_position: file.cursor_world_position.map(Vec3::from),
+            center_world_position: file.center_world_position.map(Vec3::from),
+            mouse_position: array_to_position(file.mouse_position),
+            mouse_position_delta: (file.mouse_position_delta[0], file.mouse_position_delta[1]),
+            mouse_motion: (file.mouse_motion[0], file.mouse_motion[1]),
+            scroll_delta: array_to_position(file.scroll_delta),
+            mouse_inputs: file
+                .mouse_inputs
+                .iter()
+                .filter_map(|input| {
+                    Some(MouseInput {
+                        state: element_state(input.pressed),
+                        button: parse_mouse_button(&input.button)?,
+                    })
+                })
+                .collect(),
+            mouse_held: file
+                .mouse_held
+                .iter()
+                .filter_map(|name| parse_mouse_button(name))
+                .collect(),
+            key_inputs: file
+                .key_inputs
+                .iter()
+                .map(|input| KeyInput {
+                    physical_key: parse_physical_key(&input.physical_key),
+                    logical_key: parse_logical_key(input.logical_key.as_deref()),
+                    text: input.text.as_deref().map(Into::into),
+                    state: element_state(input.pressed),
+                    repeat: input.repeat,
+                })
+                .collect(),
+            physical_held: file
+                .physical_held
+                .iter()
+                .filter_map(|name| parse_key_code(name).map(PhysicalKey::Code))
+                .collect(),
+            logical_held: file
+                .logical_held
+                .iter()
+                .map(|text| parse_logical_key(Some(text)))
+                .collect(),
+            touch: WindowTouchInputs {
+                fingers: file
+                    .fingers
+                    .iter()
+                    .map(|finger| Finger {
+                        id: finger.id,
+                        position: array_to_position(finger.position),
+                        previous_position: finger.previous_position.map(array_to_position),
+                    })
+                    .collect(),
+                pinch_delta: file.pinch_delta,
+                pan_delta: array_to_position(file.pan_delta),
+                rotation_delta: file.rotation_delta,
+            },
+            new_size: file
+                .new_size
+                .map(|[width, height]| PhysicalSize::new(width, height)),
+            new_scale_factor: file.new_scale_factor,
+            close_requested: file.close_requested,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::keyboard::KeyCode;
+
+    use super::*;
+
+    #[test]
+    fn roundtrips_through_json() {
+        let mut recording = InputRecording::new(&GameRes::new());
+        recording.camera = GeneralController {
+            position: Vec3::new(1.0, 2.0, 3.0),
+            orientation: Quat::from_rotation_y(0.5),
+            distance_to_center: 4.0,
+        };
+        recording.camera_kind = ChosenKind::Freecam;
+        recording.settings.camera.projection = Projection::Orthographic;
+        recording.settings.controller.orbit.mode = OrbitMode::Trackball;
+        let mut frame = RecordedFrame {
+            time: FrameTime {
+                frame: 7,
+                delta: Seconds(0.25),
+                elapsed: Seconds(1.75),
+            },
+            cursor_world_position: Some(Vec3::new(0.5, 0.0, -0.5)),
+            mouse_motion: (3.0, -2.0),
+            mouse_inputs: vec![MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Middle,
+            }],
+            key_inputs: vec![KeyInput {
+                physical_key: PhysicalKey::Code(KeyCode::KeyW),
+                logical_key: Key::Character("w".into()),
+                text: Some("w".into()),
+                state: ElementState::Released,
+                repeat: false,
+            }],
+            new_size: Some(PhysicalSize::new(800, 600)),
+            ..Default::default()
+        };
+        frame.mouse_held.insert(MouseButton::Middle);
+        frame
+            .physical_held
+            .insert(PhysicalKey::Code(KeyCode::ShiftLeft));
+        frame.touch.fingers.push(Finger {
+            id: 2,
+            position: PhysicalPosition::new(10.0, 20.0),
+            previous_position: None,
+        });
+        recording.frames.push(frame);
+
+        let loaded = InputRecording::from_json(&recording.to_json()).unwrap();
+        assert_eq!(loaded.camera.position, recording.camera.position);
+        assert!(matches!(loaded.camera_kind, ChosenKind::Freecam));
+        assert_eq!(loaded.settings.camera.projection, Projection::Orthographic);
+        assert_eq!(
+            loaded.settings.controller.orbit,
+            recording.settings.controller.orbit
+        );
+        assert_eq!(loaded.settings.actions, recording.settings.actions);
+        let (a, b) = (&loaded.frames[0], &recording.frames[0]);
+        assert_eq!(a.time.frame, b.time.frame);
+        assert_eq!(a.time.delta, b.time.delta);
+        assert_eq!(a.cursor_world_position, b.cursor_world_position);
+        assert_eq!(a.mouse_motion, b.mouse_motion);
+        assert_eq!(a.mouse_inputs, b.mouse_inputs);
+        assert_eq!(a.mouse_held, b.mouse_held);
+        assert_eq!(a.key_inputs, b.key_inputs);
+        assert_eq!(a.physical_held, b.physical_held);
+        assert_eq!(a.touch.fingers, b.touch.fingers);
+        assert_eq!(a.new_size, b.new_size);
+    }
+
+    #[test]
+    fn replays_every_frame_once() {
+        let mut game = GameRes::new();
+        let mut recording = InputRecording::new(&game);
+        recording.frames = vec![RecordedFrame::default(), RecordedFrame::default()];
+        let mut replay = InputReplay::start(recording, &mut game);
+        assert!(replay.next_frame().is_some());
+        assert!(!replay.is_finished());
+        assert!(replay.next_frame().is_some());
+        assert!(replay.is_finished());
+        assert!(replay.next_frame().is_none());
+    }
+
+    #[test]
+    fn replays_with_the_recorded_settings() {
+        let mut game = GameRes::new();
+        game.camera_controller.settings.fly_speed = 2.0;
+        let start = game.camera_controller.general_controller().position;
+        let mut recording = InputRecording::new(&game);
+        for frame in 1..=3 {
+            let mut recorded = RecordedFrame {
+                time: FrameTime {
+                    frame,
+                    delta: Seconds(0.5),
+                    elapsed: Seconds(frame as f32 * 0.5),
+                },
+                ..Default::default()
+            };
+            recorded
+                .physical_held
+                .insert(PhysicalKey::Code(KeyCode::KeyW));
+            recording.frames.push(recorded);
+        }
+        for frame in &recording.frames {
+            game.update_with_time(&frame.inputs(), frame.time);
+        }
+        let recorded_position = game.camera_controller.general_controller().position;
+        assert!(recorded_position.distance(start) > 1.0);
+
+        // A later session flies faster
+        let mut game = GameRes::new();
+        game.camera_controller.settings.fly_speed = 20.0;
+        let recording = InputRecording::from_json(&recording.to_json()).unwrap();
+        let mut replay = InputReplay::start(recording, &mut game);
+        while let Some(frame) = replay.next_frame() {
+            game.update_with_time(&frame.inputs(), frame.time);
+        }
+        let replayed_position = game.camera_controller.general_controller().position;
+        assert!(replayed_position.abs_diff_eq(recorded_position, 1e-5));
+
+        replay.finish(&mut game);
+        assert_eq!(game.camera_controller.settings.fly_speed, 20.0);
+    }
+}
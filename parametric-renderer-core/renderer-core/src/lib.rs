@@ -1,11 +1,14 @@
+pub mod aabb;
 pub mod application;
 pub mod buffer;
 pub mod camera;
 pub mod game;
+pub mod geometry_image;
 pub mod input;
 pub mod mesh;
 pub mod reactive;
 pub mod renderer;
+pub mod shader_parameters;
 pub mod shaders;
 pub mod texture;
 pub mod transform;
@@ -116,4 +116,22 @@ where
             }
         });
     }
+
+    /// Only visits the items that have a key, in ascending order of their keys.
+    pub fn for_each_sorted_by<Order: PartialOrd>(
+        &self,
+        mut key: impl FnMut(&Output) -> Option<Order>,
+        mut callback: impl FnMut(&Output),
+    ) {
+        self.effect.with_value_mut(|last_run| {
+            let mut items = last_run
+                .values()
+                .filter_map(|(item, _)| key(item).map(|order| (order, item)))
+                .collect::<Vec<_>>();
+            items.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+            for (_, item) in items {
+                callback(item);
+            }
+        });
+    }
 }
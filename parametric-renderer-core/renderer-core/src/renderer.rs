@@ -1,37 +1,56 @@
 pub mod frame_counter;
 mod scene;
+mod ssao;
 mod virtual_model;
 mod wgpu_context;
 
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
 
 use encase::ShaderType;
-use frame_counter::{FrameCounter, Seconds};
-use glam::UVec2;
+use frame_counter::{FrameTime, Seconds};
+use glam::{Mat4, UVec2, Vec2, Vec3};
 
 use reactive_graph::{
     computed::Memo,
     effect::{Effect, RenderEffect},
-    owner::{expect_context, provide_context, use_context, Owner, StoredValue},
+    owner::{expect_context, provide_context, Owner, StoredValue},
     prelude::*,
     signal::{signal, ArcReadSignal, ReadSignal, RwSignal, WriteSignal},
 };
 use scene::SceneData;
+use ssao::ambient_occlusion_component;
 use virtual_model::{make_missing_shader, ShaderPipelines, VirtualModel};
+pub use wgpu_context::GpuSettings;
 use wgpu_context::{create_profiler, SurfaceOrFallback, WgpuContext};
 use wgpu_profiler::GpuProfiler;
 
 use crate::{
+    aabb::Aabb,
     buffer::TypedBuffer,
-    game::{GameRes, MaterialInfo, ModelInfo, ShaderId},
+    game::{
+        AmbientOcclusion, AntiAliasingSettings, Background, GameRes, GroundPlaneMode,
+        GroundPlaneSettings, LightingSettings, MaterialInfo, ModelInfo, ShaderId, TextureId,
+        TextureInfo, ToneMappingCurve, ToneMappingSettings,
+    },
     mesh::Mesh,
     reactive::{ForEach, MemoComputed, SignalVec},
-    shaders::{compute_patches, copy_patches, ground_plane, shader},
+    shader_parameters::{ShaderParameters, MAX_PARAMETERS_SIZE},
+    shaders::{
+        compute_bounds, compute_patches, copy_patches, cursor_depth, fxaa, ground_plane, shader,
+        tonemap,
+    },
     texture::Texture,
     window_or_fallback::WindowOrFallback,
 };
 struct ComputePatchesStep {
     bind_group_0: compute_patches::bind_groups::BindGroup0,
+    bounds_bind_group_0: compute_bounds::bind_groups::BindGroup0,
 }
 #[must_use]
 pub struct GpuApplicationBuilder {
@@ -42,7 +61,15 @@ pub struct GpuApplicationBuilder {
 impl GpuApplicationBuilder {
     #[must_use]
     pub async fn new(window: WindowOrFallback) -> anyhow::Result<Self> {
-        let (context, surface) = WgpuContext::new(window).await?;
+        Self::with_settings(window, &GpuSettings::default()).await
+    }
+
+    #[must_use]
+    pub async fn with_settings(
+        window: WindowOrFallback,
+        settings: &GpuSettings,
+    ) -> anyhow::Result<Self> {
+        let (context, surface) = WgpuContext::new(window, settings).await?;
         Ok(Self { context, surface })
     }
 
@@ -55,9 +82,14 @@ impl GpuApplicationBuilder {
 /// Values that change *every frame*.
 #[derive(Clone)]
 pub struct FrameData {
+    pub time: FrameTime,
     pub camera: crate::camera::Camera,
     pub mouse_pos: glam::Vec2,
     pub mouse_held: bool,
+    pub tone_mapping: ToneMappingSettings,
+    pub lighting: LightingSettings,
+    pub background: Background,
+    pub ground_plane: GroundPlaneSettings,
     pub lod_stage: Option<std::sync::Arc<dyn Fn(&crate::game::ShaderId, &str) + 'static>>,
 }
 
@@ -66,15 +98,45 @@ pub struct GpuApplication {
     surface: RwSignal<SurfaceOrFallback>,
     profiler: StoredValue<GpuProfiler>,
     profiling_enabled: bool,
+    /// The settings that the game requested, which might not be supported.
+    requested_anti_aliasing: Option<AntiAliasingSettings>,
+    anti_aliasing: RwSignal<AntiAliasingSettings>,
+    ambient_occlusion: RwSignal<AmbientOcclusion>,
+    skybox: RwSignal<Option<TextureId>>,
     _runtime: Owner,
     render_tree: Arc<dyn Fn(&FrameData) -> Result<RenderResults, wgpu::SurfaceError>>,
     shaders: RwSignal<HashMap<ShaderId, Arc<ShaderPipelines>>>,
+    textures: RwSignal<HashMap<TextureId, Arc<Texture>>>,
     set_desired_size: WriteSignal<UVec2>,
     set_force_wait: WriteSignal<bool>,
     /// Sets the threshold factor for the LOD algorithm
     set_threshold_factor: WriteSignal<f32>,
     cursor_capture: WindowCursorCapture,
     models: SignalVec<ModelInfo>,
+    model_bounds: ModelBounds,
+    depth_probes: DepthProbes,
+}
+
+/// What the render pipelines of the main render pass have to be compatible with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RenderTargets {
+    pub sample_count: u32,
+    /// Whether the ambient light is written to a second target
+    pub ambient: bool,
+}
+
+/// Bounds of the models in model space, by model ID. Gets filled in asynchronously.
+#[derive(Clone, Default)]
+struct ModelBounds(Arc<Mutex<HashMap<String, Aabb>>>);
+
+/// The points in the scene under the cursor and in the middle of the screen. Get filled in asynchronously.
+#[derive(Clone, Default)]
+struct DepthProbes(Arc<Mutex<ProbedPositions>>);
+
+#[derive(Clone, Copy, Default)]
+struct ProbedPositions {
+    cursor: Option<Vec3>,
+    center: Option<Vec3>,
 }
 
 const PATCH_SIZES: [u32; 5] = [2, 4, 8, 16, 32];
@@ -83,6 +145,24 @@ const MAX_PATCH_COUNT: u32 = 100_000;
 #[derive(Clone)]
 struct MissingShader(Arc<ShaderPipelines>);
 
+#[derive(Clone)]
+struct MissingTextures {
+    /// For user, color and roughness textures
+    white: Arc<Texture>,
+    flat_normal: Arc<Texture>,
+}
+
+/// Material textures repeat across the surface
+#[derive(Clone)]
+struct MaterialSampler(Arc<wgpu::Sampler>);
+
+#[derive(Clone)]
+struct MaterialTextures {
+    albedo: Arc<Texture>,
+    roughness: Arc<Texture>,
+    normal: Arc<Texture>,
+}
+
 impl GpuApplication {
     pub fn new(context: WgpuContext, surface: SurfaceOrFallback) -> Self {
         let runtime = Owner::new();
@@ -94,10 +174,49 @@ impl GpuApplication {
         let (desired_size, set_desired_size) = signal(UVec2::new(1, 1));
         let (force_wait, set_force_wait) = signal(false);
         let (threshold_factor, set_threshold_factor) = signal(1.0f32);
+        let anti_aliasing = RwSignal::new(AntiAliasingSettings {
+            msaa_samples: 1,
+            fxaa: false,
+        });
+        let ambient_occlusion = RwSignal::new(AmbientOcclusion::Off);
+        let skybox = RwSignal::new(None);
         let models = SignalVec::new();
+        let model_bounds = ModelBounds::default();
+        provide_context(model_bounds.clone());
+        let depth_probes = DepthProbes::default();
+        provide_context(depth_probes.clone());
 
         provide_context(MissingShader(make_missing_shader(&context)));
         let shaders = RwSignal::new(HashMap::new());
+        provide_context(MissingTextures {
+            white: Arc::new(Texture::from_info(
+                &context.device,
+                &context.queue,
+                &TextureInfo::white(),
+                "Missing Texture",
+            )),
+            flat_normal: Arc::new(Texture::from_info(
+                &context.device,
+                &context.queue,
+                &TextureInfo::flat_normal(),
+                "Missing Normal Texture",
+            )),
+        });
+        // The material textures have no mipmaps, so the sampler always reads the full resolution
+        provide_context(MaterialSampler(Arc::new(context.device.create_sampler(
+            &wgpu::SamplerDescriptor {
+                label: Some("Material Sampler"),
+                address_mode_u: wgpu::AddressMode::Repeat,
+                address_mode_v: wgpu::AddressMode::Repeat,
+                address_mode_w: wgpu::AddressMode::Repeat,
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                mipmap_filter: wgpu::FilterMode::Nearest,
+                lod_max_clamp: 0.0,
+                ..Default::default()
+            },
+        ))));
+        let textures = RwSignal::new(HashMap::new());
 
         let render_tree = Owner::with(&runtime, || {
             Arc::new(render_component(
@@ -106,7 +225,11 @@ impl GpuApplication {
                 desired_size,
                 threshold_factor,
                 force_wait,
+                anti_aliasing,
+                ambient_occlusion,
+                skybox,
                 shaders,
+                textures,
                 models.clone(),
             ))
         });
@@ -116,15 +239,22 @@ impl GpuApplication {
             surface,
             profiler,
             profiling_enabled: false,
+            requested_anti_aliasing: None,
+            anti_aliasing,
+            ambient_occlusion,
+            skybox,
             _runtime: runtime,
             render_tree,
             shaders,
+            textures,
 
             set_desired_size,
             set_threshold_factor,
             set_force_wait,
             cursor_capture: WindowCursorCapture::Free,
             models,
+            model_bounds,
+            depth_probes,
         }
     }
 
@@ -156,6 +286,33 @@ impl GpuApplication {
         });
     }
 
+    pub fn set_texture(&self, texture_id: TextureId, info: &TextureInfo) {
+        let texture = Texture::from_info(
+            &self.context.device,
+            &self.context.queue,
+            info,
+            &texture_id.0,
+        );
+        self.textures.update(|textures| {
+            textures.insert(texture_id, Arc::new(texture));
+        });
+    }
+
+    pub fn remove_texture(&self, texture_id: &TextureId) {
+        self.textures.update(|textures| {
+            textures.remove(texture_id);
+        });
+    }
+
+    /// The parameters that a successfully compiled shader declares.
+    pub fn get_shader_parameters(&self, shader_id: &ShaderId) -> Option<ShaderParameters> {
+        self.shaders.with_untracked(|shaders| {
+            shaders
+                .get(shader_id)
+                .map(|shader| shader.parameters.clone())
+        })
+    }
+
     pub fn render(&mut self, game: &GameRes) -> Result<RenderResults, wgpu::SurfaceError> {
         self.cursor_capture = self.update_cursor_capture(game.cursor_capture);
 
@@ -170,21 +327,76 @@ impl GpuApplication {
                 .unwrap();
         }
 
+        if self.requested_anti_aliasing.as_ref() != Some(&game.anti_aliasing) {
+            self.requested_anti_aliasing = Some(game.anti_aliasing.clone());
+            self.set_anti_aliasing(game.anti_aliasing.clone());
+        }
+        if self.ambient_occlusion.get_untracked() != game.ambient_occlusion {
+            self.ambient_occlusion.set(game.ambient_occlusion);
+        }
+        let skybox = match &game.background {
+            Background::Skybox(texture_id) => Some(texture_id),
+            _ => None,
+        };
+        if self.skybox.with_untracked(|v| v.as_ref() != skybox) {
+            self.skybox.set(skybox.cloned());
+        }
+
         // TODO: Don't do "untrack"
         reactive_graph::graph::untrack(|| {
             update_models(self.models.clone(), &game.models);
         });
+        // Also removes the results of readbacks that finished after their model was removed
+        self.model_bounds
+            .0
+            .lock()
+            .unwrap()
+            .retain(|id, _| game.models.iter().any(|model| &model.id == id));
         let render_tree = self.render_tree.clone();
         let frame_data = FrameData {
+            time: game.time,
             camera: game.camera.clone(),
             mouse_pos: game.mouse,
             mouse_held: game.mouse_held,
+            tone_mapping: game.tone_mapping.clone(),
+            lighting: game.lighting.clone(),
+            background: game.background.clone(),
+            ground_plane: game.ground_plane.clone(),
             lod_stage: game.lod_stage.clone(),
         };
         let render = RenderEffect::new(move |_| (render_tree)(&frame_data));
         render.take_value().expect("Render should have executed")
     }
 
+    /// Reads back the last rendered frame of a headless renderer.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn capture_frame(&self) -> anyhow::Result<image::RgbaImage> {
+        self.surface
+            .with_untracked(|surface| surface.read_fallback_texture(&self.context))
+    }
+
+    /// The bounds of the models in model space. They lag a few frames behind.
+    pub fn model_bounds(&self) -> HashMap<String, Aabb> {
+        self.model_bounds.0.lock().unwrap().clone()
+    }
+
+    /// The point in the scene under the cursor, or `None` when the cursor is over the background.
+    /// Lags a few frames behind.
+    pub fn cursor_world_position(&self) -> Option<Vec3> {
+        self.depth_probes.0.lock().unwrap().cursor
+    }
+
+    /// The point in the scene in the middle of the screen, or `None` when the background is there.
+    /// Lags a few frames behind.
+    pub fn center_world_position(&self) -> Option<Vec3> {
+        self.depth_probes.0.lock().unwrap().center
+    }
+
+    /// In physical pixels
+    pub fn size(&self) -> UVec2 {
+        self.surface.with_untracked(|surface| surface.size())
+    }
+
     pub fn resize(&self, new_size: UVec2) {
         self.set_desired_size.set(new_size);
     }
@@ -193,6 +405,17 @@ impl GpuApplication {
         self.set_force_wait.set(true);
     }
 
+    fn set_anti_aliasing(&self, mut settings: AntiAliasingSettings) {
+        if !self.context.supports_sample_count(settings.msaa_samples) {
+            log::warn!(
+                "{} MSAA samples are not supported, falling back to 1",
+                settings.msaa_samples
+            );
+            settings.msaa_samples = 1;
+        }
+        self.anti_aliasing.set(settings);
+    }
+
     pub fn set_threshold_factor(&self, factor: f32) {
         self.set_threshold_factor
             .set(factor.clamp(0.0001, 100000.0));
@@ -210,21 +433,66 @@ fn render_component(
     desired_size: ReadSignal<UVec2>,
     threshold_factor: ReadSignal<f32>,
     force_wait: ReadSignal<bool>,
+    anti_aliasing: RwSignal<AntiAliasingSettings>,
+    ambient_occlusion: RwSignal<AmbientOcclusion>,
+    skybox: RwSignal<Option<TextureId>>,
     shaders: RwSignal<HashMap<ShaderId, Arc<ShaderPipelines>>>,
+    textures: RwSignal<HashMap<TextureId, Arc<Texture>>>,
     models: SignalVec<ModelInfo>,
 ) -> impl Fn(&FrameData) -> Result<RenderResults, wgpu::SurfaceError> {
     let context = wgpu_context();
-    let frame_counter = RwSignal::new(FrameCounter::new());
-    let new_frame_time = move || frame_counter.write().new_frame();
+
+    let sample_count = Memo::new(move |_| anti_aliasing.read().msaa_samples);
+    let fxaa_enabled = Memo::new(move |_| anti_aliasing.read().fxaa);
+    let render_targets = Memo::new(move |_| RenderTargets {
+        sample_count: sample_count.get(),
+        ambient: ambient_occlusion.get() != AmbientOcclusion::Off,
+    });
 
     let depth_texture = Memo::new_computed(move |_| {
         Texture::create_depth_texture(
             &wgpu_context().device,
             surface.read().size(),
+            sample_count.get(),
             "Depth Texture",
         )
     });
 
+    let hdr_texture = Memo::new_computed(move |_| {
+        Texture::create_render_target(
+            &wgpu_context().device,
+            surface.read().size(),
+            Texture::HDR_FORMAT,
+            1,
+            "HDR Texture",
+        )
+    });
+
+    // Gets resolved into the HDR texture
+    let msaa_texture = Memo::new_computed(move |_| {
+        let sample_count = sample_count.get();
+        (sample_count > 1).then(|| {
+            Texture::create_render_target(
+                &wgpu_context().device,
+                surface.read().size(),
+                Texture::HDR_FORMAT,
+                sample_count,
+                "MSAA Texture",
+            )
+        })
+    });
+
+    let ambient_occlusion_component = ambient_occlusion_component(
+        surface,
+        depth_texture,
+        hdr_texture,
+        sample_count,
+        ambient_occlusion,
+    );
+    let cursor_depth_component = cursor_depth_component(surface, depth_texture, sample_count);
+    let tone_mapping_component = tone_mapping_component(hdr_texture, skybox, textures);
+    let fxaa_component = fxaa_component(surface, fxaa_enabled);
+
     let scene_data = StoredValue::new(SceneData::new(&context.device));
     let render_bind_group_0 = StoredValue::new(
         scene_data.with_value(|scene_data| scene_data.as_bind_group_0(&context.device)),
@@ -241,6 +509,16 @@ fn render_component(
                 },
             )
         }),
+        bounds_bind_group_0: scene_data.with_value(|scene_data| {
+            compute_bounds::bind_groups::BindGroup0::from_bindings(
+                &context.device,
+                compute_bounds::bind_groups::BindGroupLayout0 {
+                    mouse: scene_data.mouse_buffer.as_entire_buffer_binding(),
+                    screen: scene_data.screen_buffer.as_entire_buffer_binding(),
+                    time: scene_data.time_buffer.as_entire_buffer_binding(),
+                },
+            )
+        }),
     });
 
     let copy_patches_pipeline = StoredValue::new(context.device.create_compute_pipeline(
@@ -271,13 +549,14 @@ fn render_component(
             .collect::<Vec<_>>(),
     );
 
-    let ground_plane_component = ground_plane_component(surface);
+    let ground_plane_component = ground_plane_component(surface, render_targets);
 
     let models_components = ForEach::new(move || models.iter(), |model| model.clone(), {
         move |model: ArcReadSignal<ModelInfo>| {
             model_component(
                 surface,
                 shaders,
+                textures,
                 model.clone(),
                 threshold_factor,
                 compute_patches,
@@ -285,6 +564,7 @@ fn render_component(
                 RenderInfo {
                     render_bind_group_0,
                     meshes: quad_meshes,
+                    render_targets,
                 },
             )
         }
@@ -292,7 +572,7 @@ fn render_component(
 
     move |render_data: &FrameData| {
         let context = wgpu_context();
-        let frame_time = new_frame_time();
+        let frame_time = render_data.time;
         // 2. Render
         let surface_texture = match surface.with(|surface| surface.surface_texture(&context)) {
             Ok(v) => v,
@@ -327,26 +607,47 @@ fn render_component(
 
             models_components.for_each(|renderers| {
                 (renderers.lod_stage)(render_data, &mut commands);
+                (renderers.bounds_stage)(&mut commands);
             });
 
+            let hdr_texture = hdr_texture.read();
+            let msaa_texture = msaa_texture.read();
+            let (view, resolve_target) = match msaa_texture.as_ref() {
+                Some(msaa_texture) => (&msaa_texture.view, Some(&hdr_texture.view)),
+                None => (&hdr_texture.view, None),
+            };
+            let mut color_attachments = vec![Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target,
+                ops: wgpu::Operations {
+                    // The background is drawn by the tone mapping
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: wgpu::StoreOp::Store,
+                },
+            })];
+            let ambient_texture = ambient_occlusion_component.ambient_texture.read();
+            let msaa_ambient_texture = ambient_occlusion_component.msaa_ambient_texture.read();
+            if let Some(ambient_texture) = ambient_texture.as_ref() {
+                let (view, resolve_target) = match msaa_ambient_texture.as_ref() {
+                    Some(msaa_texture) => (&msaa_texture.view, Some(&ambient_texture.view)),
+                    None => (&ambient_texture.view, None),
+                };
+                color_attachments.push(Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                }));
+            }
+
             let mut render_pass = commands.scoped_render_pass(
                 "Render Pass",
                 &context.device,
                 wgpu::RenderPassDescriptor {
                     label: Some("Render Pass"),
-                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                        view: surface_texture.texture_view(),
-                        resolve_target: None,
-                        ops: wgpu::Operations {
-                            load: wgpu::LoadOp::Clear(wgpu::Color {
-                                r: 0.1,
-                                g: 0.2,
-                                b: 0.3,
-                                a: 1.0,
-                            }),
-                            store: wgpu::StoreOp::Store,
-                        },
-                    })],
+                    color_attachments: &color_attachments,
                     depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
                         view: &depth_texture.read().view,
                         depth_ops: Some(wgpu::Operations {
@@ -360,16 +661,43 @@ fn render_component(
                 },
             );
 
-            // Render the models
+            // Render the opaque models
             models_components.for_each(|renderers| {
-                (renderers.render_stage)(&mut render_pass);
+                if !renderers.transparent.get() {
+                    (renderers.render_stage)(&mut render_pass);
+                }
             });
 
             // Render transparent objects
             (ground_plane_component)(render_data, &mut render_pass);
 
-            // Finish the profiler
+            // From back to front, since they do not write to the depth buffer
+            models_components.for_each_sorted_by(
+                |renderers| {
+                    renderers.transparent.get().then(|| {
+                        let position = renderers.model.read().transform.position;
+                        -render_data.camera.position.distance_squared(position)
+                    })
+                },
+                |renderers| {
+                    (renderers.render_stage)(&mut render_pass);
+                },
+            );
+
             std::mem::drop(render_pass);
+
+            (cursor_depth_component)(render_data, &mut commands);
+            (ambient_occlusion_component.render)(render_data, &mut commands);
+
+            let fxaa_texture = fxaa_component.render_target.read();
+            let tone_mapping_target = match fxaa_texture.as_ref() {
+                Some(fxaa_texture) => &fxaa_texture.view,
+                None => surface_texture.texture_view(),
+            };
+            (tone_mapping_component)(render_data, tone_mapping_target, &mut commands);
+            (fxaa_component.render)(surface_texture.texture_view(), &mut commands);
+
+            // Finish the profiler
             std::mem::drop(commands);
 
             command_encoder
@@ -378,6 +706,8 @@ fn render_component(
         context
             .queue
             .submit(std::iter::once(command_encoder.finish()));
+        // Lets the bounds readbacks finish
+        context.device.poll(wgpu::Maintain::Poll);
 
         surface_texture.present();
 
@@ -404,6 +734,7 @@ fn render_component(
 /// Renders the ground plane
 fn ground_plane_component(
     surface: RwSignal<SurfaceOrFallback>,
+    render_targets: Memo<RenderTargets>,
 ) -> impl Fn(&FrameData, &mut wgpu_profiler::OwningScope<'_, wgpu::RenderPass<'_>>) {
     let context = wgpu_context();
     let quad_mesh = Mesh::new_tesselated_quad(&context.device, 2);
@@ -453,6 +784,20 @@ fn ground_plane_component(
         let context = context.clone();
         move |_| {
             let shader = shader.read();
+            let render_targets = render_targets.get();
+            // The ground plane does not write to the ambient light target
+            let mut color_targets = vec![Some(wgpu::ColorTargetState {
+                format: Texture::HDR_FORMAT,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })];
+            if render_targets.ambient {
+                color_targets.push(Some(wgpu::ColorTargetState {
+                    format: Texture::HDR_FORMAT,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::empty(),
+                }));
+            }
             context
                 .device
                 .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
@@ -462,14 +807,12 @@ fn ground_plane_component(
                         &shader,
                         &ground_plane::vs_main_entry(wgpu::VertexStepMode::Vertex),
                     ),
-                    fragment: Some(ground_plane::fragment_state(
-                        &shader,
-                        &ground_plane::fs_main_entry([Some(wgpu::ColorTargetState {
-                            format: context.view_format,
-                            blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                            write_mask: wgpu::ColorWrites::ALL,
-                        })]),
-                    )),
+                    fragment: Some(wgpu::FragmentState {
+                        module: &shader,
+                        entry_point: Some(ground_plane::ENTRY_FS_MAIN),
+                        targets: &color_targets,
+                        compilation_options: Default::default(),
+                    }),
                     primitive: Default::default(),
                     depth_stencil: Some(wgpu::DepthStencilState {
                         format: Texture::DEPTH_FORMAT,
@@ -478,7 +821,10 @@ fn ground_plane_component(
                         stencil: Default::default(),
                         bias: Default::default(),
                     }),
-                    multisample: Default::default(),
+                    multisample: wgpu::MultisampleState {
+                        count: render_targets.sample_count,
+                        ..Default::default()
+                    },
                     multiview: None,
                     cache: Default::default(),
                 })
@@ -491,7 +837,11 @@ fn ground_plane_component(
         &ground_plane::Uniforms {
             model_matrix: glam::Mat4::IDENTITY,
             view_projection_matrix: glam::Mat4::IDENTITY,
-            grid_scale: 0.0,
+            grid_spacing: 1.0,
+            major_lines: 0,
+            fade_distance: 1.0,
+            mode: 0,
+            axis_colors: 0,
         },
         wgpu::BufferUsages::COPY_DST,
     );
@@ -506,25 +856,36 @@ fn ground_plane_component(
     move |render_data: &FrameData, render_pass| {
         #[cfg(feature = "desktop")]
         let _watcher = &_file_watcher;
+        let settings = render_data.ground_plane.clone().clamped();
+        if !settings.visible {
+            return;
+        }
         let size = 100_000.0;
+        // Zooming out makes the grid coarser
         let inv_grid_scale = (render_data.camera.position.distance(glam::Vec3::ZERO) / 50.0)
             .max(1.0)
             .log2()
             .ceil()
             .exp2()
             .max(1.0);
-        let grid_scale = 1.0 / inv_grid_scale;
         uniforms.write_buffer(
             &context.queue,
             &ground_plane::Uniforms {
                 model_matrix: glam::Mat4::from_scale_rotation_translation(
                     glam::Vec3::splat(size),
                     glam::Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2),
-                    glam::Vec3::new(-size / 2., 0.0, size / 2.),
+                    glam::Vec3::new(-size / 2., settings.height, size / 2.),
                 ),
                 view_projection_matrix: render_data.camera.projection_matrix(surface.read().size())
                     * render_data.camera.view_matrix(),
-                grid_scale,
+                grid_spacing: settings.grid_spacing * inv_grid_scale,
+                major_lines: settings.major_lines,
+                fade_distance: settings.fade_distance * inv_grid_scale,
+                mode: match settings.mode {
+                    GroundPlaneMode::Grid => 0,
+                    GroundPlaneMode::Axes => 1,
+                },
+                axis_colors: settings.axis_colors as u32,
             },
         );
 
@@ -536,10 +897,439 @@ fn ground_plane_component(
     }
 }
 
+/// Maps the HDR texture to the output, which has a limited range, and draws the background behind it
+fn tone_mapping_component(
+    hdr_texture: Memo<Texture>,
+    skybox: RwSignal<Option<TextureId>>,
+    textures: RwSignal<HashMap<TextureId, Arc<Texture>>>,
+) -> impl Fn(&FrameData, &wgpu::TextureView, &mut wgpu_profiler::Scope<'_, wgpu::CommandEncoder>) {
+    let context = wgpu_context();
+    let shader = tonemap::create_shader_module(&context.device);
+    let pipeline = context
+        .device
+        .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Tone Mapping"),
+            layout: Some(&tonemap::create_pipeline_layout(&context.device)),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some(tonemap::ENTRY_VS_MAIN),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some(tonemap::ENTRY_FS_MAIN),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: context.view_format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: Default::default(),
+            depth_stencil: None,
+            multisample: Default::default(),
+            multiview: None,
+            cache: Default::default(),
+        });
+
+    let uniforms = StoredValue::new(TypedBuffer::new_uniform(
+        &context.device,
+        "Tone Mapping Uniforms",
+        &tonemap::ToneMapping {
+            exposure: 0.0,
+            curve: 0,
+        },
+        wgpu::BufferUsages::COPY_DST,
+    ));
+
+    let background_uniforms = StoredValue::new(TypedBuffer::new_uniform(
+        &context.device,
+        "Background Uniforms",
+        &tonemap::Background {
+            inv_view_projection: glam::Mat4::IDENTITY,
+            top_color: glam::Vec4::ZERO,
+            bottom_color: glam::Vec4::ZERO,
+            mode: 0,
+        },
+        wgpu::BufferUsages::COPY_DST,
+    ));
+
+    // The equirectangular skybox wraps around horizontally, and stops at the poles
+    let skybox_sampler =
+        StoredValue::new(context.device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Skybox Sampler"),
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        }));
+
+    let skybox_texture = Memo::new_computed(move |_| {
+        let missing = expect_context::<MissingTextures>();
+        get_texture(&textures.read(), &skybox.read(), &missing.white)
+    });
+
+    let bind_group_0 = Memo::new_computed(move |_| {
+        let skybox_texture = skybox_texture.read();
+        tonemap::bind_groups::BindGroup0::from_bindings(
+            &wgpu_context().device,
+            tonemap::bind_groups::BindGroupLayout0 {
+                input_texture: &hdr_texture.read().view,
+                tone_mapping: uniforms.read_value().as_entire_buffer_binding(),
+                background: background_uniforms.read_value().as_entire_buffer_binding(),
+                skybox_texture: &skybox_texture.view,
+                skybox_sampler: &skybox_sampler.read_value(),
+            },
+        )
+    });
+
+    move |render_data: &FrameData,
+          output: &wgpu::TextureView,
+          commands: &mut wgpu_profiler::Scope<'_, wgpu::CommandEncoder>| {
+        let context = wgpu_context();
+        uniforms.read_value().write_buffer(
+            &context.queue,
+            &tonemap::ToneMapping {
+                exposure: render_data.tone_mapping.exposure,
+                curve: match render_data.tone_mapping.curve {
+                    ToneMappingCurve::Aces => 0,
+                    ToneMappingCurve::AgX => 1,
+                    ToneMappingCurve::Reinhard => 2,
+                },
+            },
+        );
+        let (top_color, bottom_color, mode) = match &render_data.background {
+            Background::Solid(color) => (*color, *color, 0),
+            Background::Gradient { top, bottom } => (*top, *bottom, 1),
+            Background::Skybox(_) => (glam::Vec3::ZERO, glam::Vec3::ZERO, 2),
+        };
+        let size = hdr_texture.read().texture.size();
+        let view_projection = render_data
+            .camera
+            .projection_matrix(UVec2::new(size.width, size.height))
+            * render_data.camera.view_matrix();
+        background_uniforms.read_value().write_buffer(
+            &context.queue,
+            &tonemap::Background {
+                inv_view_projection: view_projection.inverse(),
+                top_color: top_color.extend(1.0),
+                bottom_color: bottom_color.extend(1.0),
+                mode,
+            },
+        );
+
+        let mut render_pass = commands.scoped_render_pass(
+            "Tone Mapping Pass",
+            &context.device,
+            wgpu::RenderPassDescriptor {
+                label: Some("Tone Mapping Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: output,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            },
+        );
+        render_pass.set_pipeline(&pipeline);
+        bind_group_0.read().set(&mut render_pass.recorder);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+/// Reads back the depth under the cursor, and turns it into a point in the scene.
+/// Like the bounds, the result arrives a frame later.
+fn cursor_depth_component(
+    surface: RwSignal<SurfaceOrFallback>,
+    depth_texture: Memo<Texture>,
+    sample_count: Memo<u32>,
+) -> impl Fn(&FrameData, &mut wgpu_profiler::Scope<'_, wgpu::CommandEncoder>) {
+    let context = wgpu_context();
+    let depth_probes = expect_context::<DepthProbes>();
+    let depth_buffer = StoredValue::new(TypedBuffer::new_storage(
+        &context.device,
+        "Probe Depths Buffer",
+        &cursor_depth::ProbeDepths {
+            cursor: 0.0,
+            center: 0.0,
+        },
+        wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+    ));
+    let probes_uniform = StoredValue::new(TypedBuffer::new_uniform(
+        &context.device,
+        "Probes Uniform",
+        &cursor_depth::Probes {
+            cursor: UVec2::ZERO,
+            center: UVec2::ZERO,
+        },
+        wgpu::BufferUsages::COPY_DST,
+    ));
+    let pipeline = context
+        .device
+        .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Cursor Depth"),
+            layout: Some(&cursor_depth::create_pipeline_layout(&context.device)),
+            module: &cursor_depth::create_shader_module(&context.device),
+            entry_point: Some(cursor_depth::ENTRY_MAIN),
+            compilation_options: Default::default(),
+            cache: Default::default(),
+        });
+    // Multisampled depth textures cannot be copied, so a shader reads them instead
+    let bind_group_0 = Memo::new_computed(move |_| {
+        (sample_count.get() > 1).then(|| {
+            cursor_depth::bind_groups::BindGroup0::from_bindings(
+                &wgpu_context().device,
+                cursor_depth::bind_groups::BindGroupLayout0 {
+                    depth_texture: &depth_texture.read().view,
+                    probes: probes_uniform.read_value().as_entire_buffer_binding(),
+                    depths: depth_buffer.read_value().as_entire_buffer_binding(),
+                },
+            )
+        })
+    });
+
+    // What is needed to unproject the depths that were copied in the last frame
+    let copied = StoredValue::new(None::<CopiedProbes>);
+    let downloading = Arc::new(AtomicBool::new(false));
+
+    move |render_data: &FrameData, commands: &mut wgpu_profiler::Scope<'_, wgpu::CommandEncoder>| {
+        let context = wgpu_context();
+        // Probes again as soon as the last readback finished, without waiting for the GPU
+        if downloading.load(Ordering::Acquire) {
+            return;
+        }
+        if let Some(copied_probes) = copied.get_value() {
+            copied.set_value(None);
+            downloading.store(true, Ordering::Release);
+            let downloading = downloading.clone();
+            let depth_probes = depth_probes.clone();
+            depth_buffer.with_value(|depth_buffer| {
+                wgpu::util::DownloadBuffer::read_buffer(
+                    &context.device,
+                    &context.queue,
+                    &depth_buffer.slice(..),
+                    move |result| {
+                        if let Ok(data) = result {
+                            let cursor = f32::from_le_bytes(data[0..4].try_into().unwrap());
+                            let center = f32::from_le_bytes(data[4..8].try_into().unwrap());
+                            *depth_probes.0.lock().unwrap() = ProbedPositions {
+                                cursor: copied_probes
+                                    .cursor_ndc
+                                    .and_then(|ndc| copied_probes.unproject(ndc, cursor)),
+                                center: copied_probes.unproject(copied_probes.center_ndc, center),
+                            };
+                        }
+                        downloading.store(false, Ordering::Release);
+                    },
+                );
+            });
+            return;
+        }
+
+        let size = surface.read().size();
+        let mouse = render_data.mouse_pos;
+        let cursor_inside =
+            mouse.x >= 0.0 && mouse.y >= 0.0 && mouse.x < size.x as f32 && mouse.y < size.y as f32;
+        let cursor_pixel = if cursor_inside {
+            mouse.as_uvec2()
+        } else {
+            UVec2::ZERO
+        };
+        let center_pixel = size / 2;
+        if let Some(bind_group_0) = bind_group_0.read().as_ref() {
+            probes_uniform.read_value().write_buffer(
+                &context.queue,
+                &cursor_depth::Probes {
+                    cursor: cursor_pixel,
+                    center: center_pixel,
+                },
+            );
+            let mut compute_pass = commands.scoped_compute_pass("Cursor Depth", &context.device);
+            compute_pass.set_pipeline(&pipeline);
+            cursor_depth::set_bind_groups(&mut compute_pass.recorder, bind_group_0);
+            compute_pass.dispatch_workgroups(1, 1, 1);
+        } else {
+            for (pixel, offset) in [(cursor_pixel, 0), (center_pixel, 4)] {
+                commands.copy_texture_to_buffer(
+                    wgpu::ImageCopyTexture {
+                        texture: &depth_texture.read().texture,
+                        mip_level: 0,
+                        origin: wgpu::Origin3d {
+                            x: pixel.x,
+                            y: pixel.y,
+                            z: 0,
+                        },
+                        aspect: wgpu::TextureAspect::DepthOnly,
+                    },
+                    wgpu::ImageCopyBuffer {
+                        buffer: depth_buffer.read_value().buffer(),
+                        layout: wgpu::ImageDataLayout {
+                            offset,
+                            bytes_per_row: None,
+                            rows_per_image: None,
+                        },
+                    },
+                    wgpu::Extent3d {
+                        width: 1,
+                        height: 1,
+                        depth_or_array_layers: 1,
+                    },
+                );
+            }
+        }
+        // The center of the pixel, in normalized device coordinates
+        let to_ndc = |pixel: UVec2| {
+            Vec2::new(
+                (pixel.x as f32 + 0.5) / size.x as f32 * 2.0 - 1.0,
+                1.0 - (pixel.y as f32 + 0.5) / size.y as f32 * 2.0,
+            )
+        };
+        let view_projection =
+            render_data.camera.projection_matrix(size) * render_data.camera.view_matrix();
+        copied.set_value(Some(CopiedProbes {
+            inverse_view_projection: view_projection.inverse(),
+            cursor_ndc: cursor_inside.then(|| to_ndc(cursor_pixel)),
+            center_ndc: to_ndc(center_pixel),
+        }));
+    }
+}
+
+#[derive(Clone, Copy)]
+struct CopiedProbes {
+    inverse_view_projection: Mat4,
+    /// `None` when the cursor was outside of the window
+    cursor_ndc: Option<Vec2>,
+    center_ndc: Vec2,
+}
+
+impl CopiedProbes {
+    fn unproject(&self, ndc: Vec2, depth: f32) -> Option<Vec3> {
+        // With a reversed Z, the background is infinitely far away at 0
+        (depth > 0.0).then(|| {
+            self.inverse_view_projection
+                .project_point3(ndc.extend(depth))
+        })
+    }
+}
+
+struct FxaaComponent<Render> {
+    /// Where the scene should be rendered to, when FXAA is enabled.
+    /// It is sRGB like the output, so `Fxaa.wgsl` encodes the samples again to find the edges.
+    render_target: Memo<Option<Texture>>,
+    render: Render,
+}
+
+/// Applies FXAA to the render target, and writes the result into the output
+fn fxaa_component(
+    surface: RwSignal<SurfaceOrFallback>,
+    enabled: Memo<bool>,
+) -> FxaaComponent<impl Fn(&wgpu::TextureView, &mut wgpu_profiler::Scope<'_, wgpu::CommandEncoder>)>
+{
+    let context = wgpu_context();
+    let shader = fxaa::create_shader_module(&context.device);
+    let pipeline = context
+        .device
+        .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("FXAA"),
+            layout: Some(&fxaa::create_pipeline_layout(&context.device)),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some(fxaa::ENTRY_VS_MAIN),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some(fxaa::ENTRY_FS_MAIN),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: context.view_format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: Default::default(),
+            depth_stencil: None,
+            multisample: Default::default(),
+            multiview: None,
+            cache: Default::default(),
+        });
+
+    let render_target = Memo::new_computed(move |_| {
+        enabled.get().then(|| {
+            let context = wgpu_context();
+            Texture::create_render_target(
+                &context.device,
+                surface.read().size(),
+                context.view_format,
+                1,
+                "FXAA Input Texture",
+            )
+        })
+    });
+
+    let bind_group_0 = Memo::new_computed(move |_| {
+        render_target.read().as_ref().map(|texture| {
+            fxaa::bind_groups::BindGroup0::from_bindings(
+                &wgpu_context().device,
+                fxaa::bind_groups::BindGroupLayout0 {
+                    input_texture: &texture.view,
+                    input_sampler: &texture.sampler,
+                },
+            )
+        })
+    });
+
+    let render =
+        move |output: &wgpu::TextureView,
+              commands: &mut wgpu_profiler::Scope<'_, wgpu::CommandEncoder>| {
+            let bind_group_0 = bind_group_0.read();
+            let Some(bind_group_0) = bind_group_0.as_ref() else {
+                return;
+            };
+            let mut render_pass = commands.scoped_render_pass(
+                "FXAA Pass",
+                &wgpu_context().device,
+                wgpu::RenderPassDescriptor {
+                    label: Some("FXAA Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: output,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                    timestamp_writes: None,
+                    occlusion_query_set: None,
+                },
+            );
+            render_pass.set_pipeline(&pipeline);
+            bind_group_0.set(&mut render_pass.recorder);
+            render_pass.draw(0..3, 0..1);
+        };
+
+    FxaaComponent {
+        render_target,
+        render,
+    }
+}
+
 /// Returns multiple render functions
 fn model_component(
     surface: RwSignal<SurfaceOrFallback>,
     shaders: RwSignal<HashMap<ShaderId, Arc<ShaderPipelines>>>,
+    textures: RwSignal<HashMap<TextureId, Arc<Texture>>>,
     model: ArcReadSignal<ModelInfo>,
     threshold_factor: ReadSignal<f32>,
     compute_patches: StoredValue<ComputePatchesStep>,
@@ -547,6 +1337,7 @@ fn model_component(
     render_stage: RenderInfo,
 ) -> ModelRenderers<
     impl Fn(&FrameData, &mut wgpu_profiler::Scope<'_, wgpu::CommandEncoder>),
+    impl Fn(&mut wgpu_profiler::Scope<'_, wgpu::CommandEncoder>),
     impl Fn(&mut wgpu_profiler::OwningScope<'_, wgpu::RenderPass<'_>>),
 > {
     let virtual_model = Memo::new_computed({
@@ -558,40 +1349,133 @@ fn model_component(
         }
     });
 
+    let shader = Memo::new({
+        let model = model.clone();
+        move |_| {
+            let model = model.read();
+            let shaders_guard = shaders.read();
+            let shader = shaders_guard.get(&model.shader_id).cloned();
+            shader.unwrap_or_else(|| expect_context::<MissingShader>().0.clone())
+        }
+    });
+
+    let texture = Memo::new_computed({
+        let model = model.clone();
+        move |_| {
+            let missing = expect_context::<MissingTextures>();
+            get_texture(&textures.read(), &model.read().texture_id, &missing.white)
+        }
+    });
+
+    let material_textures = Memo::new_computed({
+        let model = model.clone();
+        move |_| {
+            let missing = expect_context::<MissingTextures>();
+            let textures = textures.read();
+            let model = model.read();
+            let material = &model.material_info;
+            MaterialTextures {
+                albedo: get_texture(&textures, &material.albedo_texture, &missing.white),
+                roughness: get_texture(&textures, &material.roughness_texture, &missing.white),
+                normal: get_texture(&textures, &material.normal_texture, &missing.flat_normal),
+            }
+        }
+    });
+
+    // Shared by the compute and the render shaders, and changes without recompiling any pipelines
+    let parameters_buffer = StoredValue::new(wgpu_context().device.create_buffer(
+        &wgpu::BufferDescriptor {
+            label: Some(&format!("{} Parameters Buffer", model.read().id)),
+            size: MAX_PARAMETERS_SIZE,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        },
+    ));
+
+    Effect::new({
+        let model = model.clone();
+        move |_| {
+            let contents = shader.read().parameters.to_bytes(&model.read().parameters);
+            if !contents.is_empty() {
+                parameters_buffer.with_value(|buffer| {
+                    wgpu_context().queue.write_buffer(buffer, 0, &contents);
+                });
+            }
+        }
+    });
+
+    let transparent = Memo::new({
+        let model = model.clone();
+        move |_| model.read().material_info.opacity < 1.0
+    });
+
+    let render_pipeline = Memo::new_computed(move |_| {
+        shader.read().render_pipeline(
+            &wgpu_context(),
+            render_stage.render_targets.get(),
+            transparent.get(),
+        )
+    });
+
     let lod_stage_component = lod_stage_component(
         surface,
-        shaders,
+        shader,
         model.clone(),
         virtual_model,
+        parameters_buffer,
+        texture,
+        material_textures,
         compute_patches,
         copy_patches_pipeline,
         threshold_factor,
     );
 
+    let bounds_component = bounds_component(
+        shader,
+        model.clone(),
+        parameters_buffer,
+        texture,
+        material_textures,
+        compute_patches,
+    );
+
     let render_component = render_model_component(
         render_stage.render_bind_group_0,
-        shaders,
+        render_pipeline,
         model.clone(),
         virtual_model,
+        parameters_buffer,
+        texture,
+        material_textures,
         render_stage.meshes,
     );
 
     ModelRenderers {
         lod_stage: lod_stage_component,
+        bounds_stage: bounds_component,
         render_stage: render_component,
+        transparent,
+        model,
     }
 }
 
-struct ModelRenderers<LodStage, RenderStage> {
+struct ModelRenderers<LodStage, BoundsStage, RenderStage> {
     lod_stage: LodStage,
+    bounds_stage: BoundsStage,
     render_stage: RenderStage,
+    /// Transparent models are rendered after the opaque ones, and are sorted by their distance.
+    transparent: Memo<bool>,
+    model: ArcReadSignal<ModelInfo>,
 }
 
 fn lod_stage_component(
     surface: RwSignal<SurfaceOrFallback>,
-    shaders: RwSignal<HashMap<ShaderId, Arc<ShaderPipelines>>>,
+    shader: Memo<Arc<ShaderPipelines>>,
     model: ArcReadSignal<ModelInfo>,
     virtual_model: Memo<VirtualModel>,
+    parameters_buffer: StoredValue<wgpu::Buffer>,
+    texture: Memo<Arc<Texture>>,
+    material_textures: Memo<MaterialTextures>,
     compute_patches: StoredValue<ComputePatchesStep>,
     copy_patches_pipeline: StoredValue<wgpu::ComputePipeline>,
     threshold_factor: ReadSignal<f32>,
@@ -708,6 +1592,9 @@ fn lod_stage_component(
         let context = wgpu_context();
         let virtual_model = virtual_model.read();
         let render_buffer = &virtual_model.render_buffer;
+        let texture = texture.read();
+        let material_textures = material_textures.read();
+        let material_sampler = expect_context::<MaterialSampler>().0;
         compute_patches::bind_groups::BindGroup1::from_bindings(
             &context.device,
             compute_patches::bind_groups::BindGroupLayout1 {
@@ -717,6 +1604,12 @@ fn lod_stage_component(
                 render_buffer_8: render_buffer[2].as_entire_buffer_binding(),
                 render_buffer_16: render_buffer[3].as_entire_buffer_binding(),
                 render_buffer_32: render_buffer[4].as_entire_buffer_binding(),
+                parameters: parameters_buffer.read_value().as_entire_buffer_binding(),
+                user_texture: &texture.view,
+                user_sampler: &texture.sampler,
+                albedo_texture: &material_textures.albedo.view,
+                roughness_texture: &material_textures.roughness.view,
+                material_sampler: &material_sampler,
             },
         )
     });
@@ -741,16 +1634,6 @@ fn lod_stage_component(
         ),
     ];
 
-    let shader = Memo::new({
-        let model = model.clone();
-        move |_| {
-            let model = model.read();
-            let shaders_guard = shaders.read();
-            let shader = shaders_guard.get(&model.shader_id).cloned();
-            shader.unwrap_or_else(|| expect_context::<MissingShader>().0.clone())
-        }
-    });
-
     move |frame_data: &FrameData, commands: &mut wgpu_profiler::Scope<'_, wgpu::CommandEncoder>| {
         let context = wgpu_context();
         let queue = &context.queue;
@@ -865,60 +1748,194 @@ fn lod_stage_component(
     }
 }
 
+/// Evaluates the model on a grid, and reads back its bounds.
+/// Only evaluates again when the model, its shader or its texture changes. Shaders that animate with the time keep their first bounds.
+fn bounds_component(
+    shader: Memo<Arc<ShaderPipelines>>,
+    model: ArcReadSignal<ModelInfo>,
+    parameters_buffer: StoredValue<wgpu::Buffer>,
+    texture: Memo<Arc<Texture>>,
+    material_textures: Memo<MaterialTextures>,
+    compute_patches: StoredValue<ComputePatchesStep>,
+) -> impl Fn(&mut wgpu_profiler::Scope<'_, wgpu::CommandEncoder>) {
+    let context = wgpu_context();
+    let model_bounds = expect_context::<ModelBounds>();
+    let empty_bounds = compute_bounds::Bounds {
+        min: [u32::MAX; 3],
+        max: [0; 3],
+    };
+    let bounds_buffer = StoredValue::new(TypedBuffer::new_storage(
+        &context.device,
+        &format!("{} Bounds Buffer", model.read().id),
+        &empty_bounds,
+        wgpu::BufferUsages::COPY_SRC | wgpu::BufferUsages::COPY_DST,
+    ));
+
+    let bind_group_1 = Memo::new_computed(move |_| {
+        let texture = texture.read();
+        let material_textures = material_textures.read();
+        let material_sampler = expect_context::<MaterialSampler>().0;
+        let bounds_buffer = bounds_buffer.read_value();
+        compute_bounds::bind_groups::BindGroup1::from_bindings(
+            &wgpu_context().device,
+            compute_bounds::bind_groups::BindGroupLayout1 {
+                bounds: bounds_buffer.as_entire_buffer_binding(),
+                parameters: parameters_buffer.read_value().as_entire_buffer_binding(),
+                user_texture: &texture.view,
+                user_sampler: &texture.sampler,
+                albedo_texture: &material_textures.albedo.view,
+                roughness_texture: &material_textures.roughness.view,
+                material_sampler: &material_sampler,
+            },
+        )
+    });
+
+    let dirty = StoredValue::new(true);
+    Effect::new({
+        let model = model.clone();
+        move |_| {
+            shader.track();
+            texture.track();
+            model.track();
+            dirty.set_value(true);
+        }
+    });
+
+    // The ID of the model whose results in the bounds buffer have not been read back yet
+    let evaluated = StoredValue::new(None::<String>);
+    let downloading = Arc::new(AtomicBool::new(false));
+
+    move |commands: &mut wgpu_profiler::Scope<'_, wgpu::CommandEncoder>| {
+        let context = wgpu_context();
+        if downloading.load(Ordering::Acquire) {
+            return;
+        }
+        if let Some(id) = evaluated.write_value().take() {
+            // The evaluation from the last frame has been submitted by now
+            downloading.store(true, Ordering::Release);
+            let downloading = downloading.clone();
+            let model_bounds = model_bounds.clone();
+            bounds_buffer.with_value(|bounds_buffer| {
+                wgpu::util::DownloadBuffer::read_buffer(
+                    &context.device,
+                    &context.queue,
+                    &bounds_buffer.slice(..),
+                    move |result| {
+                        if let Ok(data) = result {
+                            let mut model_bounds = model_bounds.0.lock().unwrap();
+                            match decode_bounds(&data) {
+                                Some(bounds) => model_bounds.insert(id, bounds),
+                                None => model_bounds.remove(&id),
+                            };
+                        }
+                        downloading.store(false, Ordering::Release);
+                    },
+                );
+            });
+            return;
+        }
+        if !dirty.get_value() {
+            return;
+        }
+        dirty.set_value(false);
+
+        bounds_buffer
+            .read_value()
+            .write_buffer(&context.queue, &empty_bounds);
+        let (id, instance_count) = model.with(|v| (v.id.clone(), v.instance_count));
+        let mut compute_pass = commands.scoped_compute_pass("Compute Bounds", &context.device);
+        compute_pass.set_pipeline(&shader.read().compute_bounds);
+        compute_bounds::set_bind_groups(
+            &mut compute_pass.recorder,
+            &compute_patches.read_value().bounds_bind_group_0,
+            &bind_group_1.read(),
+        );
+        // A 32x32 grid per instance
+        compute_pass.dispatch_workgroups(instance_count, 4, 4);
+        evaluated.set_value(Some(id));
+    }
+}
+
+/// The shader stores the floats as ordered integers. Returns `None` when nothing was evaluated.
+fn decode_bounds(data: &[u8]) -> Option<Aabb> {
+    let values = data
+        .chunks_exact(4)
+        .map(|bytes| {
+            let value = u32::from_le_bytes(bytes.try_into().unwrap());
+            let bits = if value & 0x8000_0000 != 0 {
+                value & 0x7fff_ffff
+            } else {
+                !value
+            };
+            f32::from_bits(bits)
+        })
+        .collect::<Vec<_>>();
+    let min = glam::Vec3::from_slice(values.get(0..3)?);
+    let max = glam::Vec3::from_slice(values.get(3..6)?);
+    // Also false for the NaNs of an empty buffer
+    min.cmple(max).all().then_some(Aabb { min, max })
+}
+
 struct RenderInfo {
     render_bind_group_0: StoredValue<shader::bind_groups::BindGroup0>,
     meshes: StoredValue<Vec<Mesh>>,
+    render_targets: Memo<RenderTargets>,
 }
 
 /// Renders a single model
 /// A model can change even when its ID stays the same. But the number of allocated buffers stays the same.
 fn render_model_component(
     render_bind_group_0: StoredValue<shader::bind_groups::BindGroup0>,
-    shaders: RwSignal<HashMap<ShaderId, Arc<ShaderPipelines>>>,
+    render_pipeline: Memo<Arc<wgpu::RenderPipeline>>,
     model: ArcReadSignal<ModelInfo>,
     virtual_model: Memo<VirtualModel>,
+    parameters_buffer: StoredValue<wgpu::Buffer>,
+    texture: Memo<Arc<Texture>>,
+    material_textures: Memo<MaterialTextures>,
     meshes: StoredValue<Vec<Mesh>>,
 ) -> impl Fn(&mut wgpu_profiler::OwningScope<'_, wgpu::RenderPass<'_>>) {
-    let shader = Memo::new({
-        let model = model.clone();
-        move |_| {
-            let model = model.read();
-            let shaders_guard = shaders.read();
-            let shader = shaders_guard.get(&model.shader_id).cloned();
-            shader.unwrap_or_else(|| use_context::<MissingShader>().unwrap().0.clone())
-        }
-    });
-
     let context = wgpu_context();
     let device = &context.device;
 
-    let model_buffer = TypedBuffer::new_uniform(
+    let model_buffer = StoredValue::new(TypedBuffer::new_uniform(
         device,
         "Model Buffer",
         &shader::Model {
             model_similarity: glam::Mat4::IDENTITY,
         },
         wgpu::BufferUsages::COPY_DST,
-    );
+    ));
 
-    let material_buffer = TypedBuffer::new_uniform(
+    let material_buffer = StoredValue::new(TypedBuffer::new_uniform(
         device,
         "Material Buffer",
         &MaterialInfo::missing().to_shader(),
         wgpu::BufferUsages::COPY_DST,
-    );
+    ));
 
-    let bind_group_1 = virtual_model.with(|virtual_model| {
+    let bind_group_1 = Memo::new_computed(move |_| {
+        let context = wgpu_context();
+        let texture = texture.read();
+        let material_textures = material_textures.read();
+        let material_sampler = expect_context::<MaterialSampler>().0;
         virtual_model
+            .read()
             .render_buffer
             .iter()
             .map(|render| {
                 shader::bind_groups::BindGroup1::from_bindings(
-                    device,
+                    &context.device,
                     shader::bind_groups::BindGroupLayout1 {
-                        model: model_buffer.as_entire_buffer_binding(),
+                        model: model_buffer.read_value().as_entire_buffer_binding(),
                         render_buffer: render.as_entire_buffer_binding(),
-                        material: material_buffer.as_entire_buffer_binding(),
+                        material: material_buffer.read_value().as_entire_buffer_binding(),
+                        parameters: parameters_buffer.read_value().as_entire_buffer_binding(),
+                        user_texture: &texture.view,
+                        user_sampler: &texture.sampler,
+                        albedo_texture: &material_textures.albedo.view,
+                        roughness_texture: &material_textures.roughness.view,
+                        normal_texture: &material_textures.normal.view,
+                        material_sampler: &material_sampler,
                     },
                 )
             })
@@ -928,21 +1945,25 @@ fn render_model_component(
     Effect::new(move |_| {
         let model = model.read();
         let queue = &wgpu_context().queue;
-        model_buffer.write_buffer(
+        model_buffer.read_value().write_buffer(
             queue,
             &shader::Model {
                 model_similarity: model.transform.to_matrix(),
             },
         );
-        material_buffer.write_buffer(queue, &model.material_info.to_shader());
+        material_buffer
+            .read_value()
+            .write_buffer(queue, &model.material_info.to_shader());
     });
 
     move |render_pass: &mut wgpu_profiler::OwningScope<'_, wgpu::RenderPass<'_>>| {
         let virtual_model = virtual_model.read();
-        render_pass.set_pipeline(&shader.read().render);
+        render_pass.set_pipeline(&render_pipeline.read());
 
         meshes.with_value(|meshes| {
-            for (i, (bind_group_1, mesh)) in bind_group_1.iter().zip(meshes.iter()).enumerate() {
+            for (i, (bind_group_1, mesh)) in
+                bind_group_1.read().iter().zip(meshes.iter()).enumerate()
+            {
                 let buffer_offset = (i as u64)
                     * Vec::<copy_patches::DrawIndexedIndirectArgs>::METADATA
                         .extra
@@ -962,6 +1983,18 @@ fn render_model_component(
     }
 }
 
+/// Falls back to the missing texture when the texture is not set, or has not been uploaded yet.
+fn get_texture(
+    textures: &HashMap<TextureId, Arc<Texture>>,
+    texture_id: &Option<TextureId>,
+    missing: &Arc<Texture>,
+) -> Arc<Texture> {
+    texture_id
+        .as_ref()
+        .and_then(|texture_id| textures.get(texture_id).cloned())
+        .unwrap_or_else(|| missing.clone())
+}
+
 fn update_models(models: SignalVec<ModelInfo>, game_models: &Vec<ModelInfo>) {
     for (model, game_model) in models.iter_mut().zip(game_models.iter()) {
         if model.with(|model| model.eq(game_model)) {
@@ -1042,3 +2075,44 @@ impl GpuApplication {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Same as `float_to_ordered` in ComputeBounds.wgsl
+    fn encode(value: f32) -> [u8; 4] {
+        let bits = value.to_bits();
+        let ordered = if bits & 0x8000_0000 != 0 {
+            !bits
+        } else {
+            bits | 0x8000_0000
+        };
+        ordered.to_le_bytes()
+    }
+
+    #[test]
+    fn decode_evaluated_bounds() {
+        let data = [-1.5, 0.0, -0.25, 2.0, 3.5, -0.0]
+            .into_iter()
+            .flat_map(encode)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            decode_bounds(&data),
+            Some(Aabb {
+                min: Vec3::new(-1.5, 0.0, -0.25),
+                max: Vec3::new(2.0, 3.5, 0.0),
+            })
+        );
+    }
+
+    #[test]
+    fn decode_empty_bounds() {
+        let data = [u32::MAX, u32::MAX, u32::MAX, 0, 0, 0]
+            .into_iter()
+            .flat_map(u32::to_le_bytes)
+            .collect::<Vec<_>>();
+        assert_eq!(decode_bounds(&data), None);
+        assert_eq!(decode_bounds(&data[..8]), None);
+    }
+}
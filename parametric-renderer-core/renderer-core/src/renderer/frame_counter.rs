@@ -1,41 +1,69 @@
 use web_time::Instant;
 
-#[derive(Debug, Default, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
 pub struct Seconds(pub f32);
 
+#[derive(Debug, Default, Clone, Copy)]
 pub struct FrameTime {
     pub frame: u64,
     pub delta: Seconds,
     pub elapsed: Seconds,
 }
 
+/// Where the time of each frame comes from.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum TimeSource {
+    /// The wall clock
+    #[default]
+    RealTime,
+    /// Every frame is exactly `delta` after the previous one, no matter how long it took.
+    /// Offline rendering uses this to get the same frames every time.
+    Fixed { start: Seconds, delta: Seconds },
+}
+
 pub struct FrameCounter {
     pub frame: u64,
     pub first_render_instant: Option<Instant>,
     pub render_instant: Option<Instant>,
+    pub time_source: TimeSource,
 }
 impl FrameCounter {
     pub fn new() -> Self {
+        Self::with_time_source(TimeSource::RealTime)
+    }
+
+    pub fn with_time_source(time_source: TimeSource) -> Self {
         Self {
             frame: 0,
             first_render_instant: None,
             render_instant: None,
+            time_source,
         }
     }
 
     pub fn new_frame(&mut self) -> FrameTime {
         let frame = self.frame;
-        let now = Instant::now();
-        let first_render_instant = *self.first_render_instant.get_or_insert(now);
-        let previous_render_instant = *self.render_instant.get_or_insert(now);
-        let delta = Seconds((now - previous_render_instant).as_secs_f32());
-        let elapsed = Seconds((now - first_render_instant).as_secs_f32());
-        self.render_instant = Some(now);
         self.frame += 1;
-        FrameTime {
-            frame,
-            delta,
-            elapsed,
+        match self.time_source {
+            TimeSource::RealTime => {
+                let now = Instant::now();
+                let first_render_instant = *self.first_render_instant.get_or_insert(now);
+                let previous_render_instant = *self.render_instant.get_or_insert(now);
+                let delta = Seconds((now - previous_render_instant).as_secs_f32());
+                let elapsed = Seconds((now - first_render_instant).as_secs_f32());
+                self.render_instant = Some(now);
+                FrameTime {
+                    frame,
+                    delta,
+                    elapsed,
+                }
+            }
+            TimeSource::Fixed { start, delta } => FrameTime {
+                frame,
+                // Like with the wall clock, the first frame has no delta
+                delta: if frame == 0 { Seconds(0.0) } else { delta },
+                elapsed: Seconds(start.0 + (frame as f32) * delta.0),
+            },
         }
     }
 }
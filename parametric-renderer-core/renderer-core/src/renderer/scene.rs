@@ -1,4 +1,4 @@
-use crate::{buffer::TypedBuffer, camera::Camera, shaders::shader};
+use crate::{buffer::TypedBuffer, camera::Camera, game::LightingSettings, shaders::shader};
 use glam::{Mat4, UVec2, Vec2, Vec4};
 
 use super::{frame_counter::FrameTime, FrameData};
@@ -52,17 +52,11 @@ impl SceneData {
                 },
                 wgpu::BufferUsages::COPY_DST,
             ),
-            light_buffer: TypedBuffer::new_storage(
+            light_buffer: TypedBuffer::new_storage_with_runtime_array(
                 device,
                 "Light Buffer",
-                &shader::Lights {
-                    ambient: Vec4::new(0.1, 0.1, 0.1, 0.0),
-                    points_length: 1,
-                    points: vec![shader::PointLight {
-                        position_range: Vec4::new(0.0, 4.0, 2.0, 40.0),
-                        color_intensity: Vec4::new(1.0, 1.0, 1.0, 3.0),
-                    }],
-                },
+                &LightingSettings::default().to_shader(),
+                LightingSettings::MAX_POINT_LIGHTS as u64,
                 wgpu::BufferUsages::COPY_DST,
             ),
         }
@@ -112,6 +106,8 @@ impl SceneData {
         );
         self.camera_buffer
             .write_buffer(queue, &render_data.camera.to_shader(size));
+        self.light_buffer
+            .write_buffer(queue, &render_data.lighting.to_shader());
     }
 }
 
@@ -124,3 +120,22 @@ impl Camera {
         }
     }
 }
+
+impl LightingSettings {
+    fn to_shader(&self) -> shader::Lights {
+        let points: Vec<_> = self
+            .points
+            .iter()
+            .take(LightingSettings::MAX_POINT_LIGHTS)
+            .map(|light| shader::PointLight {
+                position_range: light.position.extend(light.range),
+                color_intensity: light.color.extend(light.intensity),
+            })
+            .collect();
+        shader::Lights {
+            ambient: self.ambient.extend(0.0),
+            points_length: points.len() as u32,
+            points,
+        }
+    }
+}
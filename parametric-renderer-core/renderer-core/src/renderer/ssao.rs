@@ -0,0 +1,343 @@
+// Screen space ambient occlusion. It only needs the depth buffer, so it also works for user shaders.
+
+use reactive_graph::{computed::Memo, owner::StoredValue, prelude::*, signal::RwSignal};
+
+use crate::{
+    buffer::TypedBuffer,
+    game::AmbientOcclusion,
+    reactive::MemoComputed,
+    shaders::{depth_resolve, ssao, ssao_apply},
+    texture::Texture,
+};
+
+use super::{wgpu_context, wgpu_context::SurfaceOrFallback, FrameData};
+
+pub(super) struct AmbientOcclusionComponent<Render> {
+    /// The ambient light of the scene, when ambient occlusion is enabled
+    pub(super) ambient_texture: Memo<Option<Texture>>,
+    /// Gets resolved into the ambient texture
+    pub(super) msaa_ambient_texture: Memo<Option<Texture>>,
+    pub(super) render: Render,
+}
+
+/// Screen space ambient occlusion, which removes the occluded ambient light from the HDR texture
+pub(super) fn ambient_occlusion_component(
+    surface: RwSignal<SurfaceOrFallback>,
+    depth_texture: Memo<Texture>,
+    hdr_texture: Memo<Texture>,
+    sample_count: Memo<u32>,
+    ambient_occlusion: RwSignal<AmbientOcclusion>,
+) -> AmbientOcclusionComponent<
+    impl Fn(&FrameData, &mut wgpu_profiler::Scope<'_, wgpu::CommandEncoder>),
+> {
+    const RADIUS: f32 = 0.5;
+    let context = wgpu_context();
+    let enabled = Memo::new(move |_| ambient_occlusion.get() != AmbientOcclusion::Off);
+    let multisampled = Memo::new(move |_| enabled.get() && sample_count.get() > 1);
+
+    let ambient_texture = Memo::new_computed(move |_| {
+        enabled.get().then(|| {
+            Texture::create_render_target(
+                &wgpu_context().device,
+                surface.read().size(),
+                Texture::HDR_FORMAT,
+                1,
+                "Ambient Texture",
+            )
+        })
+    });
+    let msaa_ambient_texture = Memo::new_computed(move |_| {
+        multisampled.get().then(|| {
+            Texture::create_render_target(
+                &wgpu_context().device,
+                surface.read().size(),
+                Texture::HDR_FORMAT,
+                sample_count.get(),
+                "MSAA Ambient Texture",
+            )
+        })
+    });
+    // Multisampled depth textures cannot be resolved, so we copy one sample instead
+    let resolved_depth_texture = Memo::new_computed(move |_| {
+        multisampled.get().then(|| {
+            Texture::create_depth_texture(
+                &wgpu_context().device,
+                surface.read().size(),
+                1,
+                "Resolved Depth Texture",
+            )
+        })
+    });
+    let ambient_occlusion_texture = Memo::new_computed(move |_| {
+        enabled.get().then(|| {
+            Texture::create_render_target(
+                &wgpu_context().device,
+                surface.read().size(),
+                wgpu::TextureFormat::R8Unorm,
+                1,
+                "Ambient Occlusion Texture",
+            )
+        })
+    });
+
+    let depth_resolve_shader = depth_resolve::create_shader_module(&context.device);
+    let depth_resolve_pipeline =
+        context
+            .device
+            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Depth Resolve"),
+                layout: Some(&depth_resolve::create_pipeline_layout(&context.device)),
+                vertex: wgpu::VertexState {
+                    module: &depth_resolve_shader,
+                    entry_point: Some(depth_resolve::ENTRY_VS_MAIN),
+                    compilation_options: Default::default(),
+                    buffers: &[],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &depth_resolve_shader,
+                    entry_point: Some(depth_resolve::ENTRY_FS_MAIN),
+                    targets: &[],
+                    compilation_options: Default::default(),
+                }),
+                primitive: Default::default(),
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: Texture::DEPTH_FORMAT,
+                    depth_write_enabled: true,
+                    depth_compare: wgpu::CompareFunction::Always,
+                    stencil: Default::default(),
+                    bias: Default::default(),
+                }),
+                multisample: Default::default(),
+                multiview: None,
+                cache: Default::default(),
+            });
+
+    let ssao_shader = ssao::create_shader_module(&context.device);
+    let ssao_pipeline = context
+        .device
+        .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Ambient Occlusion"),
+            layout: Some(&ssao::create_pipeline_layout(&context.device)),
+            vertex: wgpu::VertexState {
+                module: &ssao_shader,
+                entry_point: Some(ssao::ENTRY_VS_MAIN),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &ssao_shader,
+                entry_point: Some(ssao::ENTRY_FS_MAIN),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: wgpu::TextureFormat::R8Unorm,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: Default::default(),
+            depth_stencil: None,
+            multisample: Default::default(),
+            multiview: None,
+            cache: Default::default(),
+        });
+
+    let apply_shader = ssao_apply::create_shader_module(&context.device);
+    let apply_pipeline = context
+        .device
+        .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Apply Ambient Occlusion"),
+            layout: Some(&ssao_apply::create_pipeline_layout(&context.device)),
+            vertex: wgpu::VertexState {
+                module: &apply_shader,
+                entry_point: Some(ssao_apply::ENTRY_VS_MAIN),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &apply_shader,
+                entry_point: Some(ssao_apply::ENTRY_FS_MAIN),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: Texture::HDR_FORMAT,
+                    // Subtracts the occluded ambient light
+                    blend: Some(wgpu::BlendState {
+                        color: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::One,
+                            dst_factor: wgpu::BlendFactor::One,
+                            operation: wgpu::BlendOperation::ReverseSubtract,
+                        },
+                        alpha: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::Zero,
+                            dst_factor: wgpu::BlendFactor::One,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: Default::default(),
+            depth_stencil: None,
+            multisample: Default::default(),
+            multiview: None,
+            cache: Default::default(),
+        });
+
+    let uniforms = StoredValue::new(TypedBuffer::new_uniform(
+        &context.device,
+        "Ambient Occlusion Uniforms",
+        &ssao::Ssao {
+            projection: glam::Mat4::IDENTITY,
+            inv_projection: glam::Mat4::IDENTITY,
+            radius: RADIUS,
+            sample_count: 0,
+        },
+        wgpu::BufferUsages::COPY_DST,
+    ));
+
+    let depth_resolve_bind_group_0 = Memo::new_computed(move |_| {
+        multisampled.get().then(|| {
+            depth_resolve::bind_groups::BindGroup0::from_bindings(
+                &wgpu_context().device,
+                depth_resolve::bind_groups::BindGroupLayout0 {
+                    depth_texture: &depth_texture.read().view,
+                },
+            )
+        })
+    });
+    let ssao_bind_group_0 = Memo::new_computed(move |_| {
+        if !enabled.get() {
+            return None;
+        }
+        let depth_texture = depth_texture.read();
+        let resolved_depth_texture = resolved_depth_texture.read();
+        let depth_texture = resolved_depth_texture.as_ref().unwrap_or(&*depth_texture);
+        Some(ssao::bind_groups::BindGroup0::from_bindings(
+            &wgpu_context().device,
+            ssao::bind_groups::BindGroupLayout0 {
+                depth_texture: &depth_texture.view,
+                ssao: uniforms.read_value().as_entire_buffer_binding(),
+            },
+        ))
+    });
+    let apply_bind_group_0 = Memo::new_computed(move |_| {
+        let ambient_occlusion_texture = ambient_occlusion_texture.read();
+        let ambient_texture = ambient_texture.read();
+        match (ambient_occlusion_texture.as_ref(), ambient_texture.as_ref()) {
+            (Some(ambient_occlusion_texture), Some(ambient_texture)) => {
+                Some(ssao_apply::bind_groups::BindGroup0::from_bindings(
+                    &wgpu_context().device,
+                    ssao_apply::bind_groups::BindGroupLayout0 {
+                        ambient_occlusion_texture: &ambient_occlusion_texture.view,
+                        ambient_texture: &ambient_texture.view,
+                    },
+                ))
+            }
+            _ => None,
+        }
+    });
+
+    let render =
+        move |render_data: &FrameData,
+              commands: &mut wgpu_profiler::Scope<'_, wgpu::CommandEncoder>| {
+            let sample_count = match ambient_occlusion.get() {
+                AmbientOcclusion::Off => return,
+                AmbientOcclusion::Low => 8,
+                AmbientOcclusion::High => 32,
+            };
+            let context = wgpu_context();
+            let projection = render_data.camera.projection_matrix(surface.read().size());
+            uniforms.read_value().write_buffer(
+                &context.queue,
+                &ssao::Ssao {
+                    projection,
+                    inv_projection: projection.inverse(),
+                    radius: RADIUS,
+                    sample_count,
+                },
+            );
+
+            if let (Some(bind_group_0), Some(resolved_depth_texture)) = (
+                depth_resolve_bind_group_0.read().as_ref(),
+                resolved_depth_texture.read().as_ref(),
+            ) {
+                let mut render_pass = commands.scoped_render_pass(
+                    "Depth Resolve Pass",
+                    &context.device,
+                    wgpu::RenderPassDescriptor {
+                        label: Some("Depth Resolve Pass"),
+                        color_attachments: &[],
+                        depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                            view: &resolved_depth_texture.view,
+                            depth_ops: Some(wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(0.0),
+                                store: wgpu::StoreOp::Store,
+                            }),
+                            stencil_ops: None,
+                        }),
+                        timestamp_writes: None,
+                        occlusion_query_set: None,
+                    },
+                );
+                render_pass.set_pipeline(&depth_resolve_pipeline);
+                bind_group_0.set(&mut render_pass.recorder);
+                render_pass.draw(0..3, 0..1);
+            }
+
+            if let (Some(bind_group_0), Some(ambient_occlusion_texture)) = (
+                ssao_bind_group_0.read().as_ref(),
+                ambient_occlusion_texture.read().as_ref(),
+            ) {
+                let mut render_pass = commands.scoped_render_pass(
+                    "Ambient Occlusion Pass",
+                    &context.device,
+                    wgpu::RenderPassDescriptor {
+                        label: Some("Ambient Occlusion Pass"),
+                        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                            view: &ambient_occlusion_texture.view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(wgpu::Color::WHITE),
+                                store: wgpu::StoreOp::Store,
+                            },
+                        })],
+                        depth_stencil_attachment: None,
+                        timestamp_writes: None,
+                        occlusion_query_set: None,
+                    },
+                );
+                render_pass.set_pipeline(&ssao_pipeline);
+                bind_group_0.set(&mut render_pass.recorder);
+                render_pass.draw(0..3, 0..1);
+            }
+
+            if let Some(bind_group_0) = apply_bind_group_0.read().as_ref() {
+                let mut render_pass = commands.scoped_render_pass(
+                    "Apply Ambient Occlusion Pass",
+                    &context.device,
+                    wgpu::RenderPassDescriptor {
+                        label: Some("Apply Ambient Occlusion Pass"),
+                        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                            view: &hdr_texture.read().view,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Load,
+                                store: wgpu::StoreOp::Store,
+                            },
+                        })],
+                        depth_stencil_attachment: None,
+                        timestamp_writes: None,
+                        occlusion_query_set: None,
+                    },
+                );
+                render_pass.set_pipeline(&apply_pipeline);
+                bind_group_0.set(&mut render_pass.recorder);
+                render_pass.draw(0..3, 0..1);
+            }
+        };
+
+    AmbientOcclusionComponent {
+        ambient_texture,
+        msaa_ambient_texture,
+        render,
+    }
+}
@@ -4,12 +4,16 @@ use crate::{
     buffer::TypedBuffer,
     game::MaterialInfo,
     mesh::Mesh,
-    shaders::{compute_patches, copy_patches, shader},
+    shader_parameters::{declares_fn, declares_struct, ReflectParametersError, ShaderParameters},
+    shaders::{compute_bounds, compute_patches, copy_patches, shader},
     texture::Texture,
 };
 
-use super::{wgpu_context::WgpuContext, MAX_PATCH_COUNT, PATCH_SIZES};
-use std::sync::Arc;
+use super::{wgpu_context::WgpuContext, RenderTargets, MAX_PATCH_COUNT, PATCH_SIZES};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
 
 use glam::{Vec3, Vec4};
 use uuid::Uuid;
@@ -18,9 +22,16 @@ use wgpu::ShaderModule;
 pub struct ShaderPipelines {
     /// Pipeline per model, for different parametric functions.
     pub compute_patches: wgpu::ComputePipeline,
-    /// Pipeline per model, for different parametric functions.
-    pub render: wgpu::RenderPipeline,
-    pub shaders: [ShaderModule; 2],
+    /// Evaluates the parametric function on a grid to get its bounds.
+    pub compute_bounds: wgpu::ComputePipeline,
+    /// Compute patches, render and compute bounds shaders
+    pub shaders: [ShaderModule; 3],
+    /// Shared by all models with this shader. Transparent pipelines only get created when a model needs them.
+    render_pipelines: Mutex<HashMap<(RenderTargets, bool), Arc<wgpu::RenderPipeline>>>,
+    label: String,
+    /// Layout of the `parameters` uniform that the user code declared.
+    pub parameters: ShaderParameters,
+    parameters_error: Option<String>,
     pub id: Uuid,
 }
 
@@ -36,19 +47,71 @@ impl ShaderPipelines {
     pub fn new(label: &str, code: &str, context: &WgpuContext) -> Self {
         let (compute_patches, shader_a) =
             create_compute_patches_pipeline(label, &context.device, code);
-        let (render, shader_b) = create_render_pipeline(label, context, code);
+        let shader_b = create_render_shader(label, context, code);
+        let (compute_bounds, shader_c) =
+            create_compute_bounds_pipeline(label, &context.device, code);
+        let (parameters, parameters_error) = match ShaderParameters::reflect_user_code(
+            code,
+            &replace_evaluate_image_code(shader::SOURCE, code),
+        ) {
+            Ok(parameters) => (parameters, None),
+            // The shader compiler already reports syntax errors
+            Err(ReflectParametersError::Parse(_)) => (ShaderParameters::default(), None),
+            Err(err) => (ShaderParameters::default(), Some(err.to_string())),
+        };
 
         Self {
             compute_patches,
-            render,
-            shaders: [shader_a, shader_b],
+            compute_bounds,
+            shaders: [shader_a, shader_b, shader_c],
+            render_pipelines: Mutex::new(HashMap::new()),
+            label: label.to_string(),
+            parameters,
+            parameters_error,
             id: Uuid::new_v4(),
         }
     }
 
+    /// The render pipelines depend on the render targets, which can change at runtime.
+    /// Transparent models blend instead of writing to the depth buffer.
+    pub fn render_pipeline(
+        &self,
+        context: &WgpuContext,
+        targets: RenderTargets,
+        transparent: bool,
+    ) -> Arc<wgpu::RenderPipeline> {
+        self.render_pipelines
+            .lock()
+            .unwrap()
+            .entry((targets, transparent))
+            .or_insert_with(|| {
+                Arc::new(create_render_pipeline(
+                    &self.label,
+                    context,
+                    &self.shaders[1],
+                    targets,
+                    transparent,
+                ))
+            })
+            .clone()
+    }
+
     pub async fn get_compilation_info(&self) -> Vec<wgpu::CompilationMessage> {
         let mut messages = self.shaders[0].get_compilation_info().await.messages;
         messages.extend(self.shaders[1].get_compilation_info().await.messages);
+        // Mostly the same user code errors as the compute patches shader
+        for message in self.shaders[2].get_compilation_info().await.messages {
+            if !messages.iter().any(|v| v.message == message.message) {
+                messages.push(message);
+            }
+        }
+        if let Some(message) = &self.parameters_error {
+            messages.push(wgpu::CompilationMessage {
+                message: message.clone(),
+                message_type: wgpu::CompilationMessageType::Error,
+                location: None,
+            });
+        }
         messages
     }
 }
@@ -126,6 +189,7 @@ impl MaterialInfo {
                 self.emissive.z,
                 self.metallic,
             ),
+            opacity: self.opacity,
         }
     }
 
@@ -135,6 +199,10 @@ impl MaterialInfo {
             emissive: Vec3::new(1.0, 0.0, 1.0),
             roughness: 0.7,
             metallic: 0.0,
+            opacity: 1.0,
+            albedo_texture: None,
+            roughness_texture: None,
+            normal_texture: None,
         }
     }
 }
@@ -145,81 +213,127 @@ impl Default for MaterialInfo {
             emissive: Vec3::new(0.0, 0.0, 0.0),
             roughness: 0.0,
             metallic: 0.0,
+            opacity: 1.0,
+            albedo_texture: None,
+            roughness_texture: None,
+            normal_texture: None,
         }
     }
 }
 
+fn create_render_shader(label: &str, context: &WgpuContext, code: &str) -> ShaderModule {
+    context
+        .device
+        .create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(&format!("Render Shader {}", label)),
+            source: wgpu::ShaderSource::Wgsl(
+                replace_evaluate_image_code(shader::SOURCE, code).into(),
+            ),
+        })
+}
+
 fn create_render_pipeline(
     label: &str,
     context: &WgpuContext,
-    code: &str,
-) -> (wgpu::RenderPipeline, ShaderModule) {
+    shader: &ShaderModule,
+    targets: RenderTargets,
+    transparent: bool,
+) -> wgpu::RenderPipeline {
     let device = &context.device;
+    let blend = if transparent {
+        wgpu::BlendState::ALPHA_BLENDING
+    } else {
+        wgpu::BlendState::REPLACE
+    };
+    // The color, and optionally the ambient light for the ambient occlusion
+    let color_targets = std::iter::once(Texture::HDR_FORMAT)
+        .chain(targets.ambient.then_some(Texture::HDR_FORMAT))
+        .map(|format| {
+            Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(blend),
+                write_mask: wgpu::ColorWrites::ALL,
+            })
+        })
+        .collect::<Vec<_>>();
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(&format!("Render Pipeline {}", label)),
+        layout: Some(&shader::create_pipeline_layout(device)),
+        vertex: shader::vertex_state(shader, &shader::vs_main_entry(wgpu::VertexStepMode::Vertex)),
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some(shader::ENTRY_FS_MAIN),
+            targets: &color_targets,
+            compilation_options: Default::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: None,
+            // Setting this to anything other than Fill requires Features::NON_FILL_POLYGON_MODE
+            polygon_mode: wgpu::PolygonMode::Fill, // Wireframe mode can be toggled here on the desktop backend
+            // Requires Features::DEPTH_CLIP_CONTROL
+            unclipped_depth: false,
+            // Requires Features::CONSERVATIVE_RASTERIZATION
+            conservative: false,
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: Texture::DEPTH_FORMAT,
+            // Transparent models are sorted instead
+            depth_write_enabled: !transparent,
+            depth_compare: wgpu::CompareFunction::Greater,
+            stencil: Default::default(),
+            bias: Default::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: targets.sample_count,
+            ..Default::default()
+        },
+        multiview: None,
+        cache: Default::default(),
+    })
+}
+
+pub fn create_compute_patches_pipeline(
+    label: &str,
+    device: &wgpu::Device,
+    code: &str,
+) -> (wgpu::ComputePipeline, ShaderModule) {
+    let source = replace_evaluate_image_code(compute_patches::SOURCE, code);
     let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-        label: Some(&format!("Render Shader {}", label)),
-        source: wgpu::ShaderSource::Wgsl(replace_evaluate_image_code(shader::SOURCE, code).into()),
+        label: None,
+        source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(source.as_ref())),
     });
     (
-        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some(&format!("Render Pipeline {}", label)),
-            layout: Some(&shader::create_pipeline_layout(device)),
-            vertex: shader::vertex_state(
-                &shader,
-                &shader::vs_main_entry(wgpu::VertexStepMode::Vertex),
-            ),
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: Some(shader::ENTRY_FS_MAIN),
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: context.view_format,
-                    blend: Some(wgpu::BlendState::REPLACE),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-                compilation_options: Default::default(),
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: None,
-                // Setting this to anything other than Fill requires Features::NON_FILL_POLYGON_MODE
-                polygon_mode: wgpu::PolygonMode::Fill, // Wireframe mode can be toggled here on the desktop backend
-                // Requires Features::DEPTH_CLIP_CONTROL
-                unclipped_depth: false,
-                // Requires Features::CONSERVATIVE_RASTERIZATION
-                conservative: false,
-            },
-            depth_stencil: Some(wgpu::DepthStencilState {
-                format: Texture::DEPTH_FORMAT,
-                depth_write_enabled: true,
-                depth_compare: wgpu::CompareFunction::Greater,
-                stencil: Default::default(),
-                bias: Default::default(),
-            }),
-            multisample: Default::default(),
-            multiview: None,
+        device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some(&format!("Compute Patches {}", label)),
+            layout: Some(&compute_patches::create_pipeline_layout(device)),
+            module: &shader,
+            entry_point: Some(compute_patches::ENTRY_MAIN),
+            compilation_options: Default::default(),
             cache: Default::default(),
         }),
         shader,
     )
 }
 
-pub fn create_compute_patches_pipeline(
+fn create_compute_bounds_pipeline(
     label: &str,
     device: &wgpu::Device,
     code: &str,
 ) -> (wgpu::ComputePipeline, ShaderModule) {
-    let source = replace_evaluate_image_code(compute_patches::SOURCE, code);
+    let source = replace_evaluate_image_code(compute_bounds::SOURCE, code);
     let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
         label: None,
         source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(source.as_ref())),
     });
     (
         device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some(&format!("Compute Patches {}", label)),
-            layout: Some(&compute_patches::create_pipeline_layout(device)),
+            label: Some(&format!("Compute Bounds {}", label)),
+            layout: Some(&compute_bounds::create_pipeline_layout(device)),
             module: &shader,
-            entry_point: Some(compute_patches::ENTRY_MAIN),
+            entry_point: Some(compute_bounds::ENTRY_MAIN),
             compilation_options: Default::default(),
             cache: Default::default(),
         }),
@@ -227,16 +341,27 @@ pub fn create_compute_patches_pipeline(
     )
 }
 
-fn replace_evaluate_image_code<'a>(source: &'a str, sample_object_code: &str) -> String {
+fn replace_evaluate_image_code(source: &str, sample_object_code: &str) -> String {
+    // The template has a fallback for everything that the user code can optionally declare
+    let mut source = source.to_string();
+    if declares_struct(sample_object_code, "Parameters") {
+        source = replace_region(&source, "Parameters", "");
+    }
+    if declares_fn(sample_object_code, "sampleMaterial") {
+        source = replace_region(&source, "sampleMaterial", "");
+    }
+    replace_region(&source, "sampleObject", sample_object_code)
+}
+
+fn replace_region(source: &str, name: &str, code: &str) -> String {
     // TODO: use wgsl-parser instead of this
-    let start = source.find("//// START sampleObject").unwrap();
-    let end = source.find("//// END sampleObject").unwrap();
+    let start = source.find(&format!("//// START {name}")).unwrap();
+    let end = source.find(&format!("//// END {name}")).unwrap();
 
-    let mut result = String::with_capacity(
-        source[..start].len() + sample_object_code.len() + source[end..].len(),
-    );
+    let mut result =
+        String::with_capacity(source[..start].len() + code.len() + source[end..].len());
     result.push_str(&source[..start]);
-    result.push_str(sample_object_code);
+    result.push_str(code);
     result.push_str(&source[end..]);
     result
 }
@@ -6,6 +6,31 @@ use wgpu_profiler::{GpuProfiler, GpuProfilerSettings};
 use winit::window::Window;
 
 use super::WindowOrFallback;
+use crate::texture::Texture;
+
+/// How to pick the GPU and present to the window. The defaults work on most machines.
+#[derive(Debug, Clone)]
+pub struct GpuSettings {
+    pub backends: wgpu::Backends,
+    pub power_preference: wgpu::PowerPreference,
+    /// Uses the first adapter whose name contains this, like `llvmpipe` or `NVIDIA`, instead of the power preference
+    pub adapter_name: Option<String>,
+    /// Only uses a software adapter
+    pub force_fallback_adapter: bool,
+    pub present_mode: wgpu::PresentMode,
+}
+
+impl Default for GpuSettings {
+    fn default() -> Self {
+        Self {
+            backends: wgpu::Backends::PRIMARY,
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            adapter_name: None,
+            force_fallback_adapter: false,
+            present_mode: wgpu::PresentMode::AutoVsync,
+        }
+    }
+}
 
 pub struct WgpuContext {
     pub instance: wgpu::Instance,
@@ -16,10 +41,13 @@ pub struct WgpuContext {
 }
 
 impl WgpuContext {
-    pub async fn new(window: WindowOrFallback) -> anyhow::Result<(Self, SurfaceOrFallback)> {
+    pub async fn new(
+        window: WindowOrFallback,
+        settings: &GpuSettings,
+    ) -> anyhow::Result<(Self, SurfaceOrFallback)> {
         let size = window.size().max(UVec2::ONE);
         let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-            backends: wgpu::Backends::PRIMARY,
+            backends: settings.backends,
             ..Default::default()
         });
 
@@ -28,15 +56,21 @@ impl WgpuContext {
             .map(|window| instance.create_surface(window))
             .transpose()?;
 
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                // Setting this is only needed for a fallback adapter. Which we don't want.
-                compatible_surface: None,
-                force_fallback_adapter: false,
-            })
-            .await
-            .ok_or_else(|| anyhow::anyhow!("No adapter found"))?;
+        let adapter = match &settings.adapter_name {
+            Some(name) => find_adapter(&instance, settings.backends, name, surface.as_ref())?,
+            None => instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: settings.power_preference,
+                    // Only needed to make sure that a fallback adapter can present to the window.
+                    // Setting it otherwise can make wgpu skip the high performance GPU.
+                    compatible_surface: surface
+                        .as_ref()
+                        .filter(|_| settings.force_fallback_adapter),
+                    force_fallback_adapter: settings.force_fallback_adapter,
+                })
+                .await
+                .ok_or_else(|| anyhow::anyhow!("No adapter found"))?,
+        };
         info!("Adapter: {:?}", adapter.get_info());
 
         let (device, queue) = adapter
@@ -44,7 +78,11 @@ impl WgpuContext {
                 &wgpu::DeviceDescriptor {
                     required_features: wgpu::Features::default()
                         | (adapter.features() & GpuProfiler::ALL_WGPU_TIMER_FEATURES)
-                        | (adapter.features() & wgpu::Features::POLYGON_MODE_LINE),
+                        | (adapter.features() & wgpu::Features::POLYGON_MODE_LINE)
+                        | (adapter.features() & wgpu::Features::FLOAT32_FILTERABLE)
+                        | (adapter.features() & wgpu::Features::TEXTURE_FORMAT_16BIT_NORM)
+                        | (adapter.features()
+                            & wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES),
                     required_limits: wgpu::Limits::default(),
                     label: None,
                     memory_hints: Default::default(),
@@ -80,7 +118,7 @@ impl WgpuContext {
                 let config = wgpu::SurfaceConfiguration {
                     format: surface_format,
                     view_formats: vec![view_format],
-                    present_mode: wgpu::PresentMode::AutoVsync,
+                    present_mode: settings.present_mode,
                     ..surface
                         .get_default_config(&adapter, size.x, size.y)
                         .ok_or_else(|| anyhow::anyhow!("No default surface config found"))?
@@ -113,6 +151,24 @@ impl WgpuContext {
         ))
     }
 
+    /// Whether the color and the depth targets support this number of MSAA samples.
+    pub fn supports_sample_count(&self, sample_count: u32) -> bool {
+        let adapter_specific = self
+            .device
+            .features()
+            .contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES);
+        [Texture::HDR_FORMAT, Texture::DEPTH_FORMAT]
+            .into_iter()
+            .all(|format| {
+                let format_features = if adapter_specific {
+                    self._adapter.get_texture_format_features(format)
+                } else {
+                    format.guaranteed_format_features(self.device.features())
+                };
+                format_features.flags.sample_count_supported(sample_count)
+            })
+    }
+
     fn create_view(&self, texture: &wgpu::Texture) -> wgpu::TextureView {
         texture.create_view(&wgpu::TextureViewDescriptor {
             format: Some(self.view_format),
@@ -121,6 +177,43 @@ impl WgpuContext {
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+fn find_adapter(
+    instance: &wgpu::Instance,
+    backends: wgpu::Backends,
+    name: &str,
+    surface: Option<&wgpu::Surface<'_>>,
+) -> anyhow::Result<wgpu::Adapter> {
+    let adapters = instance.enumerate_adapters(backends);
+    let names = adapters
+        .iter()
+        .map(|adapter| adapter.get_info().name)
+        .collect::<Vec<_>>();
+    adapters
+        .into_iter()
+        .find(|adapter| {
+            let info = adapter.get_info();
+            info.name.to_lowercase().contains(&name.to_lowercase())
+                && surface.map_or(true, |surface| adapter.is_surface_supported(surface))
+        })
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "No adapter named {name} that can present to the window, only {}",
+                names.join(", ")
+            )
+        })
+}
+
+#[cfg(target_arch = "wasm32")]
+fn find_adapter(
+    _instance: &wgpu::Instance,
+    _backends: wgpu::Backends,
+    _name: &str,
+    _surface: Option<&wgpu::Surface<'_>>,
+) -> anyhow::Result<wgpu::Adapter> {
+    anyhow::bail!("Browsers do not let us choose an adapter by name")
+}
+
 pub enum SurfaceTexture {
     Surface(wgpu::SurfaceTexture, wgpu::TextureView),
     Fallback(wgpu::TextureView),
@@ -240,11 +333,83 @@ fn create_fallback_texture(
         sample_count: 1,
         dimension: wgpu::TextureDimension::D2,
         format,
-        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        // Can be read back for offline rendering
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
         view_formats: &[],
     })
 }
 
+impl SurfaceOrFallback {
+    /// Copies the fallback texture into an image, and waits for the GPU to finish.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_fallback_texture(&self, context: &WgpuContext) -> anyhow::Result<image::RgbaImage> {
+        let SurfaceOrFallback::Fallback { texture, size } = self else {
+            anyhow::bail!("Only headless renderers can capture frames");
+        };
+        let is_bgra = match texture.format() {
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+            format => anyhow::bail!("Cannot capture frames with the format {format:?}"),
+        };
+        // Rows of a copy have to be aligned
+        let unpadded_bytes_per_row = size.x * 4;
+        let bytes_per_row =
+            unpadded_bytes_per_row.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let buffer = context.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Capture Buffer"),
+            size: (bytes_per_row * size.y) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mut command_encoder =
+            context
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Capture Encoder"),
+                });
+        command_encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(bytes_per_row),
+                    rows_per_image: None,
+                },
+            },
+            texture.size(),
+        );
+        context
+            .queue
+            .submit(std::iter::once(command_encoder.finish()));
+
+        let buffer_slice = buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        context.device.poll(wgpu::Maintain::Wait);
+        receiver.recv()??;
+
+        let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * size.y) as usize);
+        for row in buffer_slice
+            .get_mapped_range()
+            .chunks(bytes_per_row as usize)
+        {
+            for pixel in row[..unpadded_bytes_per_row as usize].chunks_exact(4) {
+                if is_bgra {
+                    pixels.extend_from_slice(&[pixel[2], pixel[1], pixel[0], pixel[3]]);
+                } else {
+                    pixels.extend_from_slice(pixel);
+                }
+            }
+        }
+        buffer.unmap();
+        image::RgbaImage::from_raw(size.x, size.y, pixels)
+            .ok_or_else(|| anyhow::anyhow!("Captured frame has the wrong size"))
+    }
+}
+
 pub fn create_profiler(_context: &WgpuContext) -> GpuProfiler {
     let gpu_profiler_settings = GpuProfilerSettings {
         enable_timer_queries: false, // Disabled by default
@@ -0,0 +1,296 @@
+use std::{collections::HashMap, fmt};
+
+use glam::Vec4;
+
+/// Every model gets a uniform buffer of this size for its parameters.
+pub const MAX_PARAMETERS_SIZE: u64 = 1024;
+
+/// The name of the uniform that user shaders can read their parameters from.
+const PARAMETERS_VARIABLE: &str = "parameters";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParameterKind {
+    F32,
+    I32,
+    U32,
+}
+
+/// A single member of the user defined `struct Parameters`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShaderParameter {
+    pub name: String,
+    pub kind: ParameterKind,
+    /// 1 for scalars, 2 to 4 for vectors
+    pub components: u32,
+    /// Byte offset in the uniform buffer
+    pub offset: u32,
+}
+
+/// The layout of the `parameters` uniform, as reflected by naga.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShaderParameters {
+    pub parameters: Vec<ShaderParameter>,
+    pub size: u32,
+}
+
+#[derive(Debug)]
+pub enum ReflectParametersError {
+    Parse(String),
+    TooLarge { size: u32 },
+}
+
+impl fmt::Display for ReflectParametersError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReflectParametersError::Parse(v) => write!(fmt, "Failed to parse shader: {v}"),
+            ReflectParametersError::TooLarge { size } => write!(
+                fmt,
+                "struct Parameters is {size} bytes, but at most {MAX_PARAMETERS_SIZE} bytes are supported"
+            ),
+        }
+    }
+}
+
+impl ShaderParameters {
+    /// Reflects the `parameters` uniform of a complete shader.
+    /// Members that are neither scalars nor vectors cannot be set from the host, and are skipped.
+    pub fn reflect(source: &str) -> Result<Self, ReflectParametersError> {
+        let module = naga::front::wgsl::parse_str(source)
+            .map_err(|e| ReflectParametersError::Parse(e.emit_to_string(source)))?;
+
+        let Some((_, variable)) = module
+            .global_variables
+            .iter()
+            .find(|(_, v)| v.name.as_deref() == Some(PARAMETERS_VARIABLE))
+        else {
+            return Ok(Self::default());
+        };
+
+        let (members, span) = match &module.types[variable.ty].inner {
+            naga::TypeInner::Struct { members, span } => (members, *span),
+            _ => return Ok(Self::default()),
+        };
+        if span as u64 > MAX_PARAMETERS_SIZE {
+            return Err(ReflectParametersError::TooLarge { size: span });
+        }
+
+        let parameters = members
+            .iter()
+            .filter_map(|member| {
+                let (scalar, components) = match module.types[member.ty].inner {
+                    naga::TypeInner::Scalar(scalar) => (scalar, 1),
+                    naga::TypeInner::Vector { size, scalar } => (scalar, size as u32),
+                    _ => return None,
+                };
+                let kind = match (scalar.kind, scalar.width) {
+                    (naga::ScalarKind::Float, 4) => ParameterKind::F32,
+                    (naga::ScalarKind::Sint, 4) => ParameterKind::I32,
+                    (naga::ScalarKind::Uint, 4) => ParameterKind::U32,
+                    _ => return None,
+                };
+                Some(ShaderParameter {
+                    name: member.name.clone()?,
+                    kind,
+                    components,
+                    offset: member.offset,
+                })
+            })
+            .collect();
+
+        Ok(Self {
+            parameters,
+            size: span,
+        })
+    }
+
+    /// Reflects the parameters of user code, after it was inserted into one of the shader templates.
+    /// The templates declare a placeholder `struct Parameters` when the user code has none, which has no parameters.
+    pub fn reflect_user_code(
+        user_code: &str,
+        source: &str,
+    ) -> Result<Self, ReflectParametersError> {
+        if !declares_struct(user_code, "Parameters") {
+            return Ok(Self::default());
+        }
+        Self::reflect(source)
+    }
+
+    /// Lays out the values like the shader expects them. Missing values are zero.
+    pub fn to_bytes(&self, values: &HashMap<String, Vec4>) -> Vec<u8> {
+        let mut bytes = vec![0u8; self.size as usize];
+        for parameter in &self.parameters {
+            let Some(value) = values.get(&parameter.name) else {
+                continue;
+            };
+            for (i, component) in value
+                .to_array()
+                .into_iter()
+                .take(parameter.components as usize)
+                .enumerate()
+            {
+                let component_bytes = match parameter.kind {
+                    ParameterKind::F32 => component.to_le_bytes(),
+                    ParameterKind::I32 => (component as i32).to_le_bytes(),
+                    ParameterKind::U32 => (component as u32).to_le_bytes(),
+                };
+                let start = parameter.offset as usize + i * 4;
+                bytes[start..start + 4].copy_from_slice(&component_bytes);
+            }
+        }
+        bytes
+    }
+}
+
+/// Checks whether the code declares `struct {name}`.
+pub(crate) fn declares_struct(code: &str, name: &str) -> bool {
+    declares(code, "struct", name)
+}
+
+/// Checks whether the code declares `fn {name}`.
+pub(crate) fn declares_fn(code: &str, name: &str) -> bool {
+    declares(code, "fn", name)
+}
+
+fn declares(code: &str, keyword: &str, name: &str) -> bool {
+    let code = strip_comments(code);
+    let words: Vec<_> = code
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| !word.is_empty())
+        .collect();
+    words.windows(2).any(|pair| pair == [keyword, name])
+}
+
+/// Replaces line comments and (nested) block comments with spaces.
+fn strip_comments(code: &str) -> String {
+    let mut result = String::with_capacity(code.len());
+    let mut chars = code.chars().peekable();
+    let mut block_depth = 0;
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('/', Some('*')) => {
+                chars.next();
+                block_depth += 1;
+                result.push(' ');
+            }
+            ('*', Some('/')) if block_depth > 0 => {
+                chars.next();
+                block_depth -= 1;
+                result.push(' ');
+            }
+            ('/', Some('/')) if block_depth == 0 => {
+                while chars.next_if(|&c| c != '\n').is_some() {}
+                result.push(' ');
+            }
+            (c, _) if block_depth == 0 => result.push(c),
+            _ => {}
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "
+struct Parameters {
+  radius: f32,
+  offset: vec3f,
+  count: u32,
+}
+@group(0) @binding(0) var<uniform> parameters: Parameters;
+";
+
+    #[test]
+    fn reflect_parameters() {
+        let parameters = ShaderParameters::reflect(SOURCE).unwrap();
+        assert_eq!(parameters.size, 32);
+        assert_eq!(
+            parameters.parameters,
+            vec![
+                ShaderParameter {
+                    name: "radius".into(),
+                    kind: ParameterKind::F32,
+                    components: 1,
+                    offset: 0,
+                },
+                ShaderParameter {
+                    name: "offset".into(),
+                    kind: ParameterKind::F32,
+                    components: 3,
+                    offset: 16,
+                },
+                ShaderParameter {
+                    name: "count".into(),
+                    kind: ParameterKind::U32,
+                    components: 1,
+                    offset: 28,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn template_placeholder_has_no_parameters() {
+        let user_code = "fn sampleObject(input: vec2f) -> vec3f { return vec3f(input, 0.0); }";
+        let source = format!(
+            "struct Parameters {{ _unused: vec4f }}
+@group(0) @binding(0) var<uniform> parameters: Parameters;
+{user_code}"
+        );
+        assert_eq!(
+            ShaderParameters::reflect_user_code(user_code, &source).unwrap(),
+            ShaderParameters::default()
+        );
+        let source = format!("{SOURCE}\n{user_code}");
+        let parameters = ShaderParameters::reflect_user_code(SOURCE, &source).unwrap();
+        assert_eq!(parameters.parameters.len(), 3);
+    }
+
+    #[test]
+    fn parameters_to_bytes() {
+        let parameters = ShaderParameters::reflect(SOURCE).unwrap();
+        let values = HashMap::from([
+            ("radius".to_string(), Vec4::new(2.0, 0.0, 0.0, 0.0)),
+            ("count".to_string(), Vec4::new(3.0, 0.0, 0.0, 0.0)),
+        ]);
+        let bytes = parameters.to_bytes(&values);
+        assert_eq!(bytes.len(), 32);
+        assert_eq!(bytes[0..4], 2.0f32.to_le_bytes());
+        assert_eq!(bytes[16..28], [0u8; 12]);
+        assert_eq!(bytes[28..32], 3u32.to_le_bytes());
+    }
+
+    #[test]
+    fn finds_declarations() {
+        assert!(declares_struct(SOURCE, "Parameters"));
+        assert!(declares_struct("struct Parameters{ a: f32 }", "Parameters"));
+        assert!(!declares_struct(
+            "struct ParametersExtra { a: f32 }",
+            "Parameters"
+        ));
+        assert!(!declares_struct("fn sampleObject() {}", "Parameters"));
+        assert!(declares_fn(
+            "fn sampleMaterial(uv: vec2f) -> MaterialOutput {}",
+            "sampleMaterial"
+        ));
+        assert!(!declares_fn("fn sampleObject() {}", "sampleMaterial"));
+        assert!(!declares_fn("fn mysampleMaterial() {}", "sampleMaterial"));
+    }
+
+    #[test]
+    fn skips_commented_declarations() {
+        assert!(!declares_fn(
+            "// fn sampleMaterial(uv: vec2f) -> MaterialOutput {}",
+            "sampleMaterial"
+        ));
+        assert!(!declares_struct(
+            "/* struct Parameters { /* nested */ a: f32 } */",
+            "Parameters"
+        ));
+        assert!(declares_struct(
+            "// The parameters\nstruct /* uniform */ Parameters { a: f32 }",
+            "Parameters"
+        ));
+    }
+}
@@ -1,4 +1,7 @@
 use glam::UVec2;
+use wgpu::util::DeviceExt;
+
+use crate::game::TextureInfo;
 
 pub struct Texture {
     pub texture: wgpu::Texture,
@@ -8,8 +11,15 @@ pub struct Texture {
 
 impl Texture {
     pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+    /// The scene is rendered in linear HDR, and tone mapped afterwards.
+    pub const HDR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
 
-    pub fn create_depth_texture(device: &wgpu::Device, size: UVec2, label: &str) -> Self {
+    pub fn create_depth_texture(
+        device: &wgpu::Device,
+        size: UVec2,
+        sample_count: u32,
+        label: &str,
+    ) -> Self {
         let size = wgpu::Extent3d {
             width: size.x.max(1),
             height: size.y.max(1),
@@ -19,10 +29,13 @@ impl Texture {
             label: Some(label),
             size,
             mip_level_count: 1,
-            sample_count: 1,
+            sample_count,
             dimension: wgpu::TextureDimension::D2,
             format: Self::DEPTH_FORMAT,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            // Copying lets the renderer read back the depth under the cursor
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC,
             view_formats: &[],
         };
         let texture = device.create_texture(&desc);
@@ -47,4 +60,167 @@ impl Texture {
             sampler,
         }
     }
+
+    /// A color texture that can be rendered to, and then sampled by a post processing pass.
+    /// Multisampled textures can only be resolved.
+    pub fn create_render_target(
+        device: &wgpu::Device,
+        size: UVec2,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        label: &str,
+    ) -> Self {
+        let usage = if sample_count > 1 {
+            wgpu::TextureUsages::RENDER_ATTACHMENT
+        } else {
+            wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING
+        };
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width: size.x.max(1),
+                height: size.y.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage,
+            view_formats: &[],
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        Self {
+            texture,
+            view,
+            sampler,
+        }
+    }
+
+    /// Uploads a texture that user shaders can sample.
+    /// Uses 32 bit floats when the device can filter them. Otherwise, 16 bit normalized integers keep the precision
+    /// of 16 bit PNGs, like geometry images. HDR images and devices without them fall back to 16 bit floats.
+    pub fn from_info(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        info: &TextureInfo,
+        label: &str,
+    ) -> Self {
+        let features = device.features();
+        let is_normalized = info.data.iter().all(|v| (0.0..=1.0).contains(v));
+        let (format, data): (_, Vec<u8>) = if features.contains(wgpu::Features::FLOAT32_FILTERABLE)
+        {
+            (
+                wgpu::TextureFormat::Rgba32Float,
+                bytemuck::cast_slice(&info.data).to_vec(),
+            )
+        } else if is_normalized && features.contains(wgpu::Features::TEXTURE_FORMAT_16BIT_NORM) {
+            let normalized = info
+                .data
+                .iter()
+                .map(|v| (v * u16::MAX as f32).round() as u16)
+                .collect::<Vec<_>>();
+            (
+                wgpu::TextureFormat::Rgba16Unorm,
+                bytemuck::cast_slice(&normalized).to_vec(),
+            )
+        } else {
+            let half_floats = info
+                .data
+                .iter()
+                .map(|v| half::f16::from_f32(*v).to_bits())
+                .collect::<Vec<_>>();
+            (
+                wgpu::TextureFormat::Rgba16Float,
+                bytemuck::cast_slice(&half_floats).to_vec(),
+            )
+        };
+        let texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some(label),
+                size: wgpu::Extent3d {
+                    width: info.width,
+                    height: info.height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            &data,
+        );
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        // Clamping, since geometry images must not wrap around at their edges
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        Self {
+            texture,
+            view,
+            sampler,
+        }
+    }
+}
+
+impl TextureInfo {
+    /// Decodes a PNG or HDR image.
+    pub fn decode(bytes: &[u8]) -> anyhow::Result<Self> {
+        Ok(Self::from_image(image::load_from_memory(bytes)?))
+    }
+
+    /// Loads a PNG or HDR file.
+    pub fn load(path: impl AsRef<std::path::Path>) -> anyhow::Result<Self> {
+        Ok(Self::from_image(image::open(path)?))
+    }
+
+    /// A single white pixel, for models without a texture.
+    pub fn white() -> Self {
+        Self {
+            width: 1,
+            height: 1,
+            data: vec![1.0; 4],
+        }
+    }
+
+    /// A single pixel that points straight up, for models without a normal texture.
+    pub fn flat_normal() -> Self {
+        Self {
+            width: 1,
+            height: 1,
+            data: vec![0.5, 0.5, 1.0, 1.0],
+        }
+    }
+
+    fn from_image(image: image::DynamicImage) -> Self {
+        let image = image.to_rgba32f();
+        Self {
+            width: image.width(),
+            height: image.height(),
+            data: image.into_raw(),
+        }
+    }
 }
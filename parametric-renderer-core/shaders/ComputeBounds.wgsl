@@ -0,0 +1,227 @@
+//// START sampleObject
+fn sampleObject(input: vec2f) -> vec3f {
+  let a = time;
+  let b = screen;
+  let c = mouse;
+  return vec3(input, 0.0); 
+}
+//// END sampleObject
+//// START Parameters
+// Replaced when the user code declares its own `struct Parameters`
+struct Parameters {
+  _unused: vec4f,
+}
+//// END Parameters
+//// START sampleMaterial
+// Replaced when the user code declares its own `fn sampleMaterial`
+fn sampleMaterial(uv: vec2f, world_pos: vec3f, normal: vec3f) -> MaterialOutput {
+  let albedo = textureSampleLevel(albedo_texture, material_sampler, uv, 0.0).rgb;
+  // Like glTF, the roughness is stored in the green channel
+  let roughness = textureSampleLevel(roughness_texture, material_sampler, uv, 0.0).g;
+  return MaterialOutput(
+    material.color_roughness.rgb * srgb_to_linear(albedo),
+    material.color_roughness.a * roughness,
+    material.emissive_metallic.rgb,
+    material.emissive_metallic.a,
+  );
+}
+//// END sampleMaterial
+var<private> instance_id: u32;
+
+////#include "./Common.wgsl"
+//// AUTOGEN 6de14edf9918265eb2e1232f93b94c84430d0069898214379635e51c3d4c9550
+struct EncodedPatch {
+  u: u32,
+  v: u32,
+  instance: u32
+};
+struct Patch {
+  min: vec2<f32>,
+  max: vec2<f32>,
+  instance: u32
+};
+struct Patches {
+  patches_length: atomic<u32>,
+  patches_capacity: u32,
+  patches : array<EncodedPatch>,
+};
+struct PatchesRead { // Is currently needed, see https://github.com/gpuweb/gpuweb/discussions/4438
+  patches_length: u32, // Same size and alignment as atomic<u32>. Should be legal, right?
+  patches_capacity: u32,
+  patches : array<EncodedPatch>,
+};
+struct RenderBuffer {
+  patches_length: atomic<u32>,
+  patches_capacity: u32,
+  patches: array<EncodedPatch>,
+};
+struct RenderBufferRead {
+  patches_length: u32,
+  patches_capacity: u32,
+  patches: array<EncodedPatch>,
+};
+struct DispatchIndirectArgs { // From https://docs.rs/wgpu/latest/wgpu/util/struct.DispatchIndirectArgs.html
+  x: atomic<u32>,
+  y: u32,
+  z: u32,
+};
+fn ceil_div(a: u32, b: u32) -> u32 { return (a + b - 1u) / b; }
+// Inspired from https://onrendering.com/data/papers/isubd/isubd.pdf
+fn patch_u_child(u: u32, child_bit: u32) -> u32 {
+  return (u << 1) | (child_bit & 1);
+}
+fn patch_top_child(encoded: EncodedPatch) -> EncodedPatch {
+  return EncodedPatch(encoded.u, patch_u_child(encoded.v, 0u), encoded.instance);
+}
+fn patch_bottom_child(encoded: EncodedPatch) -> EncodedPatch {
+  return EncodedPatch(encoded.u, patch_u_child(encoded.v, 1u), encoded.instance);
+}
+fn patch_left_child(encoded: EncodedPatch) -> EncodedPatch {
+  return EncodedPatch(patch_u_child(encoded.u, 0u), encoded.v, encoded.instance);
+}
+fn patch_right_child(encoded: EncodedPatch) -> EncodedPatch {
+  return EncodedPatch(patch_u_child(encoded.u, 1u), encoded.v, encoded.instance);
+}
+fn patch_top_left_child(encoded: EncodedPatch) -> EncodedPatch {
+  return patch_top_child(patch_left_child(encoded));
+}
+fn patch_top_right_child(encoded: EncodedPatch) -> EncodedPatch {
+  return patch_top_child(patch_right_child(encoded));
+}
+fn patch_bottom_left_child(encoded: EncodedPatch) -> EncodedPatch {
+  return patch_bottom_child(patch_left_child(encoded));
+}
+fn patch_bottom_right_child(encoded: EncodedPatch) -> EncodedPatch {
+  return patch_bottom_child(patch_right_child(encoded));
+}
+fn patch_decode(encoded: EncodedPatch) -> Patch {
+  // First we go to the implicit 1u
+  let leading_zeroes_u = countLeadingZeros(encoded.u);
+  let u_bits = extractBits(encoded.u, 0u, 31u - leading_zeroes_u);
+  let u_max_bits = u_bits + 1u; // The end position of the patch
+  let leading_zeroes_v = countLeadingZeros(encoded.v);
+  let v_bits = extractBits(encoded.v, 0u, 31u - leading_zeroes_v);
+  let v_max_bits = v_bits + 1u;
+
+  // And every bit after that describes if we go left or right
+  // Conveniently, this is already what binary numbers do.
+  // 0b0.1 == 0.5
+  // 0b0.01 == 0.25
+  // 0b0.11 == 0.75
+  // And that directly corresponds to how floats work: mantissa * 2^exponent
+  // So we can just convert the bits to a float
+  // let u = f32(u_bits) * pow(2.0, -1.0 * f32(31 - leading_zeroes_u));
+  // And that's equivalent to the size of a patch, see formula below
+  let min_value = vec2f(
+    f32(u_bits) / f32(1u << (31u - leading_zeroes_u)),
+    f32(v_bits) / f32(1u << (31u - leading_zeroes_v))
+  );
+  let max_value = vec2f(
+    f32(u_max_bits) / f32(1u << (31u - leading_zeroes_u)),
+    f32(v_max_bits) / f32(1u << (31u - leading_zeroes_v))
+  );
+  
+  // The size of the patch is 1 / 2^(31 - leading_zeroes)
+  // let u_size = 1.0 / f32(2 << (31 - leading_zeroes_u));
+  // let v_size = 1.0 / f32(2 << (31 - leading_zeroes_v));
+  // But we care about this_patch.max == next_patch.min, 
+  // so we need to do the floating point calculations more carefully
+  
+  return Patch(min_value, max_value, encoded.instance);
+}
+
+fn assert(condition: bool) {
+  // TODO: Implement this
+}
+//// END OF AUTOGEN
+
+////#include "./EvaluateImage.wgsl"
+//// AUTOGEN 961c21364e8c69d94e53b62808c484d25210f06c0cbf6045824e429da62423c4
+struct Time {
+  elapsed: f32,
+  delta: f32,
+  frame: u32,
+};
+struct Screen {
+  resolution: vec2<u32>,
+  inv_resolution: vec2<f32>,
+};
+struct Mouse {
+  pos: vec2<f32>,
+  buttons: u32,
+};
+fn mouse_held(button: u32) -> bool {
+  return (mouse.buttons & button) != 0u;
+}
+// Group 0 is for constants that change once per frame at most
+@group(0) @binding(0) var<uniform> time : Time;
+@group(0) @binding(1) var<uniform> screen : Screen;
+@group(0) @binding(2) var<uniform> mouse : Mouse;
+
+//// END OF AUTOGEN
+
+// sampleMaterial is never called here, but it must also compile
+struct MaterialOutput {
+    color: vec3f,
+    roughness: f32,
+    emissive: vec3f,
+    metallic: f32,
+}
+
+struct Material {
+    color_roughness: vec4<f32>,
+    emissive_metallic: vec4<f32>,
+    opacity: f32,
+}
+var<private> material: Material;
+
+fn srgb_to_linear(color: vec3f) -> vec3f {
+    let low = color / 12.92;
+    let high = pow((color + 0.055) / 1.055, vec3f(2.4));
+    return select(high, low, color <= vec3f(0.04045));
+}
+
+// The floats are stored as ordered integers, since atomics only work on integers
+struct Bounds {
+  min: array<atomic<u32>, 3>,
+  max: array<atomic<u32>, 3>,
+};
+
+// Group 1 is for things that change once per model
+@group(1) @binding(0) var<storage, read_write> bounds: Bounds;
+@group(1) @binding(1) var<uniform> parameters: Parameters;
+@group(1) @binding(2) var user_texture: texture_2d<f32>;
+@group(1) @binding(3) var user_sampler: sampler;
+@group(1) @binding(4) var albedo_texture: texture_2d<f32>;
+@group(1) @binding(5) var roughness_texture: texture_2d<f32>;
+@group(1) @binding(6) var material_sampler: sampler;
+
+// Flips the bits of negative numbers, and the sign bit of positive numbers.
+// Then larger floats are also larger integers.
+fn float_to_ordered(value: f32) -> u32 {
+  let bits = bitcast<u32>(value);
+  return select(bits | 0x80000000u, ~bits, (bits & 0x80000000u) != 0u);
+}
+
+const SAMPLES_PER_WORKGROUP = 8u;
+
+// Dispatched with (instance_count, 4, 4), which evaluates a 32x32 grid per instance
+@compute @workgroup_size(SAMPLES_PER_WORKGROUP, SAMPLES_PER_WORKGROUP, 1)
+fn main(@builtin(workgroup_id) workgroup_id: vec3<u32>,
+        @builtin(local_invocation_id) local_invocation_id: vec3<u32>,
+        @builtin(num_workgroups) num_workgroups: vec3<u32>) {
+  instance_id = workgroup_id.x;
+  let sample_index = workgroup_id.yz * SAMPLES_PER_WORKGROUP + local_invocation_id.xy;
+  let last_sample_index = num_workgroups.yz * SAMPLES_PER_WORKGROUP - 1u;
+  let position = sampleObject(vec2f(sample_index) / vec2f(last_sample_index));
+
+  // Infinities and NaNs would make the bounds useless
+  if (any(abs(position) > vec3f(1e30)) || any(position != position)) {
+    return;
+  }
+  for (var i = 0u; i < 3u; i += 1u) {
+    let value = float_to_ordered(position[i]);
+    atomicMin(&bounds.min[i], value);
+    atomicMax(&bounds.max[i], value);
+  }
+}
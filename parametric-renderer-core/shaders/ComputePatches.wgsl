@@ -6,6 +6,26 @@ fn sampleObject(input: vec2f) -> vec3f {
   return vec3(input, 0.0); 
 }
 //// END sampleObject
+//// START Parameters
+// Replaced when the user code declares its own `struct Parameters`
+struct Parameters {
+  _unused: vec4f,
+}
+//// END Parameters
+//// START sampleMaterial
+// Replaced when the user code declares its own `fn sampleMaterial`
+fn sampleMaterial(uv: vec2f, world_pos: vec3f, normal: vec3f) -> MaterialOutput {
+  let albedo = textureSampleLevel(albedo_texture, material_sampler, uv, 0.0).rgb;
+  // Like glTF, the roughness is stored in the green channel
+  let roughness = textureSampleLevel(roughness_texture, material_sampler, uv, 0.0).g;
+  return MaterialOutput(
+    material.color_roughness.rgb * srgb_to_linear(albedo),
+    material.color_roughness.a * roughness,
+    material.emissive_metallic.rgb,
+    material.emissive_metallic.a,
+  );
+}
+//// END sampleMaterial
 var<private> instance_id: u32;
 
 ////#include "./Common.wgsl"
@@ -149,6 +169,31 @@ struct ForceRenderFlag {
   flag: u32 // if flag == 0 { false } else { true }
 }
 
+// sampleMaterial is only called when rendering, but it must also compile here
+struct MaterialOutput {
+    color: vec3f,
+    roughness: f32,
+    emissive: vec3f,
+    metallic: f32,
+}
+
+struct Material {
+    // color.rgb is the color of the material
+    // color.a is the roughness of the material
+    color_roughness: vec4<f32>,
+    // emissive_metallic.rgb is the emissive color of the material
+    // emissive_metallic.a is the metallicness of the material
+    emissive_metallic: vec4<f32>,
+    opacity: f32,
+}
+var<private> material: Material;
+
+fn srgb_to_linear(color: vec3f) -> vec3f {
+    let low = color / 12.92;
+    let high = pow((color + 0.055) / 1.055, vec3f(2.4));
+    return select(high, low, color <= vec3f(0.04045));
+}
+
 // Group 1 is for things that change once per model
 // TODO: Read back the patches_length on the CPU to know when we're going out of bounds
 // (And how far out of bounds)
@@ -158,6 +203,12 @@ struct ForceRenderFlag {
 @group(1) @binding(3) var<storage, read_write> render_buffer_8 : RenderBuffer;
 @group(1) @binding(4) var<storage, read_write> render_buffer_16 : RenderBuffer;
 @group(1) @binding(5) var<storage, read_write> render_buffer_32 : RenderBuffer;
+@group(1) @binding(6) var<uniform> parameters: Parameters;
+@group(1) @binding(7) var user_texture: texture_2d<f32>;
+@group(1) @binding(8) var user_sampler: sampler;
+@group(1) @binding(9) var albedo_texture: texture_2d<f32>;
+@group(1) @binding(10) var roughness_texture: texture_2d<f32>;
+@group(1) @binding(11) var material_sampler: sampler;
 // Group 2 is for things that change multiple times per model
 @group(2) @binding(0) var<storage, read_write> dispatch_next : DispatchIndirectArgs;
 @group(2) @binding(1) var<storage, read> patches_from_buffer : PatchesRead;
@@ -428,6 +479,7 @@ fn main(@builtin(workgroup_id) workgroup_id : vec3<u32>,
   );
   let u_sample = sampleObject(u_sample_location);
   let u_clip_space = input_buffer.model_view_projection * vec4f(u_sample.xyz, 1.0);
+  // w is 1 for orthographic projections, so the lengths only depend on the zoom
   let u_screen_space = u_clip_space.xy / u_clip_space.w;
   u_samples[u_v_sample_index.y][u_v_sample_index.x] = u_screen_space;
 
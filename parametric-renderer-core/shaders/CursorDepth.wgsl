@@ -0,0 +1,20 @@
+// Reads the depth under the cursor and in the middle of the screen from a multisampled depth texture, since those cannot be copied
+@binding(0) @group(0) var depth_texture: texture_depth_multisampled_2d;
+
+struct Probes {
+    cursor: vec2u,
+    center: vec2u,
+}
+@binding(1) @group(0) var<uniform> probes: Probes;
+
+struct ProbeDepths {
+    cursor: f32,
+    center: f32,
+}
+@binding(2) @group(0) var<storage, read_write> depths: ProbeDepths;
+
+@compute @workgroup_size(1, 1, 1)
+fn main() {
+    depths.cursor = textureLoad(depth_texture, probes.cursor, 0);
+    depths.center = textureLoad(depth_texture, probes.center, 0);
+}
@@ -0,0 +1,20 @@
+// Copies the first sample of a multisampled depth texture, since depth textures cannot be resolved
+@binding(0) @group(0) var depth_texture: texture_depth_multisampled_2d;
+
+struct VertexOutput {
+    @builtin(position) pos: vec4f,
+}
+
+// A single triangle that covers the whole screen
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let uv = vec2f(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    var output: VertexOutput;
+    output.pos = vec4f(uv * vec2f(2.0, -2.0) + vec2f(-1.0, 1.0), 0.0, 1.0);
+    return output;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @builtin(frag_depth) f32 {
+    return textureLoad(depth_texture, vec2u(in.pos.xy), 0);
+}
@@ -0,0 +1,80 @@
+// A simple FXAA pass, based on https://github.com/mattdesl/glsl-fxaa
+@binding(0) @group(0) var input_texture: texture_2d<f32>;
+@binding(1) @group(0) var input_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) pos: vec4f,
+    @location(0) uv: vec2f
+}
+
+// A single triangle that covers the whole screen
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let uv = vec2f(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    var output: VertexOutput;
+    output.pos = vec4f(uv * vec2f(2.0, -2.0) + vec2f(-1.0, 1.0), 0.0, 1.0);
+    output.uv = uv;
+    return output;
+}
+
+const FXAA_REDUCE_MIN: f32 = 1.0 / 128.0;
+const FXAA_REDUCE_MUL: f32 = 1.0 / 8.0;
+const FXAA_SPAN_MAX: f32 = 8.0;
+
+fn sample_color(uv: vec2f) -> vec3f {
+    return textureSampleLevel(input_texture, input_sampler, uv, 0.0).rgb;
+}
+
+fn linear_to_srgb(color: vec3f) -> vec3f {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3f(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3f(0.0031308));
+}
+
+// The input texture is sRGB, so the samples are in linear space.
+// The edges are found with the luma of the sRGB encoded colors, which is what FXAA expects, while the colors are blended in linear space.
+fn luma(color: vec3f) -> f32 {
+    return dot(linear_to_srgb(saturate(color)), vec3f(0.299, 0.587, 0.114));
+}
+
+@fragment
+fn fs_main(
+    @location(0) uv: vec2f,
+) -> @location(0) vec4f {
+    let inv_resolution = 1.0 / vec2f(textureDimensions(input_texture));
+    let rgb_nw = sample_color(uv + vec2f(-1.0, -1.0) * inv_resolution);
+    let rgb_ne = sample_color(uv + vec2f(1.0, -1.0) * inv_resolution);
+    let rgb_sw = sample_color(uv + vec2f(-1.0, 1.0) * inv_resolution);
+    let rgb_se = sample_color(uv + vec2f(1.0, 1.0) * inv_resolution);
+    let rgb_m = sample_color(uv);
+
+    let luma_nw = luma(rgb_nw);
+    let luma_ne = luma(rgb_ne);
+    let luma_sw = luma(rgb_sw);
+    let luma_se = luma(rgb_se);
+    let luma_m = luma(rgb_m);
+    let luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    let luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    var dir = vec2f(
+        -((luma_nw + luma_ne) - (luma_sw + luma_se)),
+        ((luma_nw + luma_sw) - (luma_ne + luma_se))
+    );
+    let dir_reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * (0.25 * FXAA_REDUCE_MUL), FXAA_REDUCE_MIN);
+    let rcp_dir_min = 1.0 / (min(abs(dir.x), abs(dir.y)) + dir_reduce);
+    dir = clamp(dir * rcp_dir_min, vec2f(-FXAA_SPAN_MAX), vec2f(FXAA_SPAN_MAX)) * inv_resolution;
+
+    let rgb_a = 0.5 * (
+        sample_color(uv + dir * (1.0 / 3.0 - 0.5)) +
+        sample_color(uv + dir * (2.0 / 3.0 - 0.5))
+    );
+    let rgb_b = rgb_a * 0.5 + 0.25 * (
+        sample_color(uv + dir * -0.5) +
+        sample_color(uv + dir * 0.5)
+    );
+    let luma_b = luma(rgb_b);
+    if (luma_b < luma_min || luma_b > luma_max) {
+        return vec4f(rgb_a, 1.0);
+    }
+    return vec4f(rgb_b, 1.0);
+}
@@ -0,0 +1,14 @@
+// Renders a geometry image that mesh2gim generated.
+// The texture stores positions normalized to the bounding box of the mesh.
+struct Parameters {
+  bounds_min: vec3f,
+  bounds_max: vec3f,
+}
+
+fn sampleObject(input: vec2f) -> vec3f {
+  // The outermost texel centers lie on the edges of the parameter space
+  let size = vec2f(textureDimensions(user_texture));
+  let uv = (input * (size - 1.0) + 0.5) / size;
+  let normalized = textureSampleLevel(user_texture, user_sampler, uv, 0.0).xyz;
+  return mix(parameters.bounds_min, parameters.bounds_max, normalized);
+}
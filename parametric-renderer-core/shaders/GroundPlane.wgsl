@@ -1,7 +1,16 @@
 struct Uniforms {
     model_matrix: mat4x4f,
     view_projection_matrix: mat4x4f,
-    grid_scale: f32
+    /// Distance between two minor lines
+    grid_spacing: f32,
+    /// Every nth line is a major line, 0 means that there are none
+    major_lines: u32,
+    /// The grid fades out towards this distance from the origin
+    fade_distance: f32,
+    /// 0 draws the grid, 1 only draws the world axes
+    mode: u32,
+    /// Whether the X axis is red and the Z axis is blue
+    axis_colors: u32,
 }
 @binding(0) @group(0) var<uniform> uniforms: Uniforms;
 
@@ -24,18 +33,44 @@ fn vs_main(
     return output;
 }
 
+// 1 on a line, and 0 when the pixel is further than `width` pixels away. Per axis.
+fn line_coverage(distance: vec2f, coord: vec2f, width: f32) -> vec2f {
+    return 1.0 - min(distance / fwidth(coord) / width, vec2f(1.0));
+}
+
+fn grid_coverage(coord: vec2f, width: f32) -> f32 {
+    let coverage = line_coverage(abs(fract(coord - 0.5) - 0.5), coord, width);
+    return max(coverage.x, coverage.y);
+}
+
 @fragment
 fn fs_main(
     @location(0) uv: vec2f,
     @builtin(position) pos: vec4f
 ) -> @location(0) vec4f {
-    let coord = uv.xy * uniforms.grid_scale * vec2f(0.5);
-    let grid = abs(fract(coord - 0.5) - 0.5) / fwidth(coord) * 2.0;
-    let line = min(grid.x, grid.y);
-
-    let color = 1.0 - min(line, 1.0);
-    let center = vec2f(0.5);
-    let dist = length(coord - center) / 10.0;
-    let fade_factor = 1.0 - dist;
-    return vec4f(vec3f(1.0), color * fade_factor);
-}
\ No newline at end of file
+    // The world axes are the lines where the other coordinate is zero
+    let axes = line_coverage(abs(uv.yx), uv.yx, 1.5);
+    let x_axis = axes.x;
+    let z_axis = axes.y;
+
+    var alpha = max(x_axis, z_axis);
+    if (uniforms.mode == 0u) {
+        let coord = uv / uniforms.grid_spacing;
+        var minor_alpha = 1.0;
+        if (uniforms.major_lines > 0u) {
+            minor_alpha = 0.5;
+            let major_coord = coord / f32(uniforms.major_lines);
+            alpha = max(alpha, grid_coverage(major_coord, 1.0));
+        }
+        alpha = max(alpha, grid_coverage(coord, 0.5) * minor_alpha);
+    }
+
+    var color = vec3f(1.0);
+    if (uniforms.axis_colors != 0u) {
+        color = mix(color, vec3f(0.9, 0.15, 0.15), x_axis);
+        color = mix(color, vec3f(0.15, 0.3, 0.9), z_axis);
+    }
+
+    let fade_factor = 1.0 - min(length(uv) / uniforms.fade_distance, 1.0);
+    return vec4f(color, alpha * fade_factor);
+}
@@ -6,6 +6,26 @@ fn sampleObject(input: vec2f) -> vec3f {
   return vec3(input, 0.0); 
 }
 //// END sampleObject
+//// START Parameters
+// Replaced when the user code declares its own `struct Parameters`
+struct Parameters {
+  _unused: vec4f,
+}
+//// END Parameters
+//// START sampleMaterial
+// Replaced when the user code declares its own `fn sampleMaterial`
+fn sampleMaterial(uv: vec2f, world_pos: vec3f, normal: vec3f) -> MaterialOutput {
+  let albedo = textureSampleLevel(albedo_texture, material_sampler, uv, 0.0).rgb;
+  // Like glTF, the roughness is stored in the green channel
+  let roughness = textureSampleLevel(roughness_texture, material_sampler, uv, 0.0).g;
+  return MaterialOutput(
+    material.color_roughness.rgb * srgb_to_linear(albedo),
+    material.color_roughness.a * roughness,
+    material.emissive_metallic.rgb,
+    material.emissive_metallic.a,
+  );
+}
+//// END sampleMaterial
 var<private> instance_id: u32;
 
 ////#include "./Common.wgsl"
@@ -183,6 +203,14 @@ struct Material {
     // emissive_metallic.rgb is the emissive color of the material
     // emissive_metallic.a is the metallicness of the material
     emissive_metallic: vec4<f32>,
+    opacity: f32,
+}
+
+struct MaterialOutput {
+    color: vec3f,
+    roughness: f32,
+    emissive: vec3f,
+    metallic: f32,
 }
 
 @group(0) @binding(3) var<uniform> camera: Camera;
@@ -190,6 +218,13 @@ struct Material {
 @group(1) @binding(1) var<uniform> model: Model;
 @group(1) @binding(2) var<storage, read> render_buffer: RenderBufferRead;
 @group(1) @binding(3) var<uniform> material: Material;
+@group(1) @binding(4) var<uniform> parameters: Parameters;
+@group(1) @binding(5) var user_texture: texture_2d<f32>;
+@group(1) @binding(6) var user_sampler: sampler;
+@group(1) @binding(7) var albedo_texture: texture_2d<f32>;
+@group(1) @binding(8) var roughness_texture: texture_2d<f32>;
+@group(1) @binding(9) var normal_texture: texture_2d<f32>;
+@group(1) @binding(10) var material_sampler: sampler;
 
 
 
@@ -513,6 +548,32 @@ struct VertexOutput {
     @location(1) world_position: vec3<f32>,
     @location(2) texture_coords: vec2<f32>,
     @location(3) color: vec4<f32>,
+    @location(4) @interpolate(flat) instance: u32,
+}
+
+fn srgb_to_linear(color: vec3f) -> vec3f {
+    let low = color / 12.92;
+    let high = pow((color + 0.055) / 1.055, vec3f(2.4));
+    return select(high, low, color <= vec3f(0.04045));
+}
+
+// Builds a tangent frame from screen space derivatives, see http://www.thetenthplanet.de/archives/1180
+fn cotangent_frame(normal: vec3f, position: vec3f, uv: vec2f) -> mat3x3f {
+    let dp1 = dpdxFine(position);
+    let dp2 = dpdyFine(position);
+    let duv1 = dpdxFine(uv);
+    let duv2 = dpdyFine(uv);
+    let dp2perp = cross(dp2, normal);
+    let dp1perp = cross(normal, dp1);
+    let tangent = dp2perp * duv1.x + dp1perp * duv2.x;
+    let bitangent = dp2perp * duv1.y + dp1perp * duv2.y;
+    let invmax = inverseSqrt(max(max(dot(tangent, tangent), dot(bitangent, bitangent)), 1e-20));
+    return mat3x3f(tangent * invmax, bitangent * invmax, normal);
+}
+
+fn apply_normal_texture(normal: vec3f, position: vec3f, uv: vec2f) -> vec3f {
+    let tangent_normal = textureSample(normal_texture, material_sampler, uv).xyz * 2.0 - 1.0;
+    return normalize(cotangent_frame(normal, position, uv) * tangent_normal);
 }
 
 const color_options = array<vec4f,8>(
@@ -541,7 +602,9 @@ fn vs_main(
     var out: VertexOutput;
     out.clip_position = camera.projection * camera.view * world_pos;
     out.world_position = world_pos.xyz;
-    out.texture_coords = in.uv;
+    // Surface parameter coordinates
+    out.texture_coords = quad_point;
+    out.instance = quad.instance;
     let normal = vec3<f32>(0.0, -1.0, 0.0); // TODO: We'll compute this later
     out.world_normal = (model.model_similarity * vec4<f32>(normal, 0.0)).xyz; // Only uniform scaling
 
@@ -568,21 +631,41 @@ fn vs_main(
     return out;
 }
 
+fn view_direction(world_position: vec3f) -> vec3f {
+    // Orthographic projections have parallel view rays
+    if (camera.projection[3][3] == 1.0) {
+        return normalize(vec3f(camera.view[0][2], camera.view[1][2], camera.view[2][2]));
+    }
+    return normalize(camera.world_position.xyz - world_position);
+}
+
+struct FragmentOutput {
+    @location(0) color: vec4f,
+    // Screen space ambient occlusion removes parts of it later
+    @location(1) ambient: vec4f,
+}
+
 @fragment
-fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    let v = normalize(camera.world_position.xyz - in.world_position);
+fn fs_main(in: VertexOutput) -> FragmentOutput {
+    let v = view_direction(in.world_position);
     // let n = normalize(in.world_normal);
-    let n = normalize(-cross(dpdxFine(in.world_position), dpdyFine(in.world_position)));
+    let n = apply_normal_texture(
+        normalize(-cross(dpdxFine(in.world_position), dpdyFine(in.world_position))),
+        in.world_position,
+        in.texture_coords
+    );
+    instance_id = in.instance;
+    let surface = sampleMaterial(in.texture_coords, in.world_position, n);
 
     var materialInfo = MaterialInfo(
-        material.color_roughness.rgb,
+        surface.color,
         vec3f(0.04),
         vec3f(1.0),
         vec3f(0.0),
         1.0,
         1.0
     );
-    materialInfo = getMetallicRoughnessInfo(materialInfo, material.emissive_metallic.a, material.color_roughness.a);
+    materialInfo = getMetallicRoughnessInfo(materialInfo, surface.metallic, surface.roughness);
 
     var f_diffuse = vec3f(0.0);
     var f_specular = vec3f(0.0);
@@ -619,12 +702,15 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
 
     let ambient: vec3f = lights.ambient.rgb * materialInfo.baseColor;
 
-    let color = f_diffuse * 2.0 
-        + f_specular * 2.0 
-        + ambient 
-        + material.emissive_metallic.rgb;
+    let color = f_diffuse
+        + f_specular
+        + ambient
+        + surface.emissive;
 
-    return vec4<f32>(color, 1.0);
+    return FragmentOutput(
+        vec4f(color, material.opacity),
+        vec4f(ambient, material.opacity)
+    );
     // return in.color; TODO: Why does this cause z-buffer fighting?
 }
 
@@ -0,0 +1,107 @@
+// Screen space ambient occlusion, reconstructs the view space positions and normals from the depth buffer
+@binding(0) @group(0) var depth_texture: texture_depth_2d;
+@binding(1) @group(0) var<uniform> ssao: Ssao;
+
+struct Ssao {
+    projection: mat4x4f,
+    inv_projection: mat4x4f,
+    /// In world units
+    radius: f32,
+    sample_count: u32,
+}
+
+struct VertexOutput {
+    @builtin(position) pos: vec4f,
+}
+
+// A single triangle that covers the whole screen
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let uv = vec2f(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    var output: VertexOutput;
+    output.pos = vec4f(uv * vec2f(2.0, -2.0) + vec2f(-1.0, 1.0), 0.0, 1.0);
+    return output;
+}
+
+const TWO_PI: f32 = 6.28318530718;
+const GOLDEN_ANGLE: f32 = 2.39996322973;
+
+fn load_depth(pixel: vec2i) -> f32 {
+    let size = vec2i(textureDimensions(depth_texture));
+    return textureLoad(depth_texture, clamp(pixel, vec2i(0), size - 1), 0);
+}
+
+fn view_position(pixel: vec2i, depth: f32) -> vec3f {
+    let uv = (vec2f(pixel) + 0.5) / vec2f(textureDimensions(depth_texture));
+    let ndc = vec4f(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, depth, 1.0);
+    let position = ssao.inv_projection * ndc;
+    return position.xyz / position.w;
+}
+
+// Picks the neighbour that is closer in depth, to avoid artifacts at silhouettes
+fn closest_difference(center: vec3f, pixel: vec2i, offset: vec2i) -> vec3f {
+    let a = view_position(pixel - offset, load_depth(pixel - offset));
+    let b = view_position(pixel + offset, load_depth(pixel + offset));
+    if (abs(a.z - center.z) < abs(b.z - center.z)) {
+        return center - a;
+    }
+    return b - center;
+}
+
+// From "Next Generation Post Processing in Call of Duty: Advanced Warfare"
+fn interleaved_gradient_noise(pixel: vec2f) -> f32 {
+    return fract(52.9829189 * fract(dot(pixel, vec2f(0.06711056, 0.00583715))));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4f {
+    let pixel = vec2i(in.pos.xy);
+    let depth = load_depth(pixel);
+    // Reverse Z, so the background is at 0
+    if (depth <= 0.0) {
+        return vec4f(1.0);
+    }
+    let center = view_position(pixel, depth);
+    let dx = closest_difference(center, pixel, vec2i(1, 0));
+    let dy = closest_difference(center, pixel, vec2i(0, 1));
+    // Pixels go down, while the view space goes up
+    let normal = normalize(cross(dy, dx));
+
+    var up = vec3f(0.0, 1.0, 0.0);
+    if (abs(normal.y) > 0.99) {
+        up = vec3f(1.0, 0.0, 0.0);
+    }
+    let tangent = normalize(cross(up, normal));
+    let bitangent = cross(normal, tangent);
+    let tbn = mat3x3f(tangent, bitangent, normal);
+
+    let size = vec2f(textureDimensions(depth_texture));
+    let rotation = interleaved_gradient_noise(in.pos.xy) * TWO_PI;
+    let bias = 0.01 * ssao.radius;
+    var occlusion = 0.0;
+    for (var i = 0u; i < ssao.sample_count; i += 1u) {
+        // Points in a hemisphere, with more of them close to the center
+        let t = (f32(i) + 0.5) / f32(ssao.sample_count);
+        let phi = f32(i) * GOLDEN_ANGLE + rotation;
+        let direction = vec3f(cos(phi) * sqrt(t), sin(phi) * sqrt(t), sqrt(1.0 - t));
+        let sample_position = center + tbn * direction * (ssao.radius * mix(0.1, 1.0, t * t));
+
+        let clip = ssao.projection * vec4f(sample_position, 1.0);
+        let uv = clip.xy / clip.w * vec2f(0.5, -0.5) + 0.5;
+        if (any(uv < vec2f(0.0)) || any(uv > vec2f(1.0))) {
+            continue;
+        }
+        let sample_pixel = vec2i(uv * size);
+        let sample_depth = load_depth(sample_pixel);
+        if (sample_depth <= 0.0) {
+            continue;
+        }
+        let scene_position = view_position(sample_pixel, sample_depth);
+        let range_check = smoothstep(0.0, 1.0, ssao.radius / abs(center.z - scene_position.z));
+        if (scene_position.z >= sample_position.z + bias) {
+            occlusion += range_check;
+        }
+    }
+    let ambient_occlusion = 1.0 - occlusion / f32(max(ssao.sample_count, 1u));
+    return vec4f(ambient_occlusion, 0.0, 0.0, 1.0);
+}
@@ -0,0 +1,34 @@
+// Blurs the ambient occlusion, and removes the occluded part of the ambient light from the scene
+@binding(0) @group(0) var ambient_occlusion_texture: texture_2d<f32>;
+@binding(1) @group(0) var ambient_texture: texture_2d<f32>;
+
+struct VertexOutput {
+    @builtin(position) pos: vec4f,
+}
+
+// A single triangle that covers the whole screen
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let uv = vec2f(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    var output: VertexOutput;
+    output.pos = vec4f(uv * vec2f(2.0, -2.0) + vec2f(-1.0, 1.0), 0.0, 1.0);
+    return output;
+}
+
+// Gets subtracted from the scene
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4f {
+    let pixel = vec2i(in.pos.xy);
+    let size = vec2i(textureDimensions(ambient_occlusion_texture));
+    // Smooths out the noise from the randomly rotated samples
+    var ambient_occlusion = 0.0;
+    for (var y = -2; y <= 2; y += 1) {
+        for (var x = -2; x <= 2; x += 1) {
+            let sample_pixel = clamp(pixel + vec2i(x, y), vec2i(0), size - 1);
+            ambient_occlusion += textureLoad(ambient_occlusion_texture, sample_pixel, 0).r;
+        }
+    }
+    ambient_occlusion /= 25.0;
+    let ambient = textureLoad(ambient_texture, pixel, 0).rgb;
+    return vec4f(ambient * (1.0 - ambient_occlusion), 0.0);
+}
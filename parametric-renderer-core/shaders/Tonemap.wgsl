@@ -0,0 +1,136 @@
+// Maps the HDR scene to the displayable range. The output gets sRGB encoded by the surface.
+// Also draws the background behind the scene, so that background colors are shown exactly.
+@binding(0) @group(0) var input_texture: texture_2d<f32>;
+@binding(1) @group(0) var<uniform> tone_mapping: ToneMapping;
+@binding(2) @group(0) var<uniform> background: Background;
+@binding(3) @group(0) var skybox_texture: texture_2d<f32>;
+@binding(4) @group(0) var skybox_sampler: sampler;
+
+struct ToneMapping {
+    /// In stops, so each step doubles the brightness
+    exposure: f32,
+    /// 0 is ACES, 1 is AgX, 2 is Reinhard
+    curve: u32,
+}
+
+struct Background {
+    inv_view_projection: mat4x4f,
+    /// In linear sRGB
+    top_color: vec4f,
+    bottom_color: vec4f,
+    /// 0 is a solid color, 1 is a vertical gradient, 2 is an equirectangular skybox
+    mode: u32,
+}
+
+struct VertexOutput {
+    @builtin(position) pos: vec4f,
+    @location(0) uv: vec2f,
+}
+
+// A single triangle that covers the whole screen
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let uv = vec2f(f32((vertex_index << 1u) & 2u), f32(vertex_index & 2u));
+    var output: VertexOutput;
+    output.pos = vec4f(uv * vec2f(2.0, -2.0) + vec2f(-1.0, 1.0), 0.0, 1.0);
+    output.uv = uv;
+    return output;
+}
+
+// Fitted by Krzysztof Narkowicz, https://knarkowicz.wordpress.com/2016/01/06/aces-filmic-tone-mapping-curve/
+fn aces(x: vec3f) -> vec3f {
+    return clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), vec3f(0.0), vec3f(1.0));
+}
+
+// Minimal AgX, https://iolite-engine.com/blog_posts/minimal_agx_implementation
+fn agx_contrast_approx(x: vec3f) -> vec3f {
+    let x2 = x * x;
+    let x4 = x2 * x2;
+    return 15.5 * x4 * x2
+        - 40.14 * x4 * x
+        + 31.96 * x4
+        - 6.868 * x2 * x
+        + 0.4298 * x2
+        + 0.1191 * x
+        - 0.00232;
+}
+
+fn agx(x: vec3f) -> vec3f {
+    let agx_mat = mat3x3f(
+        0.842479062253094, 0.0423282422610123, 0.0423756549057051,
+        0.0784335999999992, 0.878468636469772, 0.0784336,
+        0.0792237451477643, 0.0791661274605434, 0.879142973793104
+    );
+    let agx_mat_inv = mat3x3f(
+        1.19687900512017, -0.0528968517574562, -0.0529716355144438,
+        -0.0980208811401368, 1.15190312990417, -0.0980434501171241,
+        -0.0990297440797205, -0.0989611768448433, 1.15107367264116
+    );
+    let min_ev = -12.47393;
+    let max_ev = 4.026069;
+
+    var color = agx_mat * max(x, vec3f(1e-10));
+    color = clamp(log2(color), vec3f(min_ev), vec3f(max_ev));
+    color = (color - min_ev) / (max_ev - min_ev);
+    color = agx_contrast_approx(color);
+    color = agx_mat_inv * color;
+    // AgX outputs sRGB encoded colors, while the surface expects linear colors
+    return pow(max(color, vec3f(0.0)), vec3f(2.2));
+}
+
+fn reinhard(x: vec3f) -> vec3f {
+    return x / (1.0 + x);
+}
+
+fn tone_map(hdr_color: vec3f) -> vec3f {
+    let color = hdr_color * exp2(tone_mapping.exposure);
+    switch tone_mapping.curve {
+        case 1u: {
+            return agx(color);
+        }
+        case 2u: {
+            return reinhard(color);
+        }
+        default: {
+            return aces(color);
+        }
+    }
+}
+
+const PI: f32 = 3.14159265359;
+
+fn background_color(uv: vec2f) -> vec3f {
+    switch background.mode {
+        case 1u: {
+            return mix(background.top_color.rgb, background.bottom_color.rgb, uv.y);
+        }
+        case 2u: {
+            // Works for both perspective and orthographic cameras
+            let ndc = vec2f(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0);
+            let near = background.inv_view_projection * vec4f(ndc, 1.0, 1.0);
+            let far = background.inv_view_projection * vec4f(ndc, 0.5, 1.0);
+            let direction = normalize(far.xyz / far.w - near.xyz / near.w);
+            let skybox_uv = vec2f(
+                atan2(direction.z, direction.x) / (2.0 * PI) + 0.5,
+                acos(clamp(direction.y, -1.0, 1.0)) / PI
+            );
+            let skybox = textureSampleLevel(skybox_texture, skybox_sampler, skybox_uv, 0.0).rgb;
+            return tone_map(skybox);
+        }
+        default: {
+            return background.top_color.rgb;
+        }
+    }
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4f {
+    // Premultiplied by the coverage, since the scene gets cleared to transparent black
+    let scene = textureLoad(input_texture, vec2u(in.pos.xy), 0);
+    let backdrop = background_color(in.uv);
+    if (scene.a <= 0.0) {
+        return vec4f(backdrop, 1.0);
+    }
+    let color = tone_map(scene.rgb / scene.a);
+    return vec4f(mix(backdrop, color, saturate(scene.a)), 1.0);
+}
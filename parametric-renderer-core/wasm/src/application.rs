@@ -2,8 +2,16 @@ use glam::{Quat, Vec3};
 use log::error;
 use renderer_core::{
     application::{run_on_main, AppCommand, Application, WasmCanvas},
-    camera::camera_controller::{self, CameraController},
-    game::{ModelInfo, ShaderId, ShaderInfo},
+    camera::{
+        camera_controller::{self, CameraController, GeneralControllerSettings},
+        camera_path::{CameraPath, Easing},
+        Angle, CameraSettings,
+    },
+    game::{
+        AntiAliasingSettings, GameRes, ModelInfo, ShaderId, ShaderInfo, TextureId, TextureInfo,
+        ToneMappingSettings,
+    },
+    geometry_image::GeometryImage,
     input::WinitAppHelper,
 };
 use std::sync::Arc;
@@ -11,7 +19,13 @@ use wasm_bindgen::{prelude::wasm_bindgen, JsError, JsValue};
 use web_sys::HtmlCanvasElement;
 use winit::event_loop::{EventLoop, EventLoopProxy};
 
-use crate::wasm_abi::{WasmCompilationMessage, WasmFrameTime, WasmModelInfo, WasmShaderInfo};
+use crate::wasm_abi::{
+    WasmAmbientOcclusion, WasmBackground, WasmCamera, WasmCameraBookmark, WasmCameraBookmarks,
+    WasmCameraController, WasmCameraPath, WasmCameraSettings, WasmCompilationMessage,
+    WasmFrameTime, WasmGeometryImage, WasmGroundPlane, WasmLighting, WasmModelInfo,
+    WasmParameterValue, WasmProjection, WasmShaderInfo, WasmShaderParameters, WasmToneMappingCurve,
+    WasmViewPreset,
+};
 
 #[wasm_bindgen]
 pub struct WasmApplication {
@@ -27,7 +41,12 @@ impl WasmApplication {
         })
     }
 
-    pub fn run(&mut self, _canvas: HtmlCanvasElement) -> Result<(), JsError> {
+    /// Starts with an orbit camera that looks at the origin, unless there is an initial camera.
+    pub fn run(
+        &mut self,
+        _canvas: HtmlCanvasElement,
+        initial_camera: Option<WasmCamera>,
+    ) -> Result<(), JsError> {
         let event_loop = EventLoop::<AppCommand>::with_user_event().build()?;
         event_loop.set_control_flow(winit::event_loop::ControlFlow::Poll);
         let event_loop_proxy = event_loop.create_proxy();
@@ -37,15 +56,20 @@ impl WasmApplication {
         #[cfg(not(target_arch = "wasm32"))]
         let wasm_canvas = WasmCanvas::new();
         let mut application = Application::new(event_loop_proxy, |_| {}, wasm_canvas);
-        application.app.camera_controller = CameraController::new(
-            camera_controller::GeneralController {
-                position: Vec3::new(0.0, 0.0, 4.0),
-                orientation: Quat::IDENTITY,
-                distance_to_center: 4.0,
-            },
-            application.app.camera_controller.settings,
-            camera_controller::ChosenKind::Orbitcam,
-        );
+        match initial_camera {
+            Some(camera) => set_camera(&mut application.app, camera),
+            None => {
+                application.app.camera_controller = CameraController::new(
+                    camera_controller::GeneralController {
+                        position: Vec3::new(0.0, 0.0, 4.0),
+                        orientation: Quat::IDENTITY,
+                        distance_to_center: 4.0,
+                    },
+                    application.app.camera_controller.settings,
+                    camera_controller::ChosenKind::Orbitcam,
+                );
+            }
+        }
         #[cfg(target_arch = "wasm32")]
         {
             use winit::platform::web::EventLoopExtWebSys;
@@ -67,6 +91,12 @@ impl WasmApplication {
                 material_info: v.material_info.into(),
                 shader_id: ShaderId(v.shader_id),
                 instance_count: v.instance_count,
+                parameters: v
+                    .parameters
+                    .into_iter()
+                    .map(|parameter| (parameter.name, parameter.value.into()))
+                    .collect(),
+                texture_id: v.texture_id.map(TextureId),
             })
             .collect();
         let _ = run_on_main(self.event_loop_proxy.clone().unwrap(), |app| {
@@ -95,6 +125,19 @@ impl WasmApplication {
         .await;
     }
 
+    /// The parameters that a shader declares, once it has been compiled.
+    pub async fn get_shader_parameters(&self, id: String) -> WasmShaderParameters {
+        run_on_main(self.event_loop_proxy.clone().unwrap(), |app| {
+            let parameters = app
+                .renderer
+                .as_ref()
+                .and_then(|renderer| renderer.get_shader_parameters(&ShaderId(id)))
+                .unwrap_or_default();
+            WasmShaderParameters::from(parameters)
+        })
+        .await
+    }
+
     pub async fn remove_shader(&self, id: String) {
         let _ = run_on_main(self.event_loop_proxy.clone().unwrap(), |app| {
             let shader_id = ShaderId(id);
@@ -106,6 +149,52 @@ impl WasmApplication {
         .await;
     }
 
+    /// Uploads an encoded PNG or HDR image, which models can then use.
+    pub async fn update_texture(&self, id: String, bytes: Vec<u8>) -> Result<(), JsError> {
+        let info = TextureInfo::decode(&bytes).map_err(|e| JsError::new(&e.to_string()))?;
+        let _ = run_on_main(self.event_loop_proxy.clone().unwrap(), move |app| {
+            app.set_texture(TextureId(id), info);
+        })
+        .await;
+        Ok(())
+    }
+
+    /// Uploads the PNG and the JSON bounds that mesh2gim generated, together with the built-in shader.
+    pub async fn update_geometry_image(
+        &self,
+        id: String,
+        image: Vec<u8>,
+        bounds_json: String,
+    ) -> Result<WasmGeometryImage, JsError> {
+        let geometry_image = GeometryImage::decode(&image, &bounds_json)
+            .map_err(|e| JsError::new(&e.to_string()))?;
+        let result = WasmGeometryImage {
+            shader_id: GeometryImage::shader_id().0,
+            texture_id: id.clone(),
+            parameters: geometry_image
+                .parameters()
+                .into_iter()
+                .map(|(name, value)| WasmParameterValue {
+                    name,
+                    value: value.to_array(),
+                })
+                .collect(),
+        };
+        let _ = run_on_main(self.event_loop_proxy.clone().unwrap(), move |app| {
+            app.set_shader(GeometryImage::shader_id(), GeometryImage::shader_info());
+            app.set_texture(TextureId(id), geometry_image.texture);
+        })
+        .await;
+        Ok(result)
+    }
+
+    pub async fn remove_texture(&self, id: String) {
+        let _ = run_on_main(self.event_loop_proxy.clone().unwrap(), |app| {
+            app.remove_texture(&TextureId(id));
+        })
+        .await;
+    }
+
     pub async fn set_lod_stage(&self, stage: Option<web_sys::js_sys::Function>) {
         let wrapped = stage.map(|stage| -> Arc<dyn Fn(&ShaderId, &str) + 'static> {
             Arc::new(move |shader_id: &ShaderId, buffer_id: &str| {
@@ -154,6 +243,55 @@ impl WasmApplication {
         .await;
     }
 
+    /// Called after every frame in which the camera changed, with a `WasmCamera`.
+    /// While the camera moves, that is every frame.
+    pub async fn set_on_camera_changed(
+        &self,
+        on_camera_changed: Option<web_sys::js_sys::Function>,
+    ) {
+        let wrapped =
+            on_camera_changed.map(|on_camera_changed| -> Arc<dyn Fn(&GameRes) + 'static> {
+                Arc::new(move |game: &GameRes| {
+                    let this = wasm_bindgen::JsValue::NULL;
+                    match on_camera_changed.call1(
+                        &this,
+                        &serde_wasm_bindgen::to_value(&WasmCamera::from(game)).unwrap(),
+                    ) {
+                        Ok(_) => (),
+                        Err(e) => error!("Error calling on_camera_changed: {:?}", e),
+                    }
+                })
+            });
+        let _ = run_on_main(self.event_loop_proxy.clone().unwrap(), move |app| {
+            app.on_camera_changed = wrapped;
+        })
+        .await;
+    }
+
+    pub async fn get_camera(&self) -> WasmCamera {
+        run_on_main(self.event_loop_proxy.clone().unwrap(), move |app| {
+            WasmCamera::from(&app.app)
+        })
+        .await
+    }
+
+    /// Keeps the view. The orbit camera then rotates around the point in the middle of the screen.
+    pub async fn set_camera_kind(&self, controller: WasmCameraController) {
+        let _ = run_on_main(self.event_loop_proxy.clone().unwrap(), move |app| {
+            app.app.set_camera_kind(controller.into());
+        })
+        .await;
+    }
+
+    /// Jumps to the camera, and stops any camera animation.
+    /// The field of view gets clamped to values that work.
+    pub async fn set_camera(&self, camera: WasmCamera) {
+        let _ = run_on_main(self.event_loop_proxy.clone().unwrap(), move |app| {
+            set_camera(&mut app.app, camera);
+        })
+        .await;
+    }
+
     pub async fn get_frame_time(&self) -> WasmFrameTime {
         let frame_time = run_on_main(self.event_loop_proxy.clone().unwrap(), |app| {
             WasmFrameTime {
@@ -165,6 +303,190 @@ impl WasmApplication {
         frame_time
     }
 
+    /// Falls back to no MSAA if the sample count is not supported.
+    pub async fn set_anti_aliasing(&self, msaa_samples: u32, fxaa: bool) {
+        let _ = run_on_main(self.event_loop_proxy.clone().unwrap(), move |app| {
+            app.app.anti_aliasing = AntiAliasingSettings { msaa_samples, fxaa };
+        })
+        .await;
+    }
+
+    /// The exposure is in stops.
+    pub async fn set_tone_mapping(&self, curve: WasmToneMappingCurve, exposure: f32) {
+        let _ = run_on_main(self.event_loop_proxy.clone().unwrap(), move |app| {
+            app.app.tone_mapping = ToneMappingSettings {
+                curve: curve.into(),
+                exposure,
+            };
+        })
+        .await;
+    }
+
+    pub async fn set_ambient_occlusion(&self, quality: WasmAmbientOcclusion) {
+        let _ = run_on_main(self.event_loop_proxy.clone().unwrap(), move |app| {
+            app.app.ambient_occlusion = quality.into();
+        })
+        .await;
+    }
+
+    /// Only the first 16 point lights are used.
+    pub async fn set_lighting(&self, lighting: WasmLighting) {
+        let _ = run_on_main(self.event_loop_proxy.clone().unwrap(), move |app| {
+            app.app.lighting = lighting.into();
+        })
+        .await;
+    }
+
+    pub async fn set_background(&self, background: WasmBackground) {
+        let _ = run_on_main(self.event_loop_proxy.clone().unwrap(), move |app| {
+            app.app.background = background.into();
+        })
+        .await;
+    }
+
+    pub async fn set_ground_plane(&self, ground_plane: WasmGroundPlane) {
+        let _ = run_on_main(self.event_loop_proxy.clone().unwrap(), move |app| {
+            app.app.ground_plane = ground_plane.into();
+        })
+        .await;
+    }
+
+    pub async fn set_view_preset(&self, preset: WasmViewPreset) {
+        let _ = run_on_main(self.event_loop_proxy.clone().unwrap(), move |app| {
+            app.app.camera_controller.set_view(preset.into());
+        })
+        .await;
+    }
+
+    pub async fn set_projection(&self, projection: WasmProjection) {
+        let _ = run_on_main(self.event_loop_proxy.clone().unwrap(), move |app| {
+            app.app.camera.settings.projection = projection.into();
+        })
+        .await;
+    }
+
+    pub async fn get_camera_settings(&self) -> WasmCameraSettings {
+        run_on_main(self.event_loop_proxy.clone().unwrap(), move |app| {
+            let camera = app.app.camera_settings();
+            let controller = app.app.controller_settings();
+            WasmCameraSettings {
+                fov: camera.fov.radians,
+                z_near: camera.z_near,
+                z_far: camera.z_far,
+                fly_speed: controller.fly_speed,
+                pan_speed: controller.pan_speed,
+                rotation_sensitivity: controller.rotation_sensitivity,
+            }
+        })
+        .await
+    }
+
+    /// The field of view and the clipping planes get clamped to values that work.
+    pub async fn set_camera_settings(&self, settings: WasmCameraSettings) {
+        let _ = run_on_main(self.event_loop_proxy.clone().unwrap(), move |app| {
+            app.app.set_camera_settings(CameraSettings {
+                fov: Angle::new(settings.fov),
+                z_near: settings.z_near,
+                z_far: settings.z_far,
+                ..app.app.camera_settings().clone()
+            });
+            app.app.set_controller_settings(GeneralControllerSettings {
+                fly_speed: settings.fly_speed,
+                pan_speed: settings.pan_speed,
+                rotation_sensitivity: settings.rotation_sensitivity,
+                ..app.app.controller_settings().clone()
+            });
+        })
+        .await;
+    }
+
+    /// Clicking into the canvas stops the animation.
+    pub async fn play_camera_path(&self, path: WasmCameraPath) {
+        let _ = run_on_main(self.event_loop_proxy.clone().unwrap(), move |app| {
+            let easing = if path.ease_in_out {
+                Easing::EaseInOut
+            } else {
+                Easing::Linear
+            };
+            let keyframes = path.keyframes.into_iter().map(Into::into).collect();
+            if let Some(path) = CameraPath::new(keyframes, easing, path.looping) {
+                app.app.camera_controller.play(path);
+            }
+        })
+        .await;
+    }
+
+    /// Orbits around the point that the camera is looking at, until it gets stopped.
+    pub async fn play_turntable(&self, seconds_per_turn: f32) {
+        let _ = run_on_main(self.event_loop_proxy.clone().unwrap(), move |app| {
+            app.app.camera_controller.play_turntable(seconds_per_turn);
+        })
+        .await;
+    }
+
+    pub async fn stop_camera_animation(&self) {
+        let _ = run_on_main(self.event_loop_proxy.clone().unwrap(), move |app| {
+            app.app.camera_controller.stop();
+        })
+        .await;
+    }
+
+    /// Returns `false` when the bounds of the model are not known yet.
+    pub async fn frame_model(&self, id: String) -> bool {
+        run_on_main(self.event_loop_proxy.clone().unwrap(), move |app| {
+            app.app.frame_model(&id)
+        })
+        .await
+    }
+
+    /// Returns `false` when no model bounds are known yet.
+    pub async fn frame_all(&self) -> bool {
+        run_on_main(self.event_loop_proxy.clone().unwrap(), move |app| {
+            app.app.frame_all()
+        })
+        .await
+    }
+
+    pub async fn get_camera_bookmarks(&self) -> WasmCameraBookmarks {
+        run_on_main(self.event_loop_proxy.clone().unwrap(), move |app| {
+            WasmCameraBookmarks {
+                bookmarks: app.app.bookmarks.iter().map(Into::into).collect(),
+            }
+        })
+        .await
+    }
+
+    /// Replaces all bookmarks, for example with the ones that the page saved earlier.
+    pub async fn set_camera_bookmarks(&self, bookmarks: Vec<WasmCameraBookmark>) {
+        let _ = run_on_main(self.event_loop_proxy.clone().unwrap(), move |app| {
+            app.app.bookmarks = bookmarks.into_iter().map(Into::into).collect();
+        })
+        .await;
+    }
+
+    /// Bookmarks the current camera. Replaces a bookmark with the same name.
+    pub async fn save_camera_bookmark(&self, name: String) {
+        let _ = run_on_main(self.event_loop_proxy.clone().unwrap(), move |app| {
+            app.app.save_bookmark(name);
+        })
+        .await;
+    }
+
+    /// Returns `false` when there is no bookmark with that name.
+    pub async fn go_to_camera_bookmark(&self, name: String) -> bool {
+        run_on_main(self.event_loop_proxy.clone().unwrap(), move |app| {
+            app.app.go_to_bookmark(&name)
+        })
+        .await
+    }
+
+    pub async fn remove_camera_bookmark(&self, name: String) -> bool {
+        run_on_main(self.event_loop_proxy.clone().unwrap(), move |app| {
+            app.app.remove_bookmark(&name)
+        })
+        .await
+    }
+
     pub async fn try_set_threshold_factor(&self, factor: f32) {
         let _ = run_on_main(self.event_loop_proxy.clone().unwrap(), move |app| {
             if let Some(renderer) = &app.renderer {
@@ -174,3 +496,20 @@ impl WasmApplication {
         .await;
     }
 }
+
+fn set_camera(app: &mut GameRes, camera: WasmCamera) {
+    app.camera_controller = CameraController::new(
+        camera_controller::GeneralController {
+            position: Vec3::from(camera.position),
+            orientation: Quat::from_array(camera.orientation),
+            distance_to_center: camera.distance_to_center,
+        },
+        app.camera_controller.settings.clone(),
+        camera.controller.into(),
+    );
+    app.camera.settings.projection = camera.projection.into();
+    app.set_camera_settings(CameraSettings {
+        fov: Angle::new(camera.fov),
+        ..app.camera_settings().clone()
+    });
+}
@@ -11,6 +11,20 @@ pub struct WasmModelInfo {
     pub material_info: WasmMaterialInfo,
     pub shader_id: String,
     pub instance_count: u32,
+    #[serde(default)]
+    #[tsify(optional)]
+    pub parameters: Vec<WasmParameterValue>,
+    #[serde(default)]
+    #[tsify(optional)]
+    pub texture_id: Option<String>,
+}
+
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct WasmParameterValue {
+    pub name: String,
+    /// Scalars only use the first component.
+    pub value: [f32; 4],
 }
 
 #[derive(Tsify, Serialize, Deserialize)]
@@ -50,6 +64,23 @@ pub struct WasmMaterialInfo {
     pub emissive: [f32; 3],
     pub roughness: f32,
     pub metallic: f32,
+    #[serde(default = "default_opacity")]
+    #[tsify(optional)]
+    pub opacity: f32,
+    /// IDs of textures that were uploaded with `update_texture`
+    #[serde(default)]
+    #[tsify(optional)]
+    pub albedo_texture: Option<String>,
+    #[serde(default)]
+    #[tsify(optional)]
+    pub roughness_texture: Option<String>,
+    #[serde(default)]
+    #[tsify(optional)]
+    pub normal_texture: Option<String>,
+}
+
+fn default_opacity() -> f32 {
+    1.0
 }
 
 impl From<WasmMaterialInfo> for renderer_core::game::MaterialInfo {
@@ -59,6 +90,10 @@ impl From<WasmMaterialInfo> for renderer_core::game::MaterialInfo {
             emissive: v.emissive.into(),
             roughness: v.roughness,
             metallic: v.metallic,
+            opacity: v.opacity,
+            albedo_texture: v.albedo_texture.map(renderer_core::game::TextureId),
+            roughness_texture: v.roughness_texture.map(renderer_core::game::TextureId),
+            normal_texture: v.normal_texture.map(renderer_core::game::TextureId),
         }
     }
 }
@@ -71,6 +106,63 @@ pub struct WasmShaderInfo {
     pub code: String,
 }
 
+/// What a model needs to render an uploaded geometry image.
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct WasmGeometryImage {
+    pub shader_id: String,
+    pub texture_id: String,
+    pub parameters: Vec<WasmParameterValue>,
+}
+
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct WasmShaderParameters {
+    pub parameters: Vec<WasmShaderParameter>,
+}
+
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct WasmShaderParameter {
+    pub name: String,
+    pub kind: WasmParameterKind,
+    pub components: u32,
+}
+
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum WasmParameterKind {
+    F32,
+    I32,
+    U32,
+}
+
+impl From<renderer_core::shader_parameters::ShaderParameters> for WasmShaderParameters {
+    fn from(v: renderer_core::shader_parameters::ShaderParameters) -> Self {
+        Self {
+            parameters: v
+                .parameters
+                .into_iter()
+                .map(|parameter| WasmShaderParameter {
+                    name: parameter.name,
+                    kind: match parameter.kind {
+                        renderer_core::shader_parameters::ParameterKind::F32 => {
+                            WasmParameterKind::F32
+                        }
+                        renderer_core::shader_parameters::ParameterKind::I32 => {
+                            WasmParameterKind::I32
+                        }
+                        renderer_core::shader_parameters::ParameterKind::U32 => {
+                            WasmParameterKind::U32
+                        }
+                    },
+                    components: parameter.components,
+                })
+                .collect(),
+        }
+    }
+}
+
 #[derive(Tsify, Serialize, Deserialize)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
 pub struct WasmCompilationMessage {
@@ -131,3 +223,341 @@ impl From<wgpu::SourceLocation> for WasmSourceLocation {
         }
     }
 }
+
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum WasmToneMappingCurve {
+    Aces,
+    AgX,
+    Reinhard,
+}
+
+impl From<WasmToneMappingCurve> for renderer_core::game::ToneMappingCurve {
+    fn from(v: WasmToneMappingCurve) -> Self {
+        match v {
+            WasmToneMappingCurve::Aces => Self::Aces,
+            WasmToneMappingCurve::AgX => Self::AgX,
+            WasmToneMappingCurve::Reinhard => Self::Reinhard,
+        }
+    }
+}
+
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum WasmAmbientOcclusion {
+    Off,
+    Low,
+    High,
+}
+
+impl From<WasmAmbientOcclusion> for renderer_core::game::AmbientOcclusion {
+    fn from(v: WasmAmbientOcclusion) -> Self {
+        match v {
+            WasmAmbientOcclusion::Off => Self::Off,
+            WasmAmbientOcclusion::Low => Self::Low,
+            WasmAmbientOcclusion::High => Self::High,
+        }
+    }
+}
+
+/// Colors are in linear sRGB.
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct WasmLighting {
+    pub ambient: [f32; 3],
+    pub point_lights: Vec<WasmPointLight>,
+}
+
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct WasmPointLight {
+    pub position: [f32; 3],
+    /// Distance where the light has faded out
+    pub range: f32,
+    pub color: [f32; 3],
+    pub intensity: f32,
+}
+
+impl From<WasmLighting> for renderer_core::game::LightingSettings {
+    fn from(v: WasmLighting) -> Self {
+        Self {
+            ambient: v.ambient.into(),
+            points: v
+                .point_lights
+                .into_iter()
+                .map(|light| renderer_core::game::PointLight {
+                    position: light.position.into(),
+                    range: light.range,
+                    color: light.color.into(),
+                    intensity: light.intensity,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Colors are in linear sRGB. The skybox is an equirectangular texture.
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(tag = "type")]
+pub enum WasmBackground {
+    Solid { color: [f32; 3] },
+    Gradient { top: [f32; 3], bottom: [f32; 3] },
+    Skybox { texture_id: String },
+}
+
+impl From<WasmBackground> for renderer_core::game::Background {
+    fn from(v: WasmBackground) -> Self {
+        match v {
+            WasmBackground::Solid { color } => Self::Solid(color.into()),
+            WasmBackground::Gradient { top, bottom } => Self::Gradient {
+                top: top.into(),
+                bottom: bottom.into(),
+            },
+            WasmBackground::Skybox { texture_id } => {
+                Self::Skybox(renderer_core::game::TextureId(texture_id))
+            }
+        }
+    }
+}
+
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct WasmGroundPlane {
+    pub visible: bool,
+    /// Only draws the X and Z axes, instead of the whole grid
+    pub axes_only: bool,
+    pub height: f32,
+    pub grid_spacing: f32,
+    /// Every nth line is a major line, 0 disables them
+    pub major_lines: u32,
+    /// Colors the X axis red and the Z axis blue
+    pub axis_colors: bool,
+    pub fade_distance: f32,
+}
+
+impl From<WasmGroundPlane> for renderer_core::game::GroundPlaneSettings {
+    fn from(v: WasmGroundPlane) -> Self {
+        Self {
+            visible: v.visible,
+            mode: if v.axes_only {
+                renderer_core::game::GroundPlaneMode::Axes
+            } else {
+                renderer_core::game::GroundPlaneMode::Grid
+            },
+            height: v.height,
+            grid_spacing: v.grid_spacing,
+            major_lines: v.major_lines,
+            axis_colors: v.axis_colors,
+            fade_distance: v.fade_distance,
+        }
+        .clamped()
+    }
+}
+
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum WasmViewPreset {
+    Front,
+    Top,
+    Side,
+    Isometric,
+}
+
+impl From<WasmViewPreset> for renderer_core::camera::camera_controller::ViewPreset {
+    fn from(v: WasmViewPreset) -> Self {
+        match v {
+            WasmViewPreset::Front => Self::Front,
+            WasmViewPreset::Top => Self::Top,
+            WasmViewPreset::Side => Self::Side,
+            WasmViewPreset::Isometric => Self::Isometric,
+        }
+    }
+}
+
+#[derive(Tsify, Serialize, Deserialize, Clone, Copy)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum WasmProjection {
+    Perspective,
+    Orthographic,
+}
+
+impl From<WasmProjection> for renderer_core::camera::Projection {
+    fn from(v: WasmProjection) -> Self {
+        match v {
+            WasmProjection::Perspective => Self::Perspective,
+            WasmProjection::Orthographic => Self::Orthographic,
+        }
+    }
+}
+
+impl From<renderer_core::camera::Projection> for WasmProjection {
+    fn from(v: renderer_core::camera::Projection) -> Self {
+        match v {
+            renderer_core::camera::Projection::Perspective => Self::Perspective,
+            renderer_core::camera::Projection::Orthographic => Self::Orthographic,
+        }
+    }
+}
+
+/// Orbits around the center. Angles are in radians.
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct WasmCameraKeyframe {
+    /// In seconds since the start of the path
+    pub time: f32,
+    pub center: [f32; 3],
+    /// Going from 0 to 2 PI makes a full turn
+    pub yaw: f32,
+    pub pitch: f32,
+    #[serde(default)]
+    #[tsify(optional)]
+    pub roll: f32,
+    pub distance: f32,
+}
+
+impl From<WasmCameraKeyframe> for renderer_core::camera::camera_path::CameraKeyframe {
+    fn from(v: WasmCameraKeyframe) -> Self {
+        Self {
+            time: v.time,
+            center: v.center.into(),
+            yaw: renderer_core::camera::Angle::new(v.yaw),
+            pitch: renderer_core::camera::Angle::new(v.pitch),
+            roll: renderer_core::camera::Angle::new(v.roll),
+            distance: v.distance,
+        }
+    }
+}
+
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct WasmCameraPath {
+    pub keyframes: Vec<WasmCameraKeyframe>,
+    /// Starts and stops smoothly, instead of moving at a constant speed
+    pub ease_in_out: bool,
+    pub looping: bool,
+}
+
+/// A named viewpoint. Angles are in radians.
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct WasmCameraBookmark {
+    pub name: String,
+    pub position: [f32; 3],
+    /// A quaternion
+    pub orientation: [f32; 4],
+    pub distance_to_center: f32,
+    pub controller: WasmCameraController,
+    pub fov: f32,
+    /// The number key that flies to it, from 1 to 9
+    #[serde(default)]
+    #[tsify(optional)]
+    pub slot: Option<u8>,
+}
+
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum WasmCameraController {
+    Orbitcam,
+    Freecam,
+}
+
+impl From<WasmCameraController> for renderer_core::camera::camera_controller::ChosenKind {
+    fn from(v: WasmCameraController) -> Self {
+        match v {
+            WasmCameraController::Orbitcam => Self::Orbitcam,
+            WasmCameraController::Freecam => Self::Freecam,
+        }
+    }
+}
+
+impl From<renderer_core::camera::camera_controller::ChosenKind> for WasmCameraController {
+    fn from(v: renderer_core::camera::camera_controller::ChosenKind) -> Self {
+        match v {
+            renderer_core::camera::camera_controller::ChosenKind::Orbitcam => Self::Orbitcam,
+            renderer_core::camera::camera_controller::ChosenKind::Freecam => Self::Freecam,
+        }
+    }
+}
+
+/// Where the camera is. The orbit camera looks at a point that is `distance_to_center` in front of it.
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct WasmCamera {
+    pub position: [f32; 3],
+    /// A quaternion
+    pub orientation: [f32; 4],
+    pub distance_to_center: f32,
+    pub controller: WasmCameraController,
+    pub projection: WasmProjection,
+    /// Vertical, in radians
+    pub fov: f32,
+}
+
+impl From<&renderer_core::game::GameRes> for WasmCamera {
+    fn from(v: &renderer_core::game::GameRes) -> Self {
+        use renderer_core::camera::camera_controller::IsCameraController;
+        let controller = v.camera_controller.general_controller();
+        Self {
+            position: controller.position.to_array(),
+            orientation: controller.orientation.to_array(),
+            distance_to_center: controller.distance_to_center,
+            controller: v.camera_controller.get_chosen_kind().into(),
+            projection: v.camera.settings.projection.into(),
+            fov: v.camera.settings.fov.radians,
+        }
+    }
+}
+
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct WasmCameraBookmarks {
+    pub bookmarks: Vec<WasmCameraBookmark>,
+}
+
+impl From<WasmCameraBookmark> for renderer_core::camera::CameraBookmark {
+    fn from(v: WasmCameraBookmark) -> Self {
+        Self {
+            name: v.name,
+            camera: renderer_core::camera::camera_controller::GeneralController {
+                position: v.position.into(),
+                orientation: glam::Quat::from_array(v.orientation),
+                distance_to_center: v.distance_to_center,
+            },
+            kind: v.controller.into(),
+            fov: renderer_core::camera::Angle::new(v.fov),
+            slot: v.slot,
+        }
+    }
+}
+
+impl From<&renderer_core::camera::CameraBookmark> for WasmCameraBookmark {
+    fn from(v: &renderer_core::camera::CameraBookmark) -> Self {
+        Self {
+            name: v.name.clone(),
+            position: v.camera.position.to_array(),
+            orientation: v.camera.orientation.to_array(),
+            distance_to_center: v.camera.distance_to_center,
+            controller: v.kind.into(),
+            fov: v.fov.radians,
+            slot: v.slot,
+        }
+    }
+}
+
+/// The field of view is vertical and in radians.
+#[derive(Tsify, Serialize, Deserialize)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct WasmCameraSettings {
+    pub fov: f32,
+    pub z_near: f32,
+    /// Leave it out to see infinitely far
+    #[serde(default)]
+    #[tsify(optional)]
+    pub z_far: Option<f32>,
+    /// Freecam: Units per second
+    pub fly_speed: f32,
+    pub pan_speed: f32,
+    /// Radians per pixel that the mouse moves
+    pub rotation_sensitivity: f32,
+}
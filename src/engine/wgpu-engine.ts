@@ -4,15 +4,31 @@ import init, {
   type WasmModelInfo,
   type WasmShaderInfo,
   type WasmCompilationMessage,
+  type WasmToneMappingCurve,
+  type WasmAmbientOcclusion,
+  type WasmLighting,
+  type WasmBackground,
+  type WasmGroundPlane,
+  type WasmViewPreset,
+  type WasmProjection,
+  type WasmCameraPath,
+  type WasmCameraBookmark,
+  type WasmCameraSettings,
+  type WasmCamera,
+  type WasmCameraController,
 } from "../../parametric-renderer-core/pkg";
 
 await init();
 
 export class WgpuEngine {
   private constructor(private engine: WasmApplication) {}
-  static createEngine(canvasElement: HTMLCanvasElement) {
+  /** Starts with an orbit camera that looks at the origin, unless there is an initial camera */
+  static createEngine(
+    canvasElement: HTMLCanvasElement,
+    initialCamera?: WasmCamera
+  ) {
     const engine = new WasmApplication();
-    engine.run(canvasElement);
+    engine.run(canvasElement, initialCamera);
     return new WgpuEngine(engine);
   }
   async updateModels(js_models: WasmModelInfo[]) {
@@ -24,11 +40,103 @@ export class WgpuEngine {
   async removeShader(id: string) {
     await this.engine.remove_shader(id);
   }
+  async getShaderParameters(id: string) {
+    return await this.engine.get_shader_parameters(id);
+  }
+  /** Uploads an encoded PNG or HDR image */
+  async updateTexture(id: string, bytes: Uint8Array) {
+    await this.engine.update_texture(id, bytes);
+  }
+  /** Uploads a geometry image and its JSON bounds, as generated by mesh2gim */
+  async updateGeometryImage(id: string, image: Uint8Array, boundsJson: string) {
+    return await this.engine.update_geometry_image(id, image, boundsJson);
+  }
+  async removeTexture(id: string) {
+    await this.engine.remove_texture(id);
+  }
+  /** msaaSamples is usually 1 or 4 */
+  async setAntiAliasing(msaaSamples: number, fxaa: boolean) {
+    await this.engine.set_anti_aliasing(msaaSamples, fxaa);
+  }
+  /** exposure is in stops */
+  async setToneMapping(curve: WasmToneMappingCurve, exposure: number) {
+    await this.engine.set_tone_mapping(curve, exposure);
+  }
+  async setAmbientOcclusion(quality: WasmAmbientOcclusion) {
+    await this.engine.set_ambient_occlusion(quality);
+  }
+  /** Colors are in linear sRGB, and only the first 16 point lights are used */
+  async setLighting(lighting: WasmLighting) {
+    await this.engine.set_lighting(lighting);
+  }
+  /** A skybox needs its texture to be uploaded with updateTexture */
+  async setBackground(background: WasmBackground) {
+    await this.engine.set_background(background);
+  }
+  async setGroundPlane(groundPlane: WasmGroundPlane) {
+    await this.engine.set_ground_plane(groundPlane);
+  }
+  /** Rotates the camera around the point that it is looking at */
+  async setViewPreset(preset: WasmViewPreset) {
+    await this.engine.set_view_preset(preset);
+  }
+  async setProjection(projection: WasmProjection) {
+    await this.engine.set_projection(projection);
+  }
+  async getCameraSettings(): Promise<WasmCameraSettings> {
+    return await this.engine.get_camera_settings();
+  }
+  async setCameraSettings(settings: WasmCameraSettings) {
+    await this.engine.set_camera_settings(settings);
+  }
+  async playCameraPath(path: WasmCameraPath) {
+    await this.engine.play_camera_path(path);
+  }
+  async playTurntable(secondsPerTurn: number) {
+    await this.engine.play_turntable(secondsPerTurn);
+  }
+  async stopCameraAnimation() {
+    await this.engine.stop_camera_animation();
+  }
+  /** Moves the camera so that the model fills the view. Returns false when its bounds are not known yet. */
+  async frameModel(id: string): Promise<boolean> {
+    return await this.engine.frame_model(id);
+  }
+  async frameAll(): Promise<boolean> {
+    return await this.engine.frame_all();
+  }
+  async getCameraBookmarks(): Promise<WasmCameraBookmark[]> {
+    return (await this.engine.get_camera_bookmarks()).bookmarks;
+  }
+  async setCameraBookmarks(bookmarks: WasmCameraBookmark[]) {
+    await this.engine.set_camera_bookmarks(bookmarks);
+  }
+  async saveCameraBookmark(name: string) {
+    await this.engine.save_camera_bookmark(name);
+  }
+  async goToCameraBookmark(name: string): Promise<boolean> {
+    return await this.engine.go_to_camera_bookmark(name);
+  }
+  async removeCameraBookmark(name: string): Promise<boolean> {
+    return await this.engine.remove_camera_bookmark(name);
+  }
   setOnShaderCompiled(
     callback: (shaderId: string, messages: WasmCompilationMessage[]) => void
   ) {
     setTimeout(() => this.engine.set_on_shader_compiled(callback), 0);
   }
+  setOnCameraChanged(callback: null | ((camera: WasmCamera) => void)) {
+    setTimeout(() => this.engine.set_on_camera_changed(callback), 0);
+  }
+  async getCamera(): Promise<WasmCamera> {
+    return await this.engine.get_camera();
+  }
+  async setCamera(camera: WasmCamera) {
+    await this.engine.set_camera(camera);
+  }
+  async setCameraKind(controller: WasmCameraController) {
+    await this.engine.set_camera_kind(controller);
+  }
   setLodStage(
     callback:
       | null
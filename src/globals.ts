@@ -6,6 +6,8 @@ export const sceneFilesPromise = ReactiveFilesystem.create(
 const canvasElement = document.createElement("canvas");
 canvasElement.style.width = "100%";
 canvasElement.style.height = "100%";
+// Touch gestures control the camera, instead of scrolling or zooming the page
+canvasElement.style.touchAction = "none";
 canvasElement.addEventListener(
   "wheel",
   (e) => {